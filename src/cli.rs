@@ -0,0 +1,671 @@
+use std::{env, path::PathBuf, time::Duration};
+
+use chrono::format::{Item, StrftimeItems};
+use reqwest::header::{HeaderName, HeaderValue};
+
+use crate::{
+    config::{self, FileConfig},
+    eta,
+    events::EventTarget,
+    export::ExportFormat,
+    headless::OutputFormat,
+    keymap::KeyMap,
+    notes,
+    notify::NotifyOn,
+    state,
+    template::{RowTemplate, TimeColumn},
+    theme::Theme,
+};
+
+const DEFAULT_DOWNLOADER_URL: &str = "http://localhost:8080";
+const DEFAULT_REFRESH_SECS: u64 = 3;
+const DEFAULT_EXPECTED_DURATION_SECS: u64 = 60 * 60;
+const DEFAULT_ACTION_RETRIES: u32 = 3;
+const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_ACTION_COOLDOWN_SECS: u64 = 2;
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+const DEFAULT_AUTO_RESTART_GRACE_SECS: u64 = 60;
+const DEFAULT_AUTO_RESTART_ATTEMPTS: u32 = 3;
+const DEFAULT_SHOW_RESUME_TIMEOUT_SECS: u64 = 30 * 60;
+const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DEFAULT_COLUMN_WIDTH: u16 = 80;
+
+pub struct Args {
+    pub downloader_url: String,
+    // Named servers from repeated --server flags; the first is targeted at startup.
+    pub servers: Vec<(String, String)>,
+    pub refresh_interval: Duration,
+    // How old the list may get before it's flagged as stale; three refresh intervals if unset.
+    pub stale_after: Option<Duration>,
+    // Baseline run time for a download; anything running longer is shown as overdue.
+    pub expected_duration: Duration,
+    // How many times a failed stop/pause/restart is retried before giving up.
+    pub action_retries: u32,
+    // How long further actions for a model are ignored after one is sent to it.
+    pub action_cooldown: Duration,
+    // How many requests a batch action may have in flight at once.
+    pub max_concurrency: usize,
+    pub http_timeout: Duration,
+    // Sent as `Authorization: Bearer <token>` on every request.
+    pub auth_token: Option<String>,
+    // Extra headers sent on every request, from repeated --header flags.
+    pub headers: Vec<(String, String)>,
+    // A PEM root certificate to trust in addition to the system's, for servers with a private
+    // or self-signed CA.
+    pub ca_cert: Option<PathBuf>,
+    // Skips TLS certificate verification entirely. Anyone on the network path can then
+    // impersonate the server, so this is only meant for testing.
+    pub insecure: bool,
+    // Whether to try the server's event stream before falling back to polling.
+    pub use_sse: bool,
+    // Whether stop/restart/pause on an active download asks for confirmation first.
+    pub confirm_actions: bool,
+    // Whether quitting while downloads are active asks for confirmation first.
+    pub confirm_quit: bool,
+    pub export_format: ExportFormat,
+    // Where exported snapshots of the download list are written.
+    pub export_dir: PathBuf,
+    // Where notes attached to downloads are kept; without one they only last the session.
+    pub notes_file: Option<PathBuf>,
+    // Where completion times are kept for estimating how long downloads will take.
+    pub completions_file: Option<PathBuf>,
+    // Where the sort and filters are saved on quit, to be restored at the next launch.
+    pub state_file: Option<PathBuf>,
+    // Where tracing output goes, filtered by RUST_LOG; nothing is logged to a file without it.
+    pub log_file: Option<PathBuf>,
+    // Run a single command and exit instead of starting the TUI.
+    pub command: Option<Command>,
+    pub output_format: OutputFormat,
+    // Restart downloads that stay errored for longer than the grace period.
+    pub auto_restart: bool,
+    pub auto_restart_grace: Duration,
+    // Automatic restarts allowed per model before the watchdog gives up on it.
+    pub auto_restart_attempts: u32,
+    // Resume downloads paused for a show once they've waited longer than the timeout.
+    pub auto_resume_shows: bool,
+    pub show_resume_timeout: Duration,
+    // Remove downloads that have been completed for longer than this.
+    pub auto_prune_completed: Option<Duration>,
+    // Only log what auto-pruning would remove.
+    pub prune_dry_run: bool,
+    pub keymap: KeyMap,
+    // Log add/delete/control requests instead of sending them, simulating their effect.
+    pub dry_run: bool,
+    // Raise desktop notifications when a download moves into one of `notify_on`.
+    pub notify: bool,
+    pub notify_on: Vec<NotifyOn>,
+    // Where a JSON line is written for each status transition, if anywhere.
+    pub emit_events: Option<EventTarget>,
+    pub theme: Theme,
+    // Start in the dense display without borders, for small panes.
+    pub compact: bool,
+    // Moving past the last row selects the first, and past the first the last.
+    pub wrap_navigation: bool,
+    // Width of each list column on wide terminals; 0 keeps a single column.
+    pub column_width: u16,
+    // Groups downloads whose model names share the text before this separator.
+    pub group_separator: Option<String>,
+    // Layout for list rows from the config; the built-in layout is used when unset.
+    pub row_template: Option<RowTemplate>,
+    // Which durations the built-in row layout shows, in order.
+    pub time_columns: Vec<TimeColumn>,
+    // strftime format for times shown as local clock times rather than durations.
+    pub timestamp_format: String,
+}
+
+// One-shot commands given as `downloaderctl [URL] <command> [argument]`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Status,
+    Stop(String),
+    Pause(String),
+    Restart(String),
+    Add(String),
+    // Adds every URL listed in a file, or stdin when the path is `-`.
+    AddBatch(String),
+}
+
+impl Command {
+    // Returns `None` if `name` isn't a command, so it can be treated as the server URL.
+    fn parse(name: &str, args: &[String]) -> Option<Result<Command, String>> {
+        let argument = |what: &str| match args {
+            [value] => Ok(value.clone()),
+            [] => Err(format!("`{}` requires {}", name, what)),
+            _ => Err(format!("Unexpected argument: {}", args[1])),
+        };
+
+        let command = match name {
+            "status" if args.is_empty() => Ok(Command::Status),
+            "status" => Err(format!("Unexpected argument: {}", args[0])),
+            "stop" => argument("a model name").map(Command::Stop),
+            "pause" => argument("a model name").map(Command::Pause),
+            "restart" => argument("a model name").map(Command::Restart),
+            "add" => argument("a URL").map(Command::Add),
+            "add-batch" => argument("a file of URLs, or - for stdin").map(Command::AddBatch),
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            downloader_url: DEFAULT_DOWNLOADER_URL.to_string(),
+            servers: Vec::new(),
+            refresh_interval: Duration::from_secs(DEFAULT_REFRESH_SECS),
+            stale_after: None,
+            expected_duration: Duration::from_secs(DEFAULT_EXPECTED_DURATION_SECS),
+            action_retries: DEFAULT_ACTION_RETRIES,
+            action_cooldown: Duration::from_secs(DEFAULT_ACTION_COOLDOWN_SECS),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            http_timeout: Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS),
+            auth_token: None,
+            headers: Vec::new(),
+            ca_cert: None,
+            insecure: false,
+            use_sse: true,
+            confirm_actions: true,
+            confirm_quit: false,
+            export_format: ExportFormat::Json,
+            export_dir: PathBuf::from("."),
+            notes_file: None,
+            completions_file: None,
+            state_file: None,
+            log_file: None,
+            command: None,
+            output_format: OutputFormat::Table,
+            auto_restart: false,
+            auto_restart_grace: Duration::from_secs(DEFAULT_AUTO_RESTART_GRACE_SECS),
+            auto_restart_attempts: DEFAULT_AUTO_RESTART_ATTEMPTS,
+            auto_resume_shows: false,
+            show_resume_timeout: Duration::from_secs(DEFAULT_SHOW_RESUME_TIMEOUT_SECS),
+            auto_prune_completed: None,
+            prune_dry_run: false,
+            keymap: KeyMap::default(),
+            dry_run: false,
+            notify: false,
+            notify_on: NotifyOn::ALL.to_vec(),
+            emit_events: None,
+            theme: Theme::default(),
+            compact: false,
+            wrap_navigation: false,
+            column_width: DEFAULT_COLUMN_WIDTH,
+            group_separator: None,
+            row_template: None,
+            time_columns: TimeColumn::ALL.to_vec(),
+            timestamp_format: DEFAULT_TIMESTAMP_FORMAT.to_string(),
+        }
+    }
+}
+
+impl Args {
+    // Reads options from the command line, falling back to environment variables, then the
+    // config file, then built-in defaults.
+    pub fn parse() -> Result<Args, String> {
+        let cli_args: Vec<String> = env::args().skip(1).collect();
+        let mut parsed = Args {
+            notes_file: notes::default_path(),
+            completions_file: eta::default_path(),
+            state_file: state::default_path(),
+            ..Args::default()
+        };
+
+        // The config file is applied first so that everything else can override it. Only the
+        // default location is created on first run; an explicit --config must already exist.
+        match config_flag(&cli_args)? {
+            Some(path) if !path.exists() => {
+                return Err(format!("Config file not found: {}", path.display()));
+            }
+            Some(path) => parsed.apply_file_config(config::load(&path, false)?)?,
+            None => {
+                if let Some(path) = config::default_path() {
+                    parsed.apply_file_config(config::load(&path, true)?)?;
+                }
+            }
+        }
+
+        if let Ok(url) = env::var("DOWNLOADER_URL") {
+            parsed.downloader_url = url;
+        }
+        if let Ok(secs) = env::var("DOWNLOADER_REFRESH_SECS") {
+            parsed.refresh_interval = parse_refresh_interval(&secs)?;
+        }
+        if let Ok(token) = env::var("DOWNLOADER_TOKEN") {
+            parsed.auth_token = Some(token);
+        }
+
+        let mut positional = Vec::new();
+        let mut args = cli_args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "--config" => {
+                    // Already loaded above.
+                    flag_value(&flag, inline_value, &mut args)?;
+                }
+                "--refresh-interval" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.refresh_interval = parse_refresh_interval(&value)?;
+                }
+                "--stale-after" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.stale_after = Some(parse_duration(&value)?);
+                }
+                "--expected-duration" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.expected_duration = parse_duration(&value)?;
+                }
+                "--http-timeout" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.http_timeout = parse_duration(&value)?;
+                }
+                "--auth-token" => {
+                    parsed.auth_token = Some(flag_value(&flag, inline_value, &mut args)?);
+                }
+                "--header" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.headers.push(parse_header(&value)?);
+                }
+                "--server" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.servers.push(parse_server(&value)?);
+                }
+                "--no-sse" => parsed.use_sse = false,
+                "--confirm-actions" => parsed.confirm_actions = true,
+                "--no-confirm-actions" => parsed.confirm_actions = false,
+                "--confirm-quit" => parsed.confirm_quit = true,
+                "--notify" => parsed.notify = true,
+                "--compact" => parsed.compact = true,
+                "--theme" => {
+                    parsed.theme = Theme::named(&flag_value(&flag, inline_value, &mut args)?)?;
+                }
+                "--group-by" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.group_separator = Some(parse_group_separator(&value)?);
+                }
+                "--emit-events" => {
+                    let target = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.emit_events = Some(EventTarget::parse(&target));
+                }
+                "--export-format" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.export_format = ExportFormat::parse(&value)?;
+                }
+                "--export-dir" => {
+                    parsed.export_dir = PathBuf::from(flag_value(&flag, inline_value, &mut args)?);
+                }
+                "--notes-file" => {
+                    let path = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.notes_file = Some(PathBuf::from(path));
+                }
+                "--log-file" => {
+                    let path = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.log_file = Some(PathBuf::from(path));
+                }
+                "--once" => parsed.command = Some(Command::Status),
+                "--format" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.output_format = OutputFormat::parse(&value)?;
+                }
+                "--auto-restart" => parsed.auto_restart = true,
+                "--auto-restart-grace" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.auto_restart_grace = parse_duration(&value)?;
+                }
+                "--auto-restart-attempts" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.auto_restart_attempts = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid attempt count '{}'", value))?;
+                }
+                "--auto-resume-shows" => parsed.auto_resume_shows = true,
+                "--show-resume-timeout" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.show_resume_timeout = parse_duration(&value)?;
+                }
+                "--auto-prune-completed" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.auto_prune_completed = Some(parse_minutes(&value)?);
+                }
+                "--prune-dry-run" => parsed.prune_dry_run = true,
+                "--dry-run" => parsed.dry_run = true,
+                "--ca-cert" => {
+                    let path = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.ca_cert = Some(PathBuf::from(path));
+                }
+                "--insecure" => parsed.insecure = true,
+                "--action-retries" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.action_retries = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid retry count '{}'", value))?;
+                }
+                "--max-concurrency" => {
+                    let value = flag_value(&flag, inline_value, &mut args)?;
+                    parsed.max_concurrency = parse_concurrency(&value)?;
+                }
+                f if f.starts_with('-') && f != "-" => {
+                    return Err(format!("Unknown option: {}", f))
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        // The server URL may come before the command, e.g. `downloaderctl http://host stop a`.
+        let mut positional = positional.as_slice();
+        if let Some((first, rest)) = positional.split_first() {
+            if Command::parse(first, rest).is_none() {
+                if !parsed.servers.is_empty() {
+                    return Err("Give either a server URL or --server flags, not both".to_string());
+                }
+                parsed.downloader_url = first.clone();
+                positional = rest;
+            }
+        }
+        if let Some((_, url)) = parsed.servers.first() {
+            parsed.downloader_url = url.clone();
+        }
+        if let Some((name, rest)) = positional.split_first() {
+            match Command::parse(name, rest) {
+                Some(command) => parsed.command = Some(command?),
+                None => return Err(format!("Unknown command: {}", name)),
+            }
+        }
+        if parsed.prune_dry_run && parsed.auto_prune_completed.is_none() {
+            return Err("--prune-dry-run needs --auto-prune-completed".to_string());
+        }
+        if parsed.emit_events == Some(EventTarget::Stdout) && parsed.command.is_none() {
+            return Err(
+                "--emit-events - needs a command, since the TUI uses stdout; give a file instead"
+                    .to_string(),
+            );
+        }
+
+        Ok(parsed)
+    }
+
+    fn apply_file_config(&mut self, file: FileConfig) -> Result<(), String> {
+        if let Some(url) = file.downloader_url {
+            self.downloader_url = url;
+        }
+        if let Some(secs) = file.refresh_interval {
+            self.refresh_interval = refresh_interval_from_secs(secs)?;
+        }
+        if let Some(secs) = file.stale_after {
+            if secs == 0 {
+                return Err("stale_after must be greater than zero".to_string());
+            }
+            self.stale_after = Some(Duration::from_secs(secs));
+        }
+        if let Some(secs) = file.http_timeout {
+            if secs == 0 {
+                return Err("http_timeout must be greater than zero".to_string());
+            }
+            self.http_timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = file.action_cooldown {
+            self.action_cooldown = Duration::from_secs(secs);
+        }
+        if let Some(limit) = file.max_concurrency {
+            if limit == 0 {
+                return Err("max_concurrency must be greater than zero".to_string());
+            }
+            self.max_concurrency = limit;
+        }
+        if let Some(name) = file.theme {
+            self.theme = Theme::named(&name)?;
+        }
+        if let Some(wrap) = file.wrap_navigation {
+            self.wrap_navigation = wrap;
+        }
+        if let Some(width) = file.column_width {
+            self.column_width = width;
+        }
+        if let Some(separator) = file.group_separator {
+            self.group_separator = Some(parse_group_separator(&separator)?);
+        }
+        if let Some(template) = file.row_template {
+            self.row_template = Some(RowTemplate::parse(&template)?);
+        }
+        if let Some(columns) = file.time_columns {
+            self.time_columns = columns
+                .iter()
+                .map(|column| TimeColumn::parse(column))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(format) = file.timestamp_format {
+            self.timestamp_format = parse_timestamp_format(&format)?;
+        }
+        if let Some(kinds) = file.notify_on {
+            self.notify_on = kinds
+                .iter()
+                .map(|kind| NotifyOn::parse(kind))
+                .collect::<Result<_, _>>()?;
+        }
+        if !file.keys.is_empty() {
+            let overrides = file
+                .keys
+                .iter()
+                .map(|(action, binding)| (action.as_str(), binding.keys()));
+            self.keymap = KeyMap::with_overrides(overrides)?;
+        }
+        Ok(())
+    }
+}
+
+// Finds the value of --config, if given, ahead of the main argument pass.
+fn config_flag(args: &[String]) -> Result<Option<PathBuf>, String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Ok(Some(PathBuf::from(path)));
+        }
+        if arg == "--config" {
+            return iter
+                .next()
+                .map(|path| Some(PathBuf::from(path)))
+                .ok_or_else(|| "--config requires a value".to_string());
+        }
+    }
+    Ok(None)
+}
+
+fn flag_value(
+    flag: &str,
+    inline_value: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, String> {
+    inline_value
+        .or_else(|| args.next())
+        .ok_or_else(|| format!("{} requires a value", flag))
+}
+
+// Parses a "Key: Value" header, rejecting names or values that can't be sent over HTTP.
+fn parse_header(value: &str) -> Result<(String, String), String> {
+    let (name, header_value) = value
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid header '{}': expected \"Key: Value\"", value))?;
+    let (name, header_value) = (name.trim(), header_value.trim());
+
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| format!("Invalid header name '{}'", name))?;
+    HeaderValue::from_str(header_value)
+        .map_err(|_| format!("Invalid value for header '{}'", name))?;
+    Ok((name.to_string(), header_value.to_string()))
+}
+
+fn parse_concurrency(value: &str) -> Result<usize, String> {
+    match value.trim().parse() {
+        Ok(limit) if limit > 0 => Ok(limit),
+        _ => Err(format!(
+            "Invalid concurrency limit '{}': expected a number above zero",
+            value
+        )),
+    }
+}
+
+fn parse_group_separator(value: &str) -> Result<String, String> {
+    if value.is_empty() {
+        return Err("The group separator can't be empty".to_string());
+    }
+    Ok(value.to_string())
+}
+
+// Rejects formats with specifiers chrono doesn't know, which would otherwise fail at draw time.
+fn parse_timestamp_format(value: &str) -> Result<String, String> {
+    let invalid = StrftimeItems::new(value).any(|item| matches!(item, Item::Error));
+    if invalid || value.trim().is_empty() {
+        return Err(format!("Invalid timestamp_format '{}'", value));
+    }
+    Ok(value.to_string())
+}
+
+// Parses a "name=url" server definition.
+fn parse_server(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, url)) if !name.trim().is_empty() && !url.trim().is_empty() => {
+            Ok((name.trim().to_string(), url.trim().to_string()))
+        }
+        _ => Err(format!("Invalid server '{}': expected name=url", value)),
+    }
+}
+
+fn parse_refresh_interval(value: &str) -> Result<Duration, String> {
+    let secs: u64 = value.trim().parse().map_err(|_| {
+        format!(
+            "Invalid refresh interval '{}': expected whole seconds",
+            value
+        )
+    })?;
+    refresh_interval_from_secs(secs)
+}
+
+fn refresh_interval_from_secs(secs: u64) -> Result<Duration, String> {
+    if secs < 1 {
+        return Err("Refresh interval must be at least 1 second".to_string());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+// Parses a whole number of minutes, as taken by --auto-prune-completed.
+fn parse_minutes(value: &str) -> Result<Duration, String> {
+    let secs = value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|&minutes| minutes > 0)
+        .and_then(|minutes| minutes.checked_mul(60));
+    match secs {
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => Err(format!(
+            "Invalid prune age '{}': expected whole minutes greater than zero",
+            value
+        )),
+    }
+}
+
+// Parses durations like "90", "45s", "30m" or "2h"; a bare number is taken as seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 60 * 60),
+        _ => (value, 1),
+    };
+
+    let secs: u64 = number.parse().map_err(|_| {
+        format!(
+            "Invalid duration '{}': expected a number with an optional s/m/h suffix",
+            value
+        )
+    })?;
+    if secs == 0 {
+        return Err(format!(
+            "Invalid duration '{}': must be greater than zero",
+            value
+        ));
+    }
+    match secs.checked_mul(multiplier) {
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => Err(format!("Invalid duration '{}': too long", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_their_arguments() {
+        assert_eq!(Command::parse("status", &[]), Some(Ok(Command::Status)));
+        assert_eq!(
+            Command::parse("stop", &args(&["alpha"])),
+            Some(Ok(Command::Stop("alpha".to_string())))
+        );
+        assert_eq!(
+            Command::parse("add", &args(&["http://host/model"])),
+            Some(Ok(Command::Add("http://host/model".to_string())))
+        );
+        assert!(matches!(Command::parse("pause", &[]), Some(Err(_))));
+        assert!(matches!(
+            Command::parse("restart", &args(&["a", "b"])),
+            Some(Err(_))
+        ));
+        assert_eq!(
+            Command::parse("add-batch", &args(&["-"])),
+            Some(Ok(Command::AddBatch("-".to_string())))
+        );
+        assert_eq!(Command::parse("http://localhost:8080", &[]), None);
+    }
+
+    #[test]
+    fn parses_named_servers() {
+        assert_eq!(
+            parse_server("prod = http://prod:8080"),
+            Ok(("prod".to_string(), "http://prod:8080".to_string()))
+        );
+        assert!(parse_server("http://prod:8080").is_err());
+        assert!(parse_server("=http://prod:8080").is_err());
+    }
+
+    #[test]
+    fn validates_timestamp_formats() {
+        assert_eq!(
+            parse_timestamp_format("%H:%M:%S %Z"),
+            Ok("%H:%M:%S %Z".to_string())
+        );
+        assert!(parse_timestamp_format("%Y-%Q").is_err());
+        assert!(parse_timestamp_format(" ").is_err());
+    }
+
+    #[test]
+    fn parses_prune_ages_in_minutes() {
+        assert_eq!(parse_minutes(" 90 "), Ok(Duration::from_secs(90 * 60)));
+        assert!(parse_minutes("0").is_err());
+        assert!(parse_minutes("2h").is_err());
+        assert!(parse_minutes(&u64::MAX.to_string()).is_err());
+    }
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration(" 2h "), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("99999999999999999h").is_err());
+    }
+}
@@ -0,0 +1,460 @@
+use std::{error::Error, time::Instant};
+
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use tracing::Instrument;
+
+use crate::Download;
+
+// The API version this client was written against. A server advertising a newer one may have
+// changed endpoints this client relies on.
+pub const API_VERSION: u32 = 1;
+
+// The downloader's HTTP API. It holds no UI state, so the TUI, one-shot commands and tests
+// can all drive a server through it.
+#[derive(Clone)]
+pub struct DownloaderClient {
+    base_url: String,
+    http: Client,
+}
+
+// What asking for one of the server's optional endpoints turned up.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lookup<T> {
+    // The server doesn't have the endpoint.
+    Missing,
+    Found(T),
+    Failed,
+}
+
+// Optional server features the client has code paths for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    // The `/events` stream.
+    Events,
+    Delete,
+    // Pausing, which is only safe if paused downloads can be resumed.
+    Resume,
+    Queue,
+    // Each download's log at `/downloads/{model}/logs`.
+    Logs,
+}
+
+impl Feature {
+    // How `/capabilities` names the feature.
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Events => "sse",
+            Feature::Delete => "delete",
+            Feature::Resume => "resume",
+            Feature::Queue => "queue",
+            Feature::Logs => "logs",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::Events => "streaming updates",
+            Feature::Delete => "deleting downloads",
+            Feature::Resume => "pausing and resuming downloads",
+            Feature::Queue => "a download queue",
+            Feature::Logs => "download logs",
+        }
+    }
+}
+
+// Optional settings for a new download, sent alongside its URL when set. What each one accepts
+// is up to the server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AddOptions {
+    // Place in the queue, 0 being next to start.
+    pub priority: Option<u32>,
+    // Where the server should write the download.
+    pub output_path: Option<String>,
+    pub quality: Option<String>,
+}
+
+// What a server says about itself at `/capabilities`, e.g.
+// `{"apiVersion": 1, "features": ["sse", "delete"]}`. A server that doesn't list its features
+// is assumed to support them all, as every server did before the endpoint existed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Capabilities {
+    #[serde(rename = "apiVersion")]
+    pub api_version: Option<u32>,
+    features: Option<Vec<String>>,
+}
+
+impl Capabilities {
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features.as_ref().is_none_or(|features| {
+            features
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(feature.name()))
+        })
+    }
+}
+
+// A control request that failed, and whether sending it again could succeed.
+pub struct ControlFailure {
+    pub error: Box<dyn Error>,
+    pub retriable: bool,
+}
+
+impl DownloaderClient {
+    pub fn new(base_url: String, http: Client) -> DownloaderClient {
+        DownloaderClient { base_url, http }
+    }
+
+    // The same client pointed at another server.
+    pub fn with_base_url(&self, base_url: String) -> DownloaderClient {
+        DownloaderClient::new(base_url, self.http.clone())
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn downloads_url(&self) -> String {
+        format!("{}/downloads", self.base_url)
+    }
+
+    pub fn download_url(&self, model_name: &str) -> String {
+        format!("{}/downloads/{}", self.base_url, model_name)
+    }
+
+    pub fn control_url(&self, model_name: &str, action: &str) -> String {
+        format!("{}/downloads/{}/{}", self.base_url, model_name, action)
+    }
+
+    pub async fn list_downloads(&self) -> Result<Vec<Download>, Box<dyn Error>> {
+        let response = self
+            .send(self.http.get(self.downloads_url()))
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(response_error("Failed to fetch downloads", status));
+        }
+        // The body is read as text first so it can be reported if it isn't a download list,
+        // as when a proxy answers with an HTML page or the server's format has changed.
+        let body = response.text().await?;
+        serde_json::from_str(&body).map_err(|error| {
+            Box::new(UnexpectedBody {
+                status,
+                body,
+                error,
+            }) as Box<dyn Error>
+        })
+    }
+
+    // Asks the server to download `url`, returning the model name if the server echoes the new
+    // download back.
+    pub async fn add_download(
+        &self,
+        url: &str,
+        options: &AddOptions,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let request = self
+            .http
+            .post(self.downloads_url())
+            .json(&add_body(url, options));
+        let response = self.send(request).await.map_err(request_error)?;
+
+        let status = response.status();
+        if status == StatusCode::CONFLICT {
+            return Err(format!("Already tracking a download for {}", url).into());
+        }
+        if !status.is_success() {
+            return Err(response_error("Failed to add download", status));
+        }
+
+        Ok(response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|body| body.get("modelName")?.as_str().map(str::to_string)))
+    }
+
+    // Sends `action` (stop, restart, pause...) for one model, once.
+    pub async fn control_download(
+        &self,
+        model_name: &str,
+        action: &str,
+    ) -> Result<(), ControlFailure> {
+        match self
+            .send(self.http.post(self.control_url(model_name, action)))
+            .await
+        {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let context = format!("Failed to {} download", action);
+                Err(ControlFailure {
+                    error: response_error(&context, status),
+                    retriable: status.is_server_error(),
+                })
+            }
+            Err(e) => Err(ControlFailure {
+                retriable: e.is_connect() || e.is_timeout(),
+                error: request_error(e),
+            }),
+        }
+    }
+
+    pub async fn remove_download(&self, model_name: &str) -> Result<(), Box<dyn Error>> {
+        let response = self
+            .send(self.http.delete(self.download_url(model_name)))
+            .await
+            .map_err(request_error)?;
+        if !response.status().is_success() {
+            return Err(response_error(
+                "Failed to remove download",
+                response.status(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Moves a queued download to `position` in the server's queue, 0 being next to start.
+    pub async fn set_priority(
+        &self,
+        model_name: &str,
+        position: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        let request = self
+            .http
+            .put(self.control_url(model_name, "priority"))
+            .json(&priority_body(position));
+        let response = self.send(request).await.map_err(request_error)?;
+        if !response.status().is_success() {
+            return Err(response_error(
+                "Failed to reorder the queue",
+                response.status(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Whether `/health` reports the server healthy.
+    pub async fn health(&self) -> Lookup<bool> {
+        match self
+            .send(self.http.get(format!("{}/health", self.base_url)))
+            .await
+        {
+            Ok(response) if is_missing(response.status()) => Lookup::Missing,
+            Ok(response) => Lookup::Found(response.status().is_success()),
+            Err(_) => Lookup::Failed,
+        }
+    }
+
+    // The version `/version` reports, if its response could be read as one.
+    pub async fn version(&self) -> Lookup<Option<String>> {
+        match self
+            .send(self.http.get(format!("{}/version", self.base_url)))
+            .await
+        {
+            Ok(response) if is_missing(response.status()) => Lookup::Missing,
+            Ok(response) if response.status().is_success() => match response.text().await {
+                Ok(body) => Lookup::Found(parse_version(&body)),
+                Err(_) => Lookup::Failed,
+            },
+            _ => Lookup::Failed,
+        }
+    }
+
+    // The features `/capabilities` advertises.
+    pub async fn capabilities(&self) -> Lookup<Capabilities> {
+        let request = self.http.get(format!("{}/capabilities", self.base_url));
+        match self.send(request).await {
+            Ok(response) if is_missing(response.status()) => Lookup::Missing,
+            Ok(response) if response.status().is_success() => match response.json().await {
+                Ok(capabilities) => Lookup::Found(capabilities),
+                Err(_) => Lookup::Failed,
+            },
+            _ => Lookup::Failed,
+        }
+    }
+
+    // Sends `request` inside a span naming it, recording how it went in the trace log.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        let request = request.build()?;
+        let span = tracing::debug_span!("http", method = %request.method(), url = %request.url());
+        async {
+            let started = Instant::now();
+            let result = self.http.execute(request).await;
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            match &result {
+                Ok(response) => {
+                    tracing::debug!(status = %response.status(), elapsed_ms, "response")
+                }
+                Err(e) => tracing::warn!(error = %e, elapsed_ms, "request failed"),
+            }
+            result
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+// The body of an add request, also logged by dry runs. Options left unset are left out, so
+// servers that only know about the URL see the same body as before.
+pub fn add_body(url: &str, options: &AddOptions) -> serde_json::Value {
+    let mut body = serde_json::json!({ "url": url });
+    if let Some(priority) = options.priority {
+        body["priority"] = priority.into();
+    }
+    if let Some(path) = &options.output_path {
+        body["outputPath"] = path.as_str().into();
+    }
+    if let Some(quality) = &options.quality {
+        body["quality"] = quality.as_str().into();
+    }
+    body
+}
+
+// The body of a priority request, also logged by dry runs.
+pub fn priority_body(position: u32) -> serde_json::Value {
+    serde_json::json!({ "position": position })
+}
+
+fn is_missing(status: StatusCode) -> bool {
+    status == StatusCode::NOT_FOUND || status == StatusCode::NOT_IMPLEMENTED
+}
+
+// Builds the error for a request that got no response, spelling out certificate problems since
+// those need a flag to fix rather than a retry.
+fn request_error(e: reqwest::Error) -> Box<dyn Error> {
+    if is_certificate_error(&e) {
+        format!(
+            "TLS certificate error ({}); pass --ca-cert with the server's CA certificate, \
+             or --insecure to skip verification",
+            e
+        )
+        .into()
+    } else {
+        e.into()
+    }
+}
+
+// The TLS backends only describe certificate failures in their messages, so the whole chain
+// of causes is searched.
+fn is_certificate_error(e: &(dyn Error + 'static)) -> bool {
+    let mut cause = Some(e);
+    while let Some(e) = cause {
+        let message = e.to_string().to_lowercase();
+        if message.contains("certificate") || message.contains("ssl") {
+            return true;
+        }
+        cause = e.source();
+    }
+    false
+}
+
+// Builds the error for an unsuccessful response, calling out rejected credentials specifically
+// since those otherwise look like an empty or broken server.
+fn response_error(context: &str, status: StatusCode) -> Box<dyn Error> {
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        format!("Authentication failed ({}); check the auth token", status).into()
+    } else {
+        format!("{}: {}", context, status).into()
+    }
+}
+
+// A successful response to a list request whose body couldn't be parsed. Its message quotes
+// the start of the body; the whole body is kept for the log.
+#[derive(Debug)]
+pub struct UnexpectedBody {
+    status: StatusCode,
+    pub body: String,
+    error: serde_json::Error,
+}
+
+impl std::fmt::Display for UnexpectedBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let body = self.body.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut snippet: String = body.chars().take(60).collect();
+        if snippet.len() < body.len() {
+            snippet.push('…');
+        }
+        write!(
+            f,
+            "Unexpected response from the server ({}): {}; body: {:?}",
+            self.status, self.error, snippet
+        )
+    }
+}
+
+impl Error for UnexpectedBody {}
+
+// Reads a `/version` response, which is either JSON with a `version` field, a JSON string or
+// plain text. Only the first line is kept, so a verbose page can't flood the title bar.
+fn parse_version(body: &str) -> Option<String> {
+    let version = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(fields)) => fields.get("version")?.as_str()?.to_string(),
+        Ok(serde_json::Value::String(version)) => version,
+        _ => body.to_string(),
+    };
+    let version: String = version.lines().next()?.trim().chars().take(32).collect();
+    (!version.is_empty()).then_some(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn spots_certificate_errors() {
+        let tls = io::Error::other("handshake failed: certificate verify failed");
+        assert!(is_certificate_error(&tls));
+        let refused = io::Error::new(io::ErrorKind::ConnectionRefused, "connection refused");
+        assert!(!is_certificate_error(&refused));
+    }
+
+    #[test]
+    fn reads_versions_from_json_or_text() {
+        assert_eq!(
+            parse_version(r#"{"version": "2.4.1"}"#).as_deref(),
+            Some("2.4.1")
+        );
+        assert_eq!(parse_version("\"v1.0\"").as_deref(), Some("v1.0"));
+        assert_eq!(
+            parse_version("downloader 3.2\nbuilt today").as_deref(),
+            Some("downloader 3.2")
+        );
+        assert_eq!(parse_version("  "), None);
+    }
+
+    #[test]
+    fn unlisted_features_are_assumed_supported() {
+        let listed: Capabilities =
+            serde_json::from_str(r#"{"apiVersion": 2, "features": ["SSE", "queue"]}"#).unwrap();
+        assert_eq!(listed.api_version, Some(2));
+        assert!(listed.supports(Feature::Events));
+        assert!(listed.supports(Feature::Queue));
+        assert!(!listed.supports(Feature::Delete));
+
+        let unlisted: Capabilities = serde_json::from_str(r#"{"apiVersion": 1}"#).unwrap();
+        assert!(unlisted.supports(Feature::Delete));
+        assert!(Capabilities::default().supports(Feature::Resume));
+    }
+
+    #[test]
+    fn add_bodies_leave_out_unset_options() {
+        let url = "https://host/alpha";
+        assert_eq!(
+            add_body(url, &AddOptions::default()),
+            serde_json::json!({ "url": url })
+        );
+        let options = AddOptions {
+            quality: Some("high".to_string()),
+            ..AddOptions::default()
+        };
+        assert_eq!(
+            add_body(url, &options),
+            serde_json::json!({ "url": url, "quality": "high" })
+        );
+    }
+}
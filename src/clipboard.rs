@@ -0,0 +1,26 @@
+use std::io::{self, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+// Asks the terminal to put `text` on the system clipboard with an OSC 52 escape sequence.
+// This works over SSH and needs no clipboard daemon, but terminals that don't support it
+// silently ignore the request.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52(text).as_bytes())?;
+    stdout.flush()
+}
+
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_text_as_osc52() {
+        assert_eq!(osc52("disk full"), "\x1b]52;c;ZGlzayBmdWxs\x07");
+    }
+}
@@ -0,0 +1,373 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+const DEFAULT_CONFIG: &str = r#"# downloader-ctl configuration.
+# Command-line flags and environment variables take precedence over values set here.
+
+# URL of the downloader API.
+downloader_url = "http://localhost:8080"
+
+# Seconds between background refreshes of the download list.
+refresh_interval = 3
+
+# Seconds without fresh data before the list is flagged as stale. Defaults to three refresh
+# intervals.
+# stale_after = 9
+
+# Seconds before a request to the downloader times out.
+http_timeout = 10
+
+# Seconds after an action is sent to a download during which further actions for it are
+# ignored, so a double press doesn't send two. 0 turns this off.
+action_cooldown = 2
+
+# Requests a batch action, like pausing every download, may have in flight at once.
+max_concurrency = 8
+
+# Color theme: "default", "high-contrast", or "monochrome" for terminals without color.
+theme = "default"
+
+# Groups downloads whose model names share the text before this separator, e.g. "/" puts
+# site_a/model1 and site_a/model2 under a collapsible site_a header.
+# group_separator = "/"
+
+# Layout for each download in the list. Placeholders: {model}, {status}, {elapsed} (since the
+# last status change), {age} (since the download started), {retries}, {progress}, {rate},
+# {eta} and {note}; write {{ or }} for a brace. Unset, the built-in layout is used.
+# row_template = "{model} [{status}] {elapsed} retries={retries}"
+
+# Durations the built-in layout shows: time since the last status change and time since the
+# download started. Drop one on narrow terminals.
+time_columns = ["last_change", "age"]
+
+# How times are written when the list is switched to clock times (T), in strftime syntax and
+# the local timezone.
+timestamp_format = "%Y-%m-%d %H:%M:%S"
+
+# Whether moving down from the last download selects the first, and up from the first the last.
+wrap_navigation = false
+
+# Width of a list column. Terminals at least twice this wide split the list into columns, with
+# Left and Right moving between them; 0 keeps a single column.
+column_width = 80
+
+# Statuses that raise a desktop notification when run with --notify.
+notify_on = ["error", "offline", "completed"]
+
+# Key bindings, by action. Each action takes a key or a list of keys, which replace its
+# defaults; press ? in the app to see every action and its current keys.
+# [keys]
+# down = ["Down", "Ctrl+e"]
+# up = ["Up", "Ctrl+y"]
+# quit = "q"
+"#;
+
+// Settings read from the config file. Every field is optional so a file only needs to
+// mention the values it wants to change.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub downloader_url: Option<String>,
+    pub refresh_interval: Option<u64>,
+    pub stale_after: Option<u64>,
+    pub http_timeout: Option<u64>,
+    pub action_cooldown: Option<u64>,
+    pub max_concurrency: Option<usize>,
+    // Which transitions raise a desktop notification when --notify is on.
+    pub notify_on: Option<Vec<String>>,
+    // Color preset: default, high-contrast or monochrome.
+    pub theme: Option<String>,
+    pub group_separator: Option<String>,
+    pub row_template: Option<String>,
+    pub time_columns: Option<Vec<String>>,
+    pub timestamp_format: Option<String>,
+    pub wrap_navigation: Option<bool>,
+    pub column_width: Option<u16>,
+    // `[keys]`: action name to the key or keys bound to it.
+    #[serde(default)]
+    pub keys: BTreeMap<String, KeyBinding>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            KeyBinding::One(key) => vec![key.as_str()],
+            KeyBinding::Many(keys) => keys.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+// `$XDG_CONFIG_HOME/downloader-ctl/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("downloader-ctl").join("config.toml"))
+}
+
+// Reads the config at `path`. A missing file is not an error: when `create_if_missing` is set
+// a commented default file is written in its place, and built-in defaults are used.
+pub fn load(path: &Path, create_if_missing: bool) -> Result<FileConfig, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if create_if_missing {
+                // Failing to write the template shouldn't stop the tool from starting.
+                let _ = path
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(path, DEFAULT_CONFIG));
+            }
+            return Ok(FileConfig::default());
+        }
+        Err(e) => return Err(format!("Could not read config {}: {}", path.display(), e)),
+    };
+
+    parse(&contents).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+}
+
+fn parse(contents: &str) -> Result<FileConfig, String> {
+    let table = parse_toml(contents)?;
+    serde_json::from_value(Value::Object(table)).map_err(|e| e.to_string())
+}
+
+// Parses the small subset of TOML the config needs: `key = value` pairs, `[table]` headers,
+// comments, and string, integer, boolean and single-line array values.
+fn parse_toml(contents: &str) -> Result<Map<String, Value>, String> {
+    let mut root = Map::new();
+    let mut current_table: Option<String> = None;
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line_no = index + 1;
+        let line = strip_comment(raw_line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| format!("line {}: malformed table header", line_no))?;
+            if root.contains_key(name) {
+                return Err(format!("line {}: duplicate table '{}'", line_no, name));
+            }
+            root.insert(name.to_string(), Value::Object(Map::new()));
+            current_table = Some(name.to_string());
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`", line_no))?;
+        let key = parse_key(key.trim()).map_err(|e| format!("line {}: {}", line_no, e))?;
+        let value = parse_value(value.trim()).map_err(|e| format!("line {}: {}", line_no, e))?;
+
+        let table = match &current_table {
+            Some(name) => root
+                .get_mut(name)
+                .and_then(Value::as_object_mut)
+                .expect("current table was inserted when its header was read"),
+            None => &mut root,
+        };
+        if table.insert(key.clone(), value).is_some() {
+            return Err(format!("line {}: duplicate key '{}'", line_no, key));
+        }
+    }
+
+    Ok(root)
+}
+
+// Drops a trailing `# comment`, ignoring any `#` that appears inside a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn parse_key(key: &str) -> Result<String, String> {
+    if key.starts_with('"') || key.starts_with('\'') {
+        return match parse_value(key)? {
+            Value::String(key) => Ok(key),
+            _ => Err(format!("invalid key {}", key)),
+        };
+    }
+
+    let bare = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    if key.is_empty() || !key.chars().all(bare) {
+        return Err(format!("invalid key '{}'", key));
+    }
+    Ok(key.to_string())
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    if let Some(rest) = value.strip_prefix('"') {
+        let inner = rest
+            .strip_suffix('"')
+            .ok_or_else(|| format!("unterminated string {}", value))?;
+        return unescape(inner).map(Value::String);
+    }
+    if let Some(rest) = value.strip_prefix('\'') {
+        let inner = rest
+            .strip_suffix('\'')
+            .ok_or_else(|| format!("unterminated string {}", value))?;
+        return Ok(Value::String(inner.to_string()));
+    }
+    if let Some(rest) = value.strip_prefix('[') {
+        let inner = rest
+            .strip_suffix(']')
+            .ok_or_else(|| format!("unterminated array {}", value))?;
+        return split_array(inner)
+            .into_iter()
+            .map(parse_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array);
+    }
+
+    match value {
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        _ => value
+            .replace('_', "")
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("unsupported value '{}'", value)),
+    }
+}
+
+// Splits the inside of a single-line array on commas that aren't inside strings.
+fn split_array(inner: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, ',') => {
+                items.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(inner[start..].trim());
+    items.into_iter().filter(|item| !item.is_empty()).collect()
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            other => return Err(format!("unsupported escape \\{}", other.unwrap_or(' '))),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_parses() {
+        let config = parse(DEFAULT_CONFIG).unwrap();
+        assert_eq!(
+            config.downloader_url.as_deref(),
+            Some("http://localhost:8080")
+        );
+        assert_eq!(config.refresh_interval, Some(3));
+        assert_eq!(config.http_timeout, Some(10));
+        assert_eq!(config.notify_on.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn parses_strings_comments_and_tables() {
+        let table = parse_toml(
+            r#"
+            url = "http://host:1/#not-a-comment" # trailing comment
+            quoted = "say \"hi\""
+            literal = 'C:\path'
+            count = 1_000
+            enabled = false
+            list = ["a", "b,c"]
+
+            [keys]
+            "?" = "help"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(table["url"], "http://host:1/#not-a-comment");
+        assert_eq!(table["quoted"], "say \"hi\"");
+        assert_eq!(table["literal"], "C:\\path");
+        assert_eq!(table["count"], 1000);
+        assert_eq!(table["enabled"], false);
+        assert_eq!(table["list"], serde_json::json!(["a", "b,c"]));
+        assert_eq!(table["keys"]["?"], "help");
+    }
+
+    #[test]
+    fn reports_malformed_input_with_line_numbers() {
+        assert_eq!(
+            parse_toml("a = 1\nb 2").unwrap_err(),
+            "line 2: expected `key = value`"
+        );
+        assert!(parse_toml("a = \"open").unwrap_err().starts_with("line 1"));
+        assert!(parse_toml("a = 1\na = 2")
+            .unwrap_err()
+            .contains("duplicate key"));
+        assert!(parse_toml("[keys").is_err());
+    }
+
+    #[test]
+    fn reads_key_bindings() {
+        let config = parse("[keys]\nquit = \"x\"\ndown = [\"Down\", \"Ctrl+n\"]").unwrap();
+        assert_eq!(config.keys["quit"].keys(), vec!["x"]);
+        assert_eq!(config.keys["down"].keys(), vec!["Down", "Ctrl+n"]);
+        assert!(parse("[keys]\nquit = 1").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_and_mistyped_settings() {
+        assert!(parse("refresh_intervl = 3").is_err());
+        assert!(parse("refresh_interval = \"3\"").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::notes;
+
+// How many recent completion times are averaged, across the fleet and for each model.
+const WINDOW: usize = 20;
+
+// Completions needed before the fleet-wide average stands in for a model with no history of
+// its own.
+const MIN_FLEET_SAMPLES: usize = 3;
+
+// How long recent downloads took to complete, in seconds, oldest first.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Completions {
+    recent: VecDeque<u64>,
+    by_model: BTreeMap<String, VecDeque<u64>>,
+}
+
+impl Completions {
+    pub fn record(&mut self, model: &str, took: Duration) {
+        let push = |window: &mut VecDeque<u64>| {
+            if window.len() == WINDOW {
+                window.pop_front();
+            }
+            window.push_back(took.as_secs());
+        };
+        push(&mut self.recent);
+        push(self.by_model.entry(model.to_string()).or_default());
+    }
+
+    // How long a download of `model` can be expected to take from start to completion: the
+    // model's own average, or the fleet's once there's enough of it.
+    pub fn expected(&self, model: &str) -> Option<Duration> {
+        let average = |window: &VecDeque<u64>| {
+            Duration::from_secs(window.iter().sum::<u64>() / window.len() as u64)
+        };
+        match self.by_model.get(model) {
+            Some(window) if !window.is_empty() => Some(average(window)),
+            _ => (self.recent.len() >= MIN_FLEET_SAMPLES).then(|| average(&self.recent)),
+        }
+    }
+}
+
+// `completions.json` beside the notes file.
+pub fn default_path() -> Option<PathBuf> {
+    Some(notes::default_path()?.with_file_name("completions.json"))
+}
+
+// Reads the completion times at `path`. A missing file just means nothing has completed yet.
+pub fn load(path: &Path) -> Result<Completions, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid completions file {}: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Completions::default()),
+        Err(e) => Err(format!("Could not read completions {}: {}", path.display(), e)),
+    }
+}
+
+// Writes the completion times to `path`, via a temporary file like the notes.
+pub fn save(path: &Path, completions: &Completions) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string(completions)?)?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_recent_completions() {
+        let mut completions = Completions::default();
+        let mins = |n: u64| Duration::from_secs(n * 60);
+        completions.record("alpha", mins(10));
+        assert_eq!(completions.expected("alpha"), Some(mins(10)));
+        assert_eq!(completions.expected("beta"), None);
+
+        completions.record("gamma", mins(20));
+        completions.record("alpha", mins(30));
+        assert_eq!(completions.expected("alpha"), Some(mins(20)));
+        assert_eq!(completions.expected("beta"), Some(mins(20)));
+
+        for _ in 0..WINDOW {
+            completions.record("alpha", mins(5));
+        }
+        assert_eq!(completions.expected("alpha"), Some(mins(5)));
+        assert_eq!(completions.expected("gamma"), Some(mins(20)));
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{DownloadStatus, Transition};
+
+// Where --emit-events writes: `-` is stdout, anything else a file that's appended to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventTarget {
+    Stdout,
+    File(PathBuf),
+}
+
+impl EventTarget {
+    pub fn parse(value: &str) -> EventTarget {
+        match value {
+            "-" => EventTarget::Stdout,
+            path => EventTarget::File(PathBuf::from(path)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Event<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "modelName")]
+    model_name: &'a str,
+    #[serde(rename = "oldStatus")]
+    old_status: &'a DownloadStatus,
+    #[serde(rename = "newStatus")]
+    new_status: &'a DownloadStatus,
+    #[serde(rename = "retryCount")]
+    retry_count: u32,
+}
+
+// Writes one JSON object per status transition, flushing after each line so the output can
+// be tailed as it happens.
+pub struct EventSink {
+    out: Box<dyn Write + Send + Sync>,
+}
+
+impl EventSink {
+    pub fn open(target: &EventTarget) -> io::Result<EventSink> {
+        let out: Box<dyn Write + Send + Sync> = match target {
+            EventTarget::Stdout => Box::new(io::stdout()),
+            EventTarget::File(path) => {
+                Box::new(OpenOptions::new().create(true).append(true).open(path)?)
+            }
+        };
+        Ok(EventSink { out })
+    }
+
+    pub fn write(&mut self, transition: &Transition, at: DateTime<Utc>) -> io::Result<()> {
+        let event = Event {
+            timestamp: at,
+            model_name: &transition.model_name,
+            old_status: &transition.old_status,
+            new_status: &transition.new_status,
+            retry_count: transition.retry_count,
+        };
+        serde_json::to_writer(&mut self.out, &event)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_one_json_line_per_transition() {
+        let path =
+            std::env::temp_dir().join(format!("downloader-ctl-events-{}", std::process::id()));
+        let mut sink = EventSink::open(&EventTarget::File(path.clone())).unwrap();
+        let transition = Transition {
+            model_name: "alpha".to_string(),
+            duplicate: 0,
+            old_status: DownloadStatus::Downloading,
+            new_status: DownloadStatus::Error {
+                message: Some("disk full".to_string()),
+            },
+            retry_count: 2,
+        };
+        let at = "2024-05-01T12:00:00Z".parse().unwrap();
+        sink.write(&transition, at).unwrap();
+        sink.write(&transition, at).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            r#"{"timestamp":"2024-05-01T12:00:00Z","modelName":"alpha","oldStatus":"Downloading","newStatus":"Error: disk full","retryCount":2}"#
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::Download;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Result<ExportFormat, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(format!(
+                "Invalid export format '{}': expected json or csv",
+                value
+            )),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+// Writes `downloads` to a timestamped file in `dir` and returns its path.
+pub fn write(
+    downloads: &[Download],
+    format: ExportFormat,
+    dir: &Path,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(downloads)?,
+        ExportFormat::Csv => to_csv(downloads),
+    };
+
+    fs::create_dir_all(dir)?;
+    let file_name = format!(
+        "downloads-{}.{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    let path = dir.join(file_name);
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+fn to_csv(downloads: &[Download]) -> String {
+    let mut out = String::from("modelName,status,startTime,lastStatusChange,retryCount\n");
+    for download in downloads {
+        let fields = [
+            download.model_name.clone(),
+            download.status.to_string(),
+            download.start_time.to_rfc3339(),
+            download.last_status_change.to_rfc3339(),
+            download.retry_count.to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+// Quotes a field if it contains a delimiter, quote or line break, doubling inner quotes.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DownloadStatus;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn csv_has_a_header_and_escapes_fields() {
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let downloads = vec![Download {
+            model_name: "alpha".to_string(),
+            status: DownloadStatus::Error {
+                message: Some("disk full, said \"no\"".to_string()),
+            },
+            start_time: time,
+            last_status_change: time,
+            retry_count: 2,
+            source_url: None,
+            bytes_downloaded: None,
+            total_bytes: None,
+            queue_position: None,
+            duplicate: 0,
+        }];
+
+        assert_eq!(
+            to_csv(&downloads),
+            "modelName,status,startTime,lastStatusChange,retryCount\n\
+             alpha,\"Error: disk full, said \"\"no\"\"\",2024-01-01T00:00:00+00:00,\
+             2024-01-01T00:00:00+00:00,2\n"
+        );
+    }
+
+    #[test]
+    fn parses_export_formats() {
+        assert_eq!(ExportFormat::parse("JSON"), Ok(ExportFormat::Json));
+        assert_eq!(ExportFormat::parse("csv"), Ok(ExportFormat::Csv));
+        assert!(ExportFormat::parse("xml").is_err());
+    }
+}
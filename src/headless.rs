@@ -0,0 +1,199 @@
+use crate::{
+    cli::Command, client::AddOptions, humanize_duration, validate_download_url, AddOutcome, App,
+    ControlAction, Download, DRY_RUN_PREFIX,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Result<OutputFormat, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Invalid output format '{}': expected table or json",
+                value
+            )),
+        }
+    }
+}
+
+// Runs a single command, printing its result, and returns the process exit code.
+pub async fn run(app: &mut App, command: Command, format: OutputFormat) -> i32 {
+    let code = run_command(app, command, format).await;
+    // The log isn't shown outside the TUI, so say what a dry run would have sent.
+    if app.dry_run {
+        for entry in app.log.iter().filter(|e| e.message.starts_with(DRY_RUN_PREFIX)) {
+            eprintln!("{}", entry.message);
+        }
+    }
+    code
+}
+
+async fn run_command(app: &mut App, command: Command, format: OutputFormat) -> i32 {
+    let (action, model) = match command {
+        Command::Status => return print_status(app, format).await,
+        Command::Add(url) => return add(app, &url).await,
+        Command::AddBatch(path) => return add_batch(app, &path).await,
+        Command::Stop(model) => (ControlAction::Stop, model),
+        Command::Pause(model) => (ControlAction::Pause, model),
+        Command::Restart(model) => (ControlAction::Restart, model),
+    };
+
+    match app.send_action(action, std::slice::from_ref(&model)).await.pop() {
+        None => {
+            println!("{}: {} succeeded", action, model);
+            0
+        }
+        Some(e) => {
+            eprintln!(
+                "downloaderctl: Error {} {}: {}",
+                action.progressive(),
+                model,
+                e
+            );
+            1
+        }
+    }
+}
+
+async fn add(app: &mut App, url: &str) -> i32 {
+    let url = match validate_download_url(url) {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("downloaderctl: {}", e);
+            return 2;
+        }
+    };
+
+    match app.add_download(url.clone(), &AddOptions::default()).await {
+        Ok(AddOutcome::Added(model)) => {
+            println!("Added {}", model.unwrap_or(url));
+            0
+        }
+        Ok(AddOutcome::AlreadyTracking(model)) => {
+            println!("Already tracking {}", model);
+            0
+        }
+        Err(e) => {
+            eprintln!("downloaderctl: Error adding download: {}", e);
+            1
+        }
+    }
+}
+
+async fn add_batch(app: &mut App, path: &str) -> i32 {
+    let contents = if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    };
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("downloaderctl: Could not read {}: {}", path, e);
+            return 2;
+        }
+    };
+
+    let summary = app.add_batch(&contents, &AddOptions::default()).await;
+    for (url, error) in &summary.failed {
+        eprintln!("downloaderctl: {}: {}", url, error);
+    }
+    println!("{}", summary);
+    if summary.failed.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+// Fetches the list once and prints it to stdout, returning the process exit code.
+async fn print_status(app: &mut App, format: OutputFormat) -> i32 {
+    if let Err(e) = app.fetch_downloads().await {
+        eprintln!("downloaderctl: Failed to fetch downloads: {}", e);
+        return 1;
+    }
+
+    match format {
+        OutputFormat::Table => print!("{}", table(&app.downloads)),
+        OutputFormat::Json => match serde_json::to_string_pretty(&app.downloads) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("downloaderctl: {}", e);
+                return 1;
+            }
+        },
+    }
+    0
+}
+
+fn table(downloads: &[Download]) -> String {
+    let now = chrono::Utc::now();
+    let mut rows = vec![[
+        "MODEL".to_string(),
+        "STATUS".to_string(),
+        "RETRIES".to_string(),
+        "LAST CHANGE".to_string(),
+    ]];
+    rows.extend(downloads.iter().map(|d| {
+        [
+            d.model_name.clone(),
+            d.status.to_string(),
+            d.retry_count.to_string(),
+            humanize_duration(now - d.last_status_change),
+        ]
+    }));
+
+    let mut widths = [0; 4];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DownloadStatus;
+
+    #[test]
+    fn table_aligns_columns() {
+        let now = chrono::Utc::now();
+        let downloads = vec![Download {
+            model_name: "alpha".to_string(),
+            status: DownloadStatus::Retrying { message: None },
+            start_time: now,
+            last_status_change: now,
+            retry_count: 12,
+            source_url: None,
+            bytes_downloaded: None,
+            total_bytes: None,
+            queue_position: None,
+            duplicate: 0,
+        }];
+
+        assert_eq!(
+            table(&downloads),
+            "MODEL  STATUS    RETRIES  LAST CHANGE\n\
+             alpha  Retrying  12       0s\n"
+        );
+    }
+}
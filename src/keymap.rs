@@ -0,0 +1,547 @@
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// Everything a key can do from the main list. The help overlay and shortcut bar are built
+// from the same table the event loop dispatches through, so they can't drift apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyAction {
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    First,
+    Last,
+    NextError,
+    PreviousError,
+    Details,
+    Watch,
+    ServerLog,
+    Expand,
+    Collapse,
+    Mark,
+    Clear,
+    Note,
+    CopyName,
+    Add,
+    Stop,
+    Restart,
+    RestartErrored,
+    Pause,
+    PauseAll,
+    ResumeAll,
+    ClearCompleted,
+    Delete,
+    QueueEarlier,
+    QueueLater,
+    ForceStop,
+    ForceRestart,
+    ForcePause,
+    ForceDelete,
+    Undo,
+    Filter,
+    StatusFilter,
+    Sort,
+    SortDirection,
+    Export,
+    Log,
+    Stats,
+    ChangeServer,
+    Refresh,
+    PauseRefresh,
+    Compact,
+    Timestamps,
+    NextServer,
+    Help,
+    Quit,
+}
+
+impl KeyAction {
+    // Every action, in the order the help overlay lists them.
+    pub const ALL: [KeyAction; 48] = [
+        KeyAction::Down,
+        KeyAction::Up,
+        KeyAction::PageDown,
+        KeyAction::PageUp,
+        KeyAction::First,
+        KeyAction::Last,
+        KeyAction::NextError,
+        KeyAction::PreviousError,
+        KeyAction::Details,
+        KeyAction::Watch,
+        KeyAction::ServerLog,
+        KeyAction::Expand,
+        KeyAction::Collapse,
+        KeyAction::Mark,
+        KeyAction::Clear,
+        KeyAction::Note,
+        KeyAction::CopyName,
+        KeyAction::Add,
+        KeyAction::Stop,
+        KeyAction::Restart,
+        KeyAction::RestartErrored,
+        KeyAction::Pause,
+        KeyAction::PauseAll,
+        KeyAction::ResumeAll,
+        KeyAction::ClearCompleted,
+        KeyAction::Delete,
+        KeyAction::QueueEarlier,
+        KeyAction::QueueLater,
+        KeyAction::ForceStop,
+        KeyAction::ForceRestart,
+        KeyAction::ForcePause,
+        KeyAction::ForceDelete,
+        KeyAction::Undo,
+        KeyAction::Filter,
+        KeyAction::StatusFilter,
+        KeyAction::Sort,
+        KeyAction::SortDirection,
+        KeyAction::Export,
+        KeyAction::Log,
+        KeyAction::Stats,
+        KeyAction::ChangeServer,
+        KeyAction::Refresh,
+        KeyAction::PauseRefresh,
+        KeyAction::Compact,
+        KeyAction::Timestamps,
+        KeyAction::NextServer,
+        KeyAction::Help,
+        KeyAction::Quit,
+    ];
+
+    // The name used for the action in the config file's `[keys]` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyAction::Down => "down",
+            KeyAction::Up => "up",
+            KeyAction::PageDown => "page_down",
+            KeyAction::PageUp => "page_up",
+            KeyAction::First => "first",
+            KeyAction::Last => "last",
+            KeyAction::NextError => "next_error",
+            KeyAction::PreviousError => "previous_error",
+            KeyAction::Details => "details",
+            KeyAction::Watch => "watch",
+            KeyAction::ServerLog => "server_log",
+            KeyAction::Expand => "expand",
+            KeyAction::Collapse => "collapse",
+            KeyAction::Mark => "mark",
+            KeyAction::Clear => "clear",
+            KeyAction::Note => "note",
+            KeyAction::CopyName => "copy_name",
+            KeyAction::Add => "add",
+            KeyAction::Stop => "stop",
+            KeyAction::Restart => "restart",
+            KeyAction::RestartErrored => "restart_errored",
+            KeyAction::Pause => "pause",
+            KeyAction::PauseAll => "pause_all",
+            KeyAction::ResumeAll => "resume_all",
+            KeyAction::ClearCompleted => "clear_completed",
+            KeyAction::Delete => "delete",
+            KeyAction::QueueEarlier => "queue_earlier",
+            KeyAction::QueueLater => "queue_later",
+            KeyAction::ForceStop => "force_stop",
+            KeyAction::ForceRestart => "force_restart",
+            KeyAction::ForcePause => "force_pause",
+            KeyAction::ForceDelete => "force_delete",
+            KeyAction::Undo => "undo",
+            KeyAction::Filter => "filter",
+            KeyAction::StatusFilter => "status_filter",
+            KeyAction::Sort => "sort",
+            KeyAction::SortDirection => "sort_direction",
+            KeyAction::Export => "export",
+            KeyAction::Log => "log",
+            KeyAction::Stats => "stats",
+            KeyAction::ChangeServer => "change_server",
+            KeyAction::Refresh => "refresh",
+            KeyAction::PauseRefresh => "pause_refresh",
+            KeyAction::Compact => "compact",
+            KeyAction::Timestamps => "timestamps",
+            KeyAction::NextServer => "next_server",
+            KeyAction::Help => "help",
+            KeyAction::Quit => "quit",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            KeyAction::Down => "Select next download",
+            KeyAction::Up => "Select previous download",
+            KeyAction::PageDown => "Move down a page",
+            KeyAction::PageUp => "Move up a page",
+            KeyAction::First => "Select first download",
+            KeyAction::Last => "Select last download",
+            KeyAction::NextError => "Jump to next errored download",
+            KeyAction::PreviousError => "Jump to previous errored download",
+            KeyAction::Details => "Show download details or expand a group",
+            KeyAction::Watch => "Watch the selected download full screen",
+            KeyAction::ServerLog => "Tail the server's log for the selected download",
+            KeyAction::Expand => "Expand the selected group, or move right a column",
+            KeyAction::Collapse => "Collapse the selected group, or move left a column",
+            KeyAction::Mark => "Mark for batch actions",
+            KeyAction::Clear => "Clear filter, then marks",
+            KeyAction::Note => "Edit the selected download's note",
+            KeyAction::CopyName => "Copy the selected model's name",
+            KeyAction::Add => "Add a download",
+            KeyAction::Stop => "Stop download",
+            KeyAction::Restart => "Restart download",
+            KeyAction::RestartErrored => "Restart every errored or offline download",
+            KeyAction::Pause => "Pause download",
+            KeyAction::PauseAll => "Pause all active downloads",
+            KeyAction::ResumeAll => "Resume all paused downloads",
+            KeyAction::ClearCompleted => "Delete every completed download",
+            KeyAction::Delete => "Delete download",
+            KeyAction::QueueEarlier => "Move a queued download earlier in the queue",
+            KeyAction::QueueLater => "Move a queued download later in the queue",
+            KeyAction::ForceStop => "Stop download without confirming",
+            KeyAction::ForceRestart => "Restart download without confirming",
+            KeyAction::ForcePause => "Pause download without confirming",
+            KeyAction::ForceDelete => "Delete download without confirming",
+            KeyAction::Undo => "Undo last stop or delete",
+            KeyAction::Filter => "Filter by name",
+            KeyAction::StatusFilter => "Cycle status filter",
+            KeyAction::Sort => "Cycle sort key",
+            KeyAction::SortDirection => "Reverse sort direction",
+            KeyAction::Export => "Export list to a file",
+            KeyAction::Log => "Show log",
+            KeyAction::Stats => "Show fleet statistics",
+            KeyAction::ChangeServer => "Connect to a different server URL",
+            KeyAction::Refresh => "Refresh the list now",
+            KeyAction::PauseRefresh => "Pause or resume refreshing the list",
+            KeyAction::Compact => "Toggle the compact display",
+            KeyAction::Timestamps => "Switch between relative and local clock times",
+            KeyAction::NextServer => "Switch to the next server (1-9 pick one)",
+            KeyAction::Help => "Show this help",
+            KeyAction::Quit => "Quit",
+        }
+    }
+
+    // Short label for the shortcut bar, for the actions important enough to appear there.
+    pub fn shortcut_label(self) -> Option<&'static str> {
+        match self {
+            KeyAction::Add => Some("Add"),
+            KeyAction::Stop => Some("Stop"),
+            KeyAction::Restart => Some("Restart"),
+            KeyAction::Pause => Some("Pause"),
+            KeyAction::Delete => Some("Delete"),
+            KeyAction::Mark => Some("Mark"),
+            KeyAction::Details => Some("Details"),
+            KeyAction::Filter => Some("Filter"),
+            KeyAction::Help => Some("Help"),
+            KeyAction::Quit => Some("Quit"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+impl Key {
+    const fn plain(code: KeyCode) -> Key {
+        Key { code, ctrl: false }
+    }
+
+    const fn char(c: char) -> Key {
+        Key::plain(KeyCode::Char(c))
+    }
+
+    const fn ctrl(c: char) -> Key {
+        Key {
+            code: KeyCode::Char(c),
+            ctrl: true,
+        }
+    }
+
+    // Parses a key as written in the config: a single character, a named key such as
+    // `PageDown` or `Esc`, either optionally prefixed with `Ctrl+`.
+    pub fn parse(value: &str) -> Result<Key, String> {
+        let invalid = || format!("Invalid key '{}'", value);
+        let (ctrl, name) = match value.get(..5) {
+            Some(prefix) if value.len() > 5 && prefix.eq_ignore_ascii_case("ctrl+") => {
+                (true, &value[5..])
+            }
+            _ => (false, value),
+        };
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => match c {
+                '↑' => KeyCode::Up,
+                '↓' => KeyCode::Down,
+                c => KeyCode::Char(c),
+            },
+            _ => match name.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "enter" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                other => match other.strip_prefix('f').map(str::parse::<u8>) {
+                    Some(Ok(n)) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(invalid()),
+                },
+            },
+        };
+        Ok(Key { code, ctrl })
+    }
+
+    // Shift is already reflected in the character itself, so only Ctrl is compared.
+    fn matches(&self, event: &KeyEvent) -> bool {
+        self.code == event.code && self.ctrl == event.modifiers.contains(KeyModifiers::CONTROL)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            KeyCode::Delete => write!(f, "Delete"),
+            KeyCode::Insert => write!(f, "Insert"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: Vec<(KeyAction, Vec<Key>)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let keys = |action| match action {
+            KeyAction::Down => vec![Key::plain(KeyCode::Down), Key::char('j')],
+            KeyAction::Up => vec![Key::plain(KeyCode::Up), Key::char('k')],
+            KeyAction::PageDown => vec![Key::plain(KeyCode::PageDown)],
+            KeyAction::PageUp => vec![Key::plain(KeyCode::PageUp)],
+            KeyAction::First => vec![Key::plain(KeyCode::Home)],
+            KeyAction::Last => vec![Key::plain(KeyCode::End)],
+            KeyAction::NextError => vec![Key::char('n')],
+            KeyAction::PreviousError => vec![Key::char('N')],
+            KeyAction::Details => vec![Key::plain(KeyCode::Enter)],
+            KeyAction::Watch => vec![Key::char('w')],
+            KeyAction::ServerLog => vec![Key::char('l')],
+            KeyAction::Expand => vec![Key::plain(KeyCode::Right)],
+            KeyAction::Collapse => vec![Key::plain(KeyCode::Left)],
+            KeyAction::Mark => vec![Key::char(' ')],
+            KeyAction::Clear => vec![Key::plain(KeyCode::Esc)],
+            KeyAction::Note => vec![Key::char('t')],
+            KeyAction::CopyName => vec![Key::char('y')],
+            KeyAction::Add => vec![Key::char('a')],
+            KeyAction::Stop => vec![Key::char('s')],
+            KeyAction::Restart => vec![Key::char('r')],
+            KeyAction::RestartErrored => vec![Key::char('R')],
+            KeyAction::Pause => vec![Key::char('p')],
+            KeyAction::PauseAll => vec![Key::char('P')],
+            KeyAction::ResumeAll => vec![Key::char('U')],
+            KeyAction::ClearCompleted => vec![Key::char('c')],
+            KeyAction::Delete => vec![Key::char('d')],
+            KeyAction::QueueEarlier => vec![Key::char('K')],
+            KeyAction::QueueLater => vec![Key::char('J')],
+            KeyAction::ForceStop => vec![Key::ctrl('s')],
+            KeyAction::ForceRestart => vec![Key::ctrl('r')],
+            KeyAction::ForcePause => vec![Key::ctrl('p')],
+            KeyAction::ForceDelete => vec![Key::ctrl('d')],
+            KeyAction::Undo => vec![Key::ctrl('z')],
+            KeyAction::Filter => vec![Key::char('/')],
+            KeyAction::StatusFilter => vec![Key::char('f')],
+            KeyAction::Sort => vec![Key::char('o')],
+            KeyAction::SortDirection => vec![Key::char('O')],
+            KeyAction::Export => vec![Key::char('e')],
+            KeyAction::Log => vec![Key::char('L')],
+            KeyAction::Stats => vec![Key::char('S')],
+            KeyAction::ChangeServer => vec![Key::char(':')],
+            KeyAction::Refresh => vec![Key::char('g'), Key::plain(KeyCode::F(5))],
+            KeyAction::PauseRefresh => vec![Key::char('F')],
+            KeyAction::Compact => vec![Key::char('v')],
+            KeyAction::Timestamps => vec![Key::char('T')],
+            KeyAction::NextServer => vec![Key::plain(KeyCode::Tab)],
+            KeyAction::Help => vec![Key::char('?')],
+            KeyAction::Quit => vec![Key::char('q')],
+        };
+        KeyMap {
+            bindings: KeyAction::ALL
+                .into_iter()
+                .map(|action| (action, keys(action)))
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    // The defaults with each action named in `overrides` rebound to the given keys, which
+    // replace its default keys entirely. Fails if an action is unknown, a key can't be
+    // parsed, or two actions would end up sharing a key.
+    pub fn with_overrides<'a, I>(overrides: I) -> Result<KeyMap, String>
+    where
+        I: IntoIterator<Item = (&'a str, Vec<&'a str>)>,
+    {
+        let mut keymap = KeyMap::default();
+        for (name, keys) in overrides {
+            let action = KeyAction::ALL
+                .into_iter()
+                .find(|action| action.name() == name)
+                .ok_or_else(|| format!("Unknown key binding action '{}'", name))?;
+            let mut parsed: Vec<Key> = Vec::new();
+            for key in keys {
+                let key = Key::parse(key)?;
+                if !parsed.contains(&key) {
+                    parsed.push(key);
+                }
+            }
+            if let Some((_, bound)) = keymap.bindings.iter_mut().find(|(a, _)| *a == action) {
+                *bound = parsed;
+            }
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, (action, keys)) in self.bindings.iter().enumerate() {
+            for key in keys {
+                let clash = self.bindings[i + 1..]
+                    .iter()
+                    .find(|(_, other)| other.contains(key));
+                if let Some((other, _)) = clash {
+                    return Err(format!(
+                        "Key '{}' is bound to both {} and {}",
+                        key,
+                        action.name(),
+                        other.name()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| key.matches(event)))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: KeyAction) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    // Each action's keys, joined for display, alongside its description.
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        self.bindings
+            .iter()
+            .map(|(action, keys)| {
+                let keys: Vec<String> = keys.iter().map(Key::to_string).collect();
+                (keys.join(" / "), action.description())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispatches_default_keys() {
+        let keymap = KeyMap::default();
+        let press = |code, modifiers| keymap.action(&KeyEvent::new(code, modifiers));
+
+        assert_eq!(
+            press(KeyCode::Char('j'), KeyModifiers::NONE),
+            Some(KeyAction::Down)
+        );
+        assert_eq!(
+            press(KeyCode::Char('P'), KeyModifiers::SHIFT),
+            Some(KeyAction::PauseAll)
+        );
+        assert_eq!(
+            press(KeyCode::Char('z'), KeyModifiers::CONTROL),
+            Some(KeyAction::Undo)
+        );
+        assert_eq!(press(KeyCode::Char('z'), KeyModifiers::NONE), None);
+        assert_eq!(
+            press(KeyCode::Char('s'), KeyModifiers::CONTROL),
+            Some(KeyAction::ForceStop)
+        );
+    }
+
+    #[test]
+    fn every_action_has_a_default_key() {
+        let keymap = KeyMap::default();
+        for action in KeyAction::ALL {
+            assert!(!keymap.keys(action).is_empty(), "{:?} is unbound", action);
+        }
+        assert!(keymap
+            .help()
+            .contains(&("Ctrl+z".to_string(), "Undo last stop or delete")));
+    }
+
+    #[test]
+    fn overrides_replace_default_keys() {
+        let keymap =
+            KeyMap::with_overrides([("down", vec!["Ctrl+n", "PgDn"]), ("page_down", vec![])])
+                .unwrap();
+        let press = |code, modifiers| keymap.action(&KeyEvent::new(code, modifiers));
+
+        assert_eq!(
+            press(KeyCode::Char('n'), KeyModifiers::CONTROL),
+            Some(KeyAction::Down)
+        );
+        assert_eq!(
+            press(KeyCode::PageDown, KeyModifiers::NONE),
+            Some(KeyAction::Down)
+        );
+        assert_eq!(press(KeyCode::Char('j'), KeyModifiers::NONE), None);
+        assert_eq!(
+            press(KeyCode::Char('n'), KeyModifiers::NONE),
+            Some(KeyAction::NextError)
+        );
+    }
+
+    #[test]
+    fn rejects_conflicts_and_unknown_names() {
+        assert_eq!(
+            KeyMap::with_overrides([("quit", vec!["j"])]).unwrap_err(),
+            "Key 'j' is bound to both down and quit"
+        );
+        assert!(KeyMap::with_overrides([("jump", vec!["x"])]).is_err());
+        assert!(KeyMap::with_overrides([("quit", vec!["Ctrl+"])]).is_err());
+        assert!(KeyMap::with_overrides([("quit", vec!["F13"])]).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{Debug, Write as _},
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::{SecondsFormat, Utc};
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span, Event, Level, Metadata, Subscriber,
+};
+
+// Used when --log-file is given without RUST_LOG.
+const DEFAULT_FILTER: &str = "info";
+
+// Which events reach the log file, from a RUST_LOG-style list of directives: a bare level
+// ("debug") sets the default, and "target=level" overrides it for a module and its children,
+// e.g. "info,downloaderctl::client=trace,hyper=off".
+#[derive(Debug, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub fn parse(spec: &str) -> Result<LogFilter, String> {
+        let level = |value: &str| {
+            value
+                .trim()
+                .parse::<LevelFilter>()
+                .map_err(|_| format!("Invalid RUST_LOG level '{}'", value.trim()))
+        };
+        let mut filter = LogFilter {
+            default: LevelFilter::ERROR,
+            targets: Vec::new(),
+        };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, value)) => filter
+                    .targets
+                    .push((target.trim().to_string(), level(value)?)),
+                // A bare target name enables everything from it, as env_logger does.
+                None => match level(directive) {
+                    Ok(level) => filter.default = level,
+                    Err(_) => filter
+                        .targets
+                        .push((directive.to_string(), LevelFilter::TRACE)),
+                },
+            }
+        }
+        // The most specific target wins.
+        filter
+            .targets
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Ok(filter)
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .find(|(prefix, _)| {
+                target == prefix
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn enabled(&self, target: &str, level: &Level) -> bool {
+        self.level_for(target) >= *level
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, LevelFilter::max)
+    }
+}
+
+// Routes `tracing` events to `path`, filtered by RUST_LOG. Lasts for the rest of the process.
+pub fn init(path: &Path, spec: Option<&str>) -> Result<(), String> {
+    let filter = LogFilter::parse(spec.unwrap_or(DEFAULT_FILTER))?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Could not open log file {}: {}", path.display(), e))?;
+    tracing::subscriber::set_global_default(FileLog::new(filter, file)).map_err(|e| e.to_string())
+}
+
+// Writes one line per event, prefixed with the spans it happened in:
+// `2024-05-01T12:00:00.000Z DEBUG refresh: http{method=GET url=...}: target: message k=v`.
+pub struct FileLog {
+    filter: LogFilter,
+    out: Mutex<File>,
+    spans: Mutex<HashMap<u64, SpanRecord>>,
+    next_id: AtomicU64,
+}
+
+struct SpanRecord {
+    name: &'static str,
+    fields: String,
+    // Handles to the span still alive; it's forgotten when the last one closes.
+    refs: usize,
+}
+
+thread_local! {
+    // The spans entered on this thread, innermost last.
+    static CURRENT: RefCell<Vec<span::Id>> = const { RefCell::new(Vec::new()) };
+}
+
+impl FileLog {
+    pub fn new(filter: LogFilter, out: File) -> FileLog {
+        FileLog {
+            filter,
+            out: Mutex::new(out),
+            spans: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn span_context(&self) -> String {
+        let spans = self.spans.lock().unwrap();
+        CURRENT.with(|current| {
+            current
+                .borrow()
+                .iter()
+                .filter_map(|id| spans.get(&id.into_u64()))
+                .map(|span| match span.fields.trim_start() {
+                    "" => format!("{}: ", span.name),
+                    fields => format!("{}{{{}}}: ", span.name, fields),
+                })
+                .collect()
+        })
+    }
+}
+
+impl Subscriber for FileLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.enabled(metadata.target(), metadata.level())
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.filter.max_level())
+    }
+
+    fn new_span(&self, attributes: &span::Attributes) -> span::Id {
+        let mut fields = Fields::default();
+        attributes.record(&mut fields);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.spans.lock().unwrap().insert(
+            id,
+            SpanRecord {
+                name: attributes.metadata().name(),
+                fields: fields.rest,
+                refs: 1,
+            },
+        );
+        span::Id::from_u64(id)
+    }
+
+    fn record(&self, span: &span::Id, values: &span::Record) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            span.fields.push_str(&fields.rest);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        let line = format!(
+            "{} {:>5} {}{}: {}{}\n",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            metadata.level(),
+            self.span_context(),
+            metadata.target(),
+            fields.message,
+            fields.rest
+        );
+        // There's nowhere to report a failed write, so the line is dropped.
+        let _ = self.out.lock().unwrap().write_all(line.as_bytes());
+    }
+
+    fn enter(&self, span: &span::Id) {
+        CURRENT.with(|current| current.borrow_mut().push(span.clone()));
+    }
+
+    fn exit(&self, span: &span::Id) {
+        CURRENT.with(|current| {
+            let mut current = current.borrow_mut();
+            if let Some(i) = current.iter().rposition(|entered| entered == span) {
+                current.remove(i);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &span::Id) -> span::Id {
+        if let Some(record) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            record.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: span::Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let Some(record) = spans.get_mut(&span.into_u64()) else {
+            return false;
+        };
+        record.refs -= 1;
+        if record.refs > 0 {
+            return false;
+        }
+        spans.remove(&span.into_u64());
+        true
+    }
+}
+
+// An event's message and its other fields as ` name=value` pairs.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_by_the_most_specific_target() {
+        let filter =
+            LogFilter::parse("warn, downloaderctl=debug,downloaderctl::client=trace").unwrap();
+        assert!(filter.enabled("downloaderctl::client", &Level::TRACE));
+        assert!(filter.enabled("downloaderctl", &Level::DEBUG));
+        assert!(!filter.enabled("downloaderctl::sse", &Level::TRACE));
+        assert!(!filter.enabled("hyper::proto", &Level::INFO));
+        assert!(filter.enabled("hyper::proto", &Level::WARN));
+        // Prefixes only match whole path segments.
+        assert!(!filter.enabled("downloaderctlx", &Level::DEBUG));
+        assert_eq!(filter.max_level(), LevelFilter::TRACE);
+
+        assert!(LogFilter::parse("hyper=loud").is_err());
+        assert!(LogFilter::parse("downloaderctl")
+            .unwrap()
+            .enabled("downloaderctl::client", &Level::TRACE));
+    }
+
+    #[test]
+    fn writes_events_with_their_spans() {
+        let path = std::env::temp_dir().join(format!("downloader-ctl-log-{}", std::process::id()));
+        let file = File::create(&path).unwrap();
+        let log = FileLog::new(LogFilter::parse("debug").unwrap(), file);
+        tracing::subscriber::with_default(log, || {
+            let span = tracing::debug_span!("http", method = "GET");
+            let _entered = span.enter();
+            tracing::info!(status = 200, "response");
+            tracing::trace!("too detailed");
+        });
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", contents);
+        assert!(
+            lines[0].ends_with(
+                r#" INFO http{method="GET"}: downloaderctl::logging::tests: response status=200"#
+            ),
+            "{}",
+            lines[0]
+        );
+    }
+}
@@ -1,59 +1,194 @@
+mod cli;
+mod client;
+mod clipboard;
+mod config;
+mod eta;
+mod events;
+mod export;
+mod headless;
+mod keymap;
+mod logging;
+mod notes;
+mod notify;
+mod sse;
+mod state;
+mod template;
+mod theme;
+
+use futures_util::future::join_all;
 use std::{
-    env,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     error::Error,
+    future::Future,
     io,
-    sync::Arc,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU16, AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, MouseButton, MouseEventKind,
+    },
+    cursor::Show,
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use reqwest::Client;
-use serde::{Deserialize, Serialize, Deserializer};
-use tokio::sync::Mutex;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION},
+    Certificate, Client,
+};
+use serde::{
+    de::{self, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use tokio::{
+    sync::{Mutex, Notify, Semaphore},
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
+use url::Url;
 
-// Custom deserialization function for DownloadStatus
-fn deserialize_status<'de, D>(deserializer: D) -> Result<DownloadStatus, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: String = String::deserialize(deserializer)?;
-    match s.to_lowercase().as_str() {
-        "downloading" => Ok(DownloadStatus::Downloading),
-        "initializing" => Ok(DownloadStatus::Initializing),
-        "retrying" => Ok(DownloadStatus::Retrying { message: None }),
-        "retrying: " => Ok(DownloadStatus::Retrying { message: None }),
-        s if s.starts_with("retrying: ") => Ok(DownloadStatus::Retrying {
+use client::{
+    AddOptions, Capabilities, ControlFailure, DownloaderClient, Feature, Lookup, UnexpectedBody,
+};
+use events::EventSink;
+use keymap::{KeyAction, KeyMap};
+use notify::NotifyOn;
+use state::UiState;
+use template::{Field, RowTemplate, Segment, TimeColumn};
+use theme::Theme;
+
+// Frames of the activity spinner shown while requests are in flight.
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+// How long the event loop waits for input before redrawing.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+// Upper bound on establishing a connection, regardless of the overall request timeout.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Connection attempts made at startup before giving up, and the wait before the first retry,
+// which doubles after each failure.
+const STARTUP_ATTEMPTS: u32 = 5;
+const STARTUP_BACKOFF: Duration = Duration::from_millis(500);
+
+// Number of status transitions remembered per model.
+const HISTORY_LIMIT: usize = 50;
+
+// Number of entries kept in the in-app log.
+const LOG_LIMIT: usize = 200;
+
+// Number of lines of a download's server log kept in the server log pane.
+const SERVER_LOG_LIMIT: usize = 1000;
+
+// How long an action's result stays in the banner.
+const BANNER_DURATION: Duration = Duration::from_secs(5);
+
+// How often the server's health and version endpoints are checked.
+const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+// The longest the background refresh waits between attempts while the server is failing.
+const BACKOFF_CAP: Duration = Duration::from_secs(30);
+
+// How long a download the server restarted is flagged in the list.
+const RESTART_FLASH: Duration = Duration::from_secs(10);
+
+// How long after a stop or delete Ctrl+Z can still reverse it.
+const UNDO_WINDOW: Duration = Duration::from_secs(30);
+
+// Starts every log entry describing a request a dry run didn't send.
+const DRY_RUN_PREFIX: &str = "Dry run:";
+
+// Delay before the first retry of a failed control action; later retries back off exponentially.
+const ACTION_RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+// Retry counts above which a row is tinted yellow and then red.
+const RETRY_WARN_THRESHOLD: u32 = 3;
+const RETRY_ALERT_THRESHOLD: u32 = 10;
+
+// Parses the plain string form of a status, e.g. "Downloading" or "Error: timed out".
+// Matching is case-insensitive, but any trailing message keeps its original casing.
+fn parse_status(s: &str) -> Option<DownloadStatus> {
+    let lower = s.to_lowercase();
+    match lower.as_str() {
+        "downloading" => Some(DownloadStatus::Downloading),
+        "initializing" => Some(DownloadStatus::Initializing),
+        "queued" => Some(DownloadStatus::Queued),
+        "retrying" | "retrying: " => Some(DownloadStatus::Retrying { message: None }),
+        l if l.starts_with("retrying: ") => Some(DownloadStatus::Retrying {
             message: Some(s[10..].to_string()),
         }),
-        "offline" => Ok(DownloadStatus::Offline),
-        "paused" => Ok(DownloadStatus::Paused),
-        "paused for exclusive show" => Ok(DownloadStatus::PausedForExclusiveShow),
-        "paused for ticket show" => Ok(DownloadStatus::PausedForTicketShow),
-        "error" => Ok(DownloadStatus::Error { message: None }),
-        s if s.starts_with("error: ") => Ok(DownloadStatus::Error {
+        "offline" => Some(DownloadStatus::Offline),
+        "paused" => Some(DownloadStatus::Paused),
+        "paused for exclusive show" => Some(DownloadStatus::PausedForExclusiveShow),
+        "paused for ticket show" => Some(DownloadStatus::PausedForTicketShow),
+        "error" | "error: " => Some(DownloadStatus::Error { message: None }),
+        l if l.starts_with("error: ") => Some(DownloadStatus::Error {
             message: Some(s[7..].to_string()),
         }),
-        "completed" => Ok(DownloadStatus::Completed),
-        _ => Err(serde::de::Error::custom(format!("Unknown status: {}", s))),
+        "completed" => Some(DownloadStatus::Completed),
+        _ => None,
+    }
+}
+
+// Accepts either a plain string ("Downloading") or a single-key object carrying a
+// message ({"ErrorWithMessage": "..."}), which is how the server reports errors and retries.
+struct DownloadStatusVisitor;
+
+impl<'de> Visitor<'de> for DownloadStatusVisitor {
+    type Value = DownloadStatus;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a status string or a single-key status object")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<DownloadStatus, E>
+    where
+        E: de::Error,
+    {
+        parse_status(s).ok_or_else(|| E::custom(format!("Unknown status: {}", s)))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<DownloadStatus, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let key: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let message: Option<String> = map.next_value()?;
+        if map.next_key::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom("status object must have exactly one key"));
+        }
+
+        match key.as_str() {
+            "RetryingWithMessage" | "Retrying" => Ok(DownloadStatus::Retrying { message }),
+            "ErrorWithMessage" | "Error" => Ok(DownloadStatus::Error { message }),
+            _ => Err(de::Error::custom(format!("Unknown status: {}", key))),
+        }
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum DownloadStatus {
     Downloading,
     Initializing,
+    // Accepted by the server but waiting for a slot before it starts.
+    Queued,
     Retrying { message: Option<String> },
     Offline,
     Paused,
@@ -68,7 +203,17 @@ impl<'de> Deserialize<'de> for DownloadStatus {
     where
         D: Deserializer<'de>,
     {
-        deserialize_status(deserializer)
+        deserializer.deserialize_any(DownloadStatusVisitor)
+    }
+}
+
+// Serializes to the same plain string form that `parse_status` reads back.
+impl Serialize for DownloadStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
     }
 }
 
@@ -77,6 +222,7 @@ impl std::fmt::Display for DownloadStatus {
         match self {
             DownloadStatus::Downloading => write!(f, "Downloading"),
             DownloadStatus::Initializing => write!(f, "Initializing"),
+            DownloadStatus::Queued => write!(f, "Queued"),
             DownloadStatus::Retrying { message } => match message {
                 Some(msg) => write!(f, "Retrying: {}", msg),
                 None => write!(f, "Retrying"),
@@ -94,331 +240,7789 @@ impl std::fmt::Display for DownloadStatus {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct Download {
-    #[serde(rename = "modelName")]
-    model_name: String,
-    status: DownloadStatus,
-    #[serde(rename = "startTime")]
-    start_time: DateTime<Utc>,
-    #[serde(rename = "lastStatusChange")]
-    last_status_change: DateTime<Utc>,
-    #[serde(rename = "retryCount")]
-    retry_count: u32,
+// Coarse grouping of statuses used for filtering and summaries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StatusCategory {
+    Active,
+    Queued,
+    Errored,
+    Paused,
+    Completed,
 }
 
-struct App {
-    downloader_url: String,
-    downloads: Vec<Download>,
-    list_state: ListState,
-    input_mode: InputMode,
-    input_buffer: String,
-    client: Client,
-    last_refresh: Instant,
+impl StatusCategory {
+    // Cycles All -> Active -> Errored -> Paused -> Completed -> Queued -> All, where `None` is
+    // "All".
+    fn next(filter: Option<StatusCategory>) -> Option<StatusCategory> {
+        match filter {
+            None => Some(StatusCategory::Active),
+            Some(StatusCategory::Active) => Some(StatusCategory::Errored),
+            Some(StatusCategory::Errored) => Some(StatusCategory::Paused),
+            Some(StatusCategory::Paused) => Some(StatusCategory::Completed),
+            Some(StatusCategory::Completed) => Some(StatusCategory::Queued),
+            Some(StatusCategory::Queued) => None,
+        }
+    }
 }
 
-#[derive(PartialEq, Eq, Clone)]
-enum InputMode {
-    Normal,
-    AddingDownload,
+impl std::fmt::Display for StatusCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StatusCategory::Active => write!(f, "Active"),
+            StatusCategory::Queued => write!(f, "Queued"),
+            StatusCategory::Errored => write!(f, "Errored"),
+            StatusCategory::Paused => write!(f, "Paused"),
+            StatusCategory::Completed => write!(f, "Completed"),
+        }
+    }
 }
 
-impl App {
-    fn new(downloader_url: String) -> Self {
-        let mut list_state = ListState::default();
-        list_state.select(Some(0));
-        
-        App {
-            downloader_url,
-            downloads: Vec::new(),
-            list_state,
-            input_mode: InputMode::Normal,
-            input_buffer: String::new(),
-            client: Client::new(),
-            last_refresh: Instant::now(),
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SortKey {
+    ModelName,
+    Status,
+    StartTime,
+    LastStatusChange,
+    RetryCount,
+}
+
+impl SortKey {
+    // Cycles through each key and back to `None`, which keeps the server's order.
+    fn next(sort: Option<SortKey>) -> Option<SortKey> {
+        match sort {
+            None => Some(SortKey::ModelName),
+            Some(SortKey::ModelName) => Some(SortKey::Status),
+            Some(SortKey::Status) => Some(SortKey::StartTime),
+            Some(SortKey::StartTime) => Some(SortKey::LastStatusChange),
+            Some(SortKey::LastStatusChange) => Some(SortKey::RetryCount),
+            Some(SortKey::RetryCount) => None,
         }
     }
 
-    async fn fetch_downloads(&mut self) -> Result<(), Box<dyn Error>> {
-        let url = format!("{}/downloads", self.downloader_url);
-        let response = self.client.get(&url).send().await?;
-
-        if response.status().is_success() {
-            let prev_selected = self.list_state.selected();
-            let mut downloads: Vec<Download> = response.json().await?;
-            
-            downloads.sort_by(|a, b| {
-                match (&a.status, &b.status) {
-                    (DownloadStatus::Offline, DownloadStatus::Offline) => std::cmp::Ordering::Equal,
-                    (DownloadStatus::Offline, _) => std::cmp::Ordering::Greater,
-                    (_, DownloadStatus::Offline) => std::cmp::Ordering::Less,
-                    _ => std::cmp::Ordering::Equal,
-                }
-            });
-            
-            self.downloads = downloads;
-            self.last_refresh = Instant::now();
-            
-            if !self.downloads.is_empty() {
-                let selected = prev_selected.unwrap_or(0).min(self.downloads.len() - 1);
-                self.list_state.select(Some(selected));
-            } else {
-                self.list_state.select(None);
-            }
-            Ok(())
-        } else {
-            Err(format!("Failed to fetch downloads: {}", response.status()).into())
+    fn compare(&self, a: &Download, b: &Download) -> std::cmp::Ordering {
+        match self {
+            SortKey::ModelName => a.model_name.cmp(&b.model_name),
+            SortKey::Status => a.status.sort_rank().cmp(&b.status.sort_rank()),
+            SortKey::StartTime => a.start_time.cmp(&b.start_time),
+            SortKey::LastStatusChange => a.last_status_change.cmp(&b.last_status_change),
+            SortKey::RetryCount => a.retry_count.cmp(&b.retry_count),
         }
     }
+}
 
-    async fn add_download(&mut self, url: String) -> Result<(), Box<dyn Error>> {
-        let add_url = format!("{}/downloads", self.downloader_url);
-        let response = self
-            .client
-            .post(&add_url)
-            .json(&serde_json::json!({"url": url}))
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            self.fetch_downloads().await?;
-            Ok(())
-        } else {
-            Err(format!("Failed to add download: {}", response.status()).into())
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SortKey::ModelName => write!(f, "Model"),
+            SortKey::Status => write!(f, "Status"),
+            SortKey::StartTime => write!(f, "Start Time"),
+            SortKey::LastStatusChange => write!(f, "Last Change"),
+            SortKey::RetryCount => write!(f, "Retries"),
         }
     }
+}
 
-    async fn control_download(&self, model_name: &str, action: &str) -> Result<(), Box<dyn Error>> {
-        let control_url = format!("{}/downloads/{}/{}", self.downloader_url, model_name, action);
-        let response = self.client.post(&control_url).send().await?;
+// How densely the interface is drawn. Compact drops the borders and abbreviates statuses so
+// more rows fit in a small pane; selection and everything else is shared between the two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DisplayMode {
+    Normal,
+    Compact,
+}
 
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(format!("Failed to {} download: {}", action, response.status()).into())
+impl DisplayMode {
+    fn toggled(self) -> DisplayMode {
+        match self {
+            DisplayMode::Normal => DisplayMode::Compact,
+            DisplayMode::Compact => DisplayMode::Normal,
         }
     }
 
-    fn select_next(&mut self) {
-        if self.downloads.is_empty() {
-            self.list_state.select(None);
-            return;
+    // Borders drawn around the download list.
+    fn borders(self) -> Borders {
+        match self {
+            DisplayMode::Normal => Borders::ALL,
+            DisplayMode::Compact => Borders::NONE,
         }
-
-        let i = match self.list_state.selected() {
-            Some(i) => (i + 1).min(self.downloads.len() - 1),
-            None => 0,
-        };
-        self.list_state.select(Some(i));
     }
 
-    fn select_previous(&mut self) {
-        if self.downloads.is_empty() {
-            self.list_state.select(None);
-            return;
+    fn shortcuts_height(self) -> u16 {
+        match self {
+            DisplayMode::Normal => 3,
+            DisplayMode::Compact => 1,
         }
-
-        let i = match self.list_state.selected() {
-            Some(i) => i.saturating_sub(1),
-            None => 0,
-        };
-        self.list_state.select(Some(i));
     }
 
-    fn selected_model_name(&self) -> Option<&str> {
-        self.list_state
-            .selected()
-            .map(|i| self.downloads[i].model_name.as_str())
+    fn retries_label(self, retries: u32) -> String {
+        match self {
+            DisplayMode::Normal => format!(" (retries: {})", retries),
+            DisplayMode::Compact => format!(" r{}", retries),
+        }
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let downloader_url = env::args()
-        .nth(1)
-        .unwrap_or_else(|| {
-            env::var("DOWNLOADER_URL").unwrap_or_else(|_| "http://localhost:8080".to_string())
-        });
+impl DownloadStatus {
+    // Position of the variant in declaration order, used when sorting by status.
+    fn sort_rank(&self) -> u8 {
+        match self {
+            DownloadStatus::Downloading => 0,
+            DownloadStatus::Initializing => 1,
+            DownloadStatus::Queued => 2,
+            DownloadStatus::Retrying { .. } => 3,
+            DownloadStatus::Offline => 4,
+            DownloadStatus::Paused => 5,
+            DownloadStatus::PausedForExclusiveShow => 6,
+            DownloadStatus::PausedForTicketShow => 7,
+            DownloadStatus::Error { .. } => 8,
+            DownloadStatus::Completed => 9,
+        }
+    }
 
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    fn category(&self) -> StatusCategory {
+        match self {
+            DownloadStatus::Downloading
+            | DownloadStatus::Initializing
+            | DownloadStatus::Retrying { .. } => StatusCategory::Active,
+            DownloadStatus::Queued => StatusCategory::Queued,
+            DownloadStatus::Offline | DownloadStatus::Error { .. } => StatusCategory::Errored,
+            DownloadStatus::Paused
+            | DownloadStatus::PausedForExclusiveShow
+            | DownloadStatus::PausedForTicketShow => StatusCategory::Paused,
+            DownloadStatus::Completed => StatusCategory::Completed,
+        }
+    }
 
-    let app = Arc::new(Mutex::new(App::new(downloader_url)));
+    // Short form for the compact display.
+    fn abbreviation(&self) -> &'static str {
+        match self {
+            DownloadStatus::Downloading => "DL",
+            DownloadStatus::Initializing => "INIT",
+            DownloadStatus::Queued => "QUEUE",
+            DownloadStatus::Retrying { .. } => "RETRY",
+            DownloadStatus::Offline => "OFF",
+            DownloadStatus::Paused => "PAUSE",
+            DownloadStatus::PausedForExclusiveShow | DownloadStatus::PausedForTicketShow => "SHOW",
+            DownloadStatus::Error { .. } => "ERR",
+            DownloadStatus::Completed => "DONE",
+        }
+    }
 
-    let app_clone = app.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(3));
-        loop {
-            interval.tick().await;
-            let mut app = app_clone.lock().await;
-            if let Err(e) = app.fetch_downloads().await {
-                eprintln!("Error fetching downloads: {}", e);
+    // The server's explanation carried by an error or retry, if any.
+    fn message(&self) -> Option<&str> {
+        match self {
+            DownloadStatus::Error { message } | DownloadStatus::Retrying { message } => {
+                message.as_deref()
             }
+            _ => None,
         }
-    });
+    }
 
-    {
-        let mut app = app.lock().await;
-        app.fetch_downloads().await?;
+    // Pauses the downloader applies on its own while a show is running.
+    fn is_show_pause(&self) -> bool {
+        matches!(
+            self,
+            DownloadStatus::PausedForExclusiveShow | DownloadStatus::PausedForTicketShow
+        )
     }
+}
 
-    let res = run_app(&mut terminal, app).await;
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Download {
+    #[serde(rename = "modelName")]
+    model_name: String,
+    status: DownloadStatus,
+    #[serde(rename = "startTime")]
+    start_time: DateTime<Utc>,
+    #[serde(rename = "lastStatusChange")]
+    last_status_change: DateTime<Utc>,
+    #[serde(rename = "retryCount")]
+    retry_count: u32,
+    // The URL the download was created from, for servers that report it.
+    #[serde(rename = "sourceUrl", default, skip_serializing_if = "Option::is_none")]
+    source_url: Option<String>,
+    // Byte progress, for servers that report it.
+    #[serde(rename = "bytesDownloaded", default, skip_serializing_if = "Option::is_none")]
+    bytes_downloaded: Option<u64>,
+    #[serde(rename = "totalBytes", default, skip_serializing_if = "Option::is_none")]
+    total_bytes: Option<u64>,
+    // Place in the server's queue while queued, 0 being next to start.
+    #[serde(rename = "queuePosition", default, skip_serializing_if = "Option::is_none")]
+    queue_position: Option<u32>,
+    // Which of the downloads the server listed under this model name this is, from 0. Names
+    // should be unique, but a server that repeats one mustn't make its rows trade places.
+    #[serde(skip)]
+    duplicate: usize,
+}
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+impl Download {
+    // The model name, with `#2`, `#3`… after downloads that repeat an earlier one's name.
+    fn display_name(&self) -> String {
+        numbered_name(&self.model_name, self.duplicate)
+    }
 
-    res
+    fn key(&self) -> DownloadKey {
+        (self.model_name.clone(), self.duplicate)
+    }
 }
 
-async fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
-    app: Arc<Mutex<App>>,
-) -> Result<(), Box<dyn Error>> {
-    loop {
-        {
-            let mut app = app.lock().await;
-            terminal.draw(|f| ui(f, &mut app))?;
-        }
+// What tells downloads apart: the model name, and which of the downloads listed under it this
+// is. Everything remembered about a download is keyed on it, so repeated names don't mix.
+type DownloadKey = (String, usize);
 
-        if let Event::Key(key) = event::read()? {
-            let mut app = app.lock().await;
+// The models to send requests for `keys` to. Requests name the model, so there's one per name
+// however many of its downloads are in `keys`.
+fn model_names(keys: &[DownloadKey]) -> Vec<String> {
+    let mut models: Vec<String> = keys.iter().map(|(model, _)| model.clone()).collect();
+    models.sort();
+    models.dedup();
+    models
+}
 
-            match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Char('a') => {
-                        app.input_mode = InputMode::AddingDownload;
-                    }
-                    KeyCode::Char('s') => {
-                        if let Some(model_name) = app.selected_model_name() {
-                            let model_name = model_name.to_string();
-                            if let Err(e) = app.control_download(&model_name, "stop").await {
-                                eprintln!("Error stopping download: {}", e);
-                            }
-                            app.fetch_downloads().await?;
-                        }
-                    }
-                    KeyCode::Char('r') => {
-                        if let Some(model_name) = app.selected_model_name() {
-                            let model_name = model_name.to_string();
-                            if let Err(e) = app.control_download(&model_name, "restart").await {
-                                eprintln!("Error restarting download: {}", e);
-                            }
-                            app.fetch_downloads().await?;
-                        }
-                    }
-                    KeyCode::Char('p') => {
-                        if let Some(model_name) = app.selected_model_name() {
-                            let model_name = model_name.to_string();
-                            if let Err(e) = app.control_download(&model_name, "pause").await {
-                                eprintln!("Error pausing download: {}", e);
-                            }
-                            app.fetch_downloads().await?;
-                        }
-                    }
-                    KeyCode::Down | KeyCode::Char('j') => app.select_next(),
-                    KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
-                    _ => {}
-                },
-                InputMode::AddingDownload => match key.code {
-                    KeyCode::Enter => {
-                        let url = app.input_buffer.clone();
-                        app.input_buffer.clear();
-                        app.input_mode = InputMode::Normal;
-                        if !url.is_empty() {
-                            app.add_download(url).await?;
-                        }
-                    }
-                    KeyCode::Char(c) => app.input_buffer.push(c),
-                    KeyCode::Backspace => {
-                        app.input_buffer.pop();
-                    }
-                    KeyCode::Esc => {
-                        app.input_mode = InputMode::Normal;
-                        app.input_buffer.clear();
-                    }
-                    _ => {}
-                },
-            }
+fn numbered_name(model_name: &str, duplicate: usize) -> String {
+    match duplicate {
+        0 => model_name.to_string(),
+        n => format!("{} #{}", model_name, n + 1),
+    }
+}
+
+// Numbers downloads that share a model name in the order they're listed, returning the names
+// listed more than once.
+fn number_duplicates(downloads: &mut [Download]) -> BTreeSet<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut duplicated = BTreeSet::new();
+    for download in downloads {
+        let count = seen.entry(download.model_name.clone()).or_default();
+        download.duplicate = *count;
+        if *count > 0 {
+            duplicated.insert(download.model_name.clone());
         }
+        *count += 1;
     }
+    duplicated
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
-        .split(f.size());
+// An entry in a model's history.
+#[derive(Clone, Debug, PartialEq)]
+enum HistoryEvent {
+    Status(DownloadStatus),
+    // The retry count went down, which means the server started the download over.
+    Restarted,
+}
 
-    let items: Vec<ListItem> = if app.downloads.is_empty() {
-        vec![ListItem::new("No downloads available")]
-    } else {
-        app.downloads
-            .iter()
-            .map(|download| {
-                let time_since_last_change = Utc::now() - download.last_status_change;
-                let time_str = if time_since_last_change.num_seconds() < 60 {
-                    format!("{}s", time_since_last_change.num_seconds())
-                } else {
-                    format!("{}m", time_since_last_change.num_minutes())
-                };
+// A change in a model's status between one list from the server and the next.
+struct Transition {
+    model_name: String,
+    duplicate: usize,
+    old_status: DownloadStatus,
+    new_status: DownloadStatus,
+    retry_count: u32,
+}
 
-                ListItem::new(vec![Spans::from(vec![
-                    Span::styled(
-                        format!("{} ", download.model_name),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ),
-                    Span::raw(format!(
-                        "Status: {}, Last Change: {}",
-                        download.status, time_str
-                    )),
-                ])])
+// Compares a fresh list against the previous one. Models that are new to the list have no
+// previous status, so they don't count as transitions.
+fn status_transitions(previous: &[Download], current: &[Download]) -> Vec<Transition> {
+    current
+        .iter()
+        .filter_map(|download| {
+            let old = previous.iter().find(|d| {
+                d.model_name == download.model_name && d.duplicate == download.duplicate
+            })?;
+            (old.status != download.status).then(|| Transition {
+                model_name: download.model_name.clone(),
+                duplicate: download.duplicate,
+                old_status: old.status.clone(),
+                new_status: download.status.clone(),
+                retry_count: download.retry_count,
             })
-            .collect()
+        })
+        .collect()
+}
+
+// A line of the download list: a download, or the header of a group of downloads whose model
+// names share a prefix. `D` is a borrowed download in the app and an owned one in snapshots.
+#[derive(Clone, Debug)]
+enum ListRow<D> {
+    Group(GroupRow),
+    Download { download: D, in_group: bool },
+}
+
+impl<D> ListRow<D> {
+    fn download(&self) -> Option<&D> {
+        match self {
+            ListRow::Download { download, .. } => Some(download),
+            ListRow::Group(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct GroupRow {
+    name: String,
+    expanded: bool,
+    len: usize,
+    // How many of the group's visible downloads fall in each category.
+    counts: Vec<(StatusCategory, usize)>,
+}
+
+// The part of a model name before the first `separator`, if there is one.
+fn group_prefix<'a>(model_name: &'a str, separator: &str) -> Option<&'a str> {
+    let (prefix, _) = model_name.split_once(separator)?;
+    (!prefix.is_empty()).then_some(prefix)
+}
+
+// Lays out the visible downloads as list rows. Without a separator every download is its own
+// row; with one, downloads sharing a prefix are gathered under a header at the position of the
+// first of them, and listed below it only while the group is expanded. A prefix only one
+// visible download has isn't worth a group.
+fn list_rows<'a>(
+    downloads: Vec<&'a Download>,
+    separator: Option<&str>,
+    expanded: &HashSet<String>,
+) -> Vec<ListRow<&'a Download>> {
+    let ungrouped = |download| ListRow::Download {
+        download,
+        in_group: false,
+    };
+    let Some(separator) = separator else {
+        return downloads.into_iter().map(ungrouped).collect();
     };
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Downloads"))
-        .highlight_style(
-            Style::default()
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-        );
+    let mut groups: HashMap<&str, Vec<&Download>> = HashMap::new();
+    for download in &downloads {
+        if let Some(prefix) = group_prefix(&download.model_name, separator) {
+            groups.entry(prefix).or_default().push(download);
+        }
+    }
+    groups.retain(|_, members| members.len() > 1);
+    let grouped: HashSet<&str> = groups.keys().copied().collect();
+
+    let mut rows = Vec::new();
+    for download in &downloads {
+        let prefix = group_prefix(&download.model_name, separator);
+        let Some(prefix) = prefix.filter(|prefix| grouped.contains(prefix)) else {
+            rows.push(ungrouped(download));
+            continue;
+        };
+        // Later members were already placed under the header.
+        let Some(members) = groups.remove(prefix) else {
+            continue;
+        };
+        let is_expanded = expanded.contains(prefix);
+        rows.push(ListRow::Group(GroupRow {
+            name: prefix.to_string(),
+            expanded: is_expanded,
+            len: members.len(),
+            counts: category_counts(members.iter().copied()),
+        }));
+        if is_expanded {
+            rows.extend(members.into_iter().map(|download| ListRow::Download {
+                download,
+                in_group: true,
+            }));
+        }
+    }
+    rows
+}
 
-    f.render_stateful_widget(list, chunks[0], &mut app.list_state);
+// The last byte count seen for a model, used to estimate its transfer rate.
+struct ByteSample {
+    bytes: u64,
+    at: Instant,
+    // Bytes per second between the previous two samples, once there have been two.
+    rate: Option<f64>,
+}
+
+struct App {
+    // Named servers to switch between, and which of them `client` points at.
+    servers: Vec<(String, String)>,
+    active_server: Option<usize>,
+    // Woken on every server switch, so an open event stream can move to the new server.
+    server_changed: Arc<Notify>,
+    refresh_interval: Duration,
+    // Age at which the list is flagged as stale.
+    stale_after: Duration,
+    expected_duration: Duration,
+    downloads: Vec<Download>,
+    // Model names the server last listed more than once, already warned about.
+    duplicated_names: BTreeSet<String>,
+    // Selection is tracked by model name so it survives the list being reordered on refresh,
+    // along with which of the downloads sharing that name it is.
+    selected_model: Option<String>,
+    selected_duplicate: usize,
+    // The group whose header is highlighted; at most one of this and `selected_model` is set.
+    selected_group: Option<String>,
+    // Splits model names into a group prefix and the rest, when grouping is on.
+    group_separator: Option<String>,
+    expanded_groups: HashSet<String>,
+    // Downloads marked for batch actions.
+    marked: HashSet<DownloadKey>,
+    // Recent status transitions per download, oldest first.
+    history: HashMap<DownloadKey, VecDeque<(DateTime<Utc>, HistoryEvent)>>,
+    // When each download was last seen restarted by the server.
+    restarts: HashMap<DownloadKey, DateTime<Utc>>,
+    detail_scroll: u16,
+    // Scroll position of the detail text, for status messages too long to fit, and how far
+    // it could scroll at the last draw.
+    message_scroll: u16,
+    message_overflow: Arc<AtomicU16>,
+    // Action outcomes, fetch errors and connection changes, oldest first.
+    log: VecDeque<LogEntry>,
+    log_scroll: u16,
+    // The download log being tailed, and the task streaming it in, while the pane is open.
+    server_log: Option<ServerLog>,
+    server_log_task: Option<JoinHandle<()>>,
+    list_state: ListState,
+    input_mode: InputMode,
+    input_buffer: String,
+    // Byte offset of the cursor in `input_buffer`, always on a character boundary.
+    input_cursor: usize,
+    // Validation message shown in the input box when the typed URL was rejected.
+    input_error: Option<String>,
+    // Values of the add form's fields other than the focused one, which is in `input_buffer`.
+    add_form: [String; 4],
+    filter_query: String,
+    status_filter: Option<StatusCategory>,
+    sort_key: Option<SortKey>,
+    sort_descending: bool,
+    display_mode: DisplayMode,
+    // The configured layout for list rows, replacing the built-in one.
+    row_template: Option<RowTemplate>,
+    time_columns: Vec<TimeColumn>,
+    // Whether the list shows when things happened, as local clock times, instead of how long ago.
+    absolute_times: bool,
+    timestamp_format: String,
+    // Whether moving past either end of the list continues from the other end.
+    wrap_navigation: bool,
+    // Width of each list column on wide terminals; 0 keeps a single column.
+    column_width: u16,
+    client: DownloaderClient,
+    stream_client: Client,
+    // Requests currently running. Shared with the render loop so it can be read while another
+    // task holds the app lock for the duration of a request.
+    in_flight: Arc<AtomicUsize>,
+    // The batch action running now, if any, shared with the render loop like `in_flight`
+    // since the app stays locked until the whole batch is done.
+    batch: Arc<std::sync::Mutex<Option<BatchProgress>>>,
+    // How many requests a batch may have in flight at once.
+    max_concurrency: usize,
+    // Time of the last successful fetch, if there has been one.
+    last_refresh: Option<Instant>,
+    // Freezes the list: the background task stops fetching and ignores pushed events.
+    paused_refresh: bool,
+    connection_ok: bool,
+    last_error: Option<String>,
+    // Fetches that have failed in a row, which stretch the time between background refreshes.
+    failure_streak: u32,
+    // What the server's optional `/version` and `/health` endpoints last reported; `None`
+    // when the endpoint is missing. `health_checked` is when they were last queried.
+    server_version: Option<String>,
+    server_health: Option<bool>,
+    health_checked: Option<Instant>,
+    // The optional features the server advertises, checked alongside its health; everything
+    // counts as supported until it says otherwise.
+    capabilities: Capabilities,
+    banner: Option<Banner>,
+    action_retry: RetryPolicy,
+    // How long after an action is sent to a download further ones for it are ignored, and
+    // when each download was last sent one.
+    action_cooldown: Duration,
+    last_actions: HashMap<DownloadKey, Instant>,
+    confirm_actions: bool,
+    confirm_quit: bool,
+    auto_restart: Option<AutoRestart>,
+    // Automatic restarts issued per model since it was last seen healthy.
+    auto_restart_attempts: HashMap<String, u32>,
+    // How long a show pause may last before it's resumed, if auto-resume is on.
+    show_resume_timeout: Option<Duration>,
+    // Models already resumed during their current show pause, so each pause gets one attempt.
+    show_resumes: HashSet<String>,
+    auto_prune: Option<AutoPrune>,
+    // Completed models already pruned (or reported, in a prune dry run), so each completion is
+    // handled once.
+    pruned: HashSet<String>,
+    export_format: export::ExportFormat,
+    export_dir: PathBuf,
+    // The most recent stop or delete and when it happened; only one level of undo is kept.
+    last_action: Option<(Action, Instant)>,
+    // URLs of downloads added from this session, used when the server doesn't report a
+    // download's source URL.
+    source_urls: HashMap<String, String>,
+    keymap: KeyMap,
+    byte_samples: HashMap<DownloadKey, ByteSample>,
+    dry_run: bool,
+    // In a dry run, the simulated state of each model a rehearsed request touched, or `None`
+    // once deleted. Laid over every list received from the server.
+    rehearsals: HashMap<String, Option<Download>>,
+    // Freeform notes keyed by model name, kept even while the model isn't listed.
+    notes: BTreeMap<String, String>,
+    notes_path: Option<PathBuf>,
+    // How long past downloads took to complete, for estimating the running ones.
+    completions: eta::Completions,
+    completions_path: Option<PathBuf>,
+    // Where the sort and filters are saved on quit.
+    state_path: Option<PathBuf>,
+    // Kinds of transition that raise a desktop notification; empty unless --notify is on.
+    notify_on: Vec<NotifyOn>,
+    // The kind last notified for each download, so one stuck in a state notifies once.
+    notified: HashMap<DownloadKey, NotifyOn>,
+    events: Option<EventSink>,
+    theme: Theme,
+}
 
-    let shortcuts = Paragraph::new(Text::from(Spans::from(vec![
-        Span::raw("[A]dd Download "),
-        Span::raw("[S]top Download "),
-        Span::raw("[R]estart Download "),
-        Span::raw("[P]ause Download "),
-        Span::raw("[Q]uit"),
-    ])))
-    .block(Block::default().borders(Borders::ALL).title("Shortcuts"));
+// A point-in-time copy of the state `ui` renders from.
+struct AppSnapshot {
+    // The visible rows, already filtered, sorted and grouped into display order.
+    rows: Vec<ListRow<Download>>,
+    total_downloads: usize,
+    // How many of all downloads, visible or not, fall in each category.
+    category_counts: Vec<(StatusCategory, usize)>,
+    selected: Option<usize>,
+    selected_download: Option<Download>,
+    marked: HashSet<DownloadKey>,
+    selected_history: Vec<(DateTime<Utc>, HistoryEvent)>,
+    restarts: HashMap<DownloadKey, DateTime<Utc>>,
+    detail_scroll: u16,
+    message_scroll: u16,
+    // Written while drawing, so the app knows how far the detail text can scroll.
+    message_overflow: Arc<AtomicU16>,
+    // Only filled in while the log is open.
+    log: Vec<LogEntry>,
+    log_scroll: u16,
+    // Only filled while the server log pane is open.
+    server_log: Option<ServerLog>,
+    // Only filled in while the stats view is open.
+    stats: Option<FleetStats>,
+    input_mode: InputMode,
+    input_buffer: String,
+    input_cursor: usize,
+    // Validation message shown in the input box when the typed URL was rejected.
+    input_error: Option<String>,
+    // The add form's values by field; the focused one is being edited in `input_buffer`.
+    add_form: [String; 4],
+    filter_query: String,
+    status_filter: Option<StatusCategory>,
+    sort_key: Option<SortKey>,
+    sort_descending: bool,
+    display_mode: DisplayMode,
+    row_template: Option<RowTemplate>,
+    time_columns: Vec<TimeColumn>,
+    absolute_times: bool,
+    timestamp_format: String,
+    column_width: u16,
+    downloader_url: String,
+    // Name of the active server, when servers were given by name.
+    server_name: Option<String>,
+    server_version: Option<String>,
+    server_health: Option<bool>,
+    capabilities: Capabilities,
+    // Whether any request is running, and which spinner frame to draw if so.
+    in_flight: bool,
+    batch: Option<BatchProgress>,
+    frame: usize,
+    connection_ok: bool,
+    last_error: Option<String>,
+    // The wait between background refreshes, longer than the refresh interval while backing off.
+    poll_delay: Duration,
+    failure_streak: u32,
+    last_refresh: Option<Instant>,
+    paused_refresh: bool,
+    stale_after: Duration,
+    banner: Option<Banner>,
+    expected_duration: Duration,
+    keymap: KeyMap,
+    // Bytes per second for each download with a known transfer rate.
+    rates: HashMap<DownloadKey, f64>,
+    // How long each downloading model is expected to take in total, where history allows.
+    expected_completions: HashMap<String, Duration>,
+    dry_run: bool,
+    notes: BTreeMap<String, String>,
+    theme: Theme,
+}
 
-    f.render_widget(shortcuts, chunks[1]);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ControlAction {
+    Stop,
+    Pause,
+    Restart,
+}
 
-    if app.input_mode == InputMode::AddingDownload {
-        let input_rect = Rect::new(chunks[0].x + 1, chunks[0].y + 1, chunks[0].width - 2, 3);
-        
-        // Clear the area to remove underlying content
-        f.render_widget(Clear, input_rect);
+impl ControlAction {
+    // The final path segment of the control endpoint.
+    fn endpoint(&self) -> &'static str {
+        match self {
+            ControlAction::Stop => "stop",
+            ControlAction::Pause => "pause",
+            ControlAction::Restart => "restart",
+        }
+    }
 
-        // Render the input paragraph with a solid background
-        let input = Paragraph::new(app.input_buffer.as_ref())
-            // .style(Style::default().fg(Color::White).bg(Color::Black))
-            .block(Block::default()
-                .borders(Borders::ALL)
-                .title("Enter URL")
-                .border_style(Style::default().fg(Color::White)));
-        f.render_widget(input, input_rect);
+    // The status a download is expected to reach once the action takes effect.
+    fn resulting_status(&self) -> DownloadStatus {
+        match self {
+            ControlAction::Stop => DownloadStatus::Offline,
+            ControlAction::Pause => DownloadStatus::Paused,
+            ControlAction::Restart => DownloadStatus::Initializing,
+        }
     }
-}
\ No newline at end of file
+
+    fn progressive(&self) -> &'static str {
+        match self {
+            ControlAction::Stop => "stopping",
+            ControlAction::Pause => "pausing",
+            ControlAction::Restart => "restarting",
+        }
+    }
+}
+
+impl std::fmt::Display for ControlAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ControlAction::Stop => write!(f, "Stop"),
+            ControlAction::Pause => write!(f, "Pause"),
+            ControlAction::Restart => write!(f, "Restart"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum AddOutcome {
+    // The name of the model created, when the server reported it or it could be worked out.
+    Added(Option<String>),
+    // The URL belongs to a model that was already in the list.
+    AlreadyTracking(String),
+}
+
+// A failed fetch as text, which unlike the error itself can be held across an await by a task
+// that only locks the app once the server has answered.
+struct FetchFailure {
+    error: String,
+    // The response, when the server answered with something other than a download list.
+    body: Option<String>,
+}
+
+impl From<Box<dyn Error>> for FetchFailure {
+    fn from(e: Box<dyn Error>) -> Self {
+        let body = e.downcast_ref::<UnexpectedBody>().map(|unexpected| unexpected.body.clone());
+        FetchFailure {
+            error: e.to_string(),
+            body,
+        }
+    }
+}
+
+// What the health, version and capabilities endpoints said.
+struct HealthReport {
+    health: Lookup<bool>,
+    version: Lookup<Option<String>>,
+    capabilities: Lookup<Capabilities>,
+}
+
+async fn health_report(client: &DownloaderClient) -> HealthReport {
+    HealthReport {
+        health: client.health().await,
+        version: client.version().await,
+        capabilities: client.capabilities().await,
+    }
+}
+
+// Counts a request as in flight for as long as the guard is alive.
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn start(counter: &Arc<AtomicUsize>) -> InFlight {
+        counter.fetch_add(1, Ordering::SeqCst);
+        InFlight(counter.clone())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// How far the batch action under way has got, e.g. "Restarting 12/40…" in the banner.
+#[derive(Clone, Debug, PartialEq)]
+struct BatchProgress {
+    verb: &'static str,
+    done: usize,
+    total: usize,
+}
+
+// Settings for the watchdog that restarts downloads stuck in an error state.
+struct AutoRestart {
+    grace: Duration,
+    max_attempts: u32,
+}
+
+// Settings for removing downloads that have been completed for a while.
+struct AutoPrune {
+    after: Duration,
+    // Only log what would be removed.
+    dry_run: bool,
+}
+
+// Outcome of adding a list of URLs.
+#[derive(Debug, Default)]
+struct BatchSummary {
+    added: usize,
+    // URLs whose model was already in the list.
+    skipped: usize,
+    // Each rejected or failed URL with the reason.
+    failed: Vec<(String, String)>,
+}
+
+impl std::fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Added {}, skipped {}, failed {}",
+            self.added,
+            self.skipped,
+            self.failed.len()
+        )?;
+        if let Some((url, error)) = self.failed.first() {
+            write!(f, " ({}: {})", url, error)?;
+        }
+        Ok(())
+    }
+}
+
+// A destructive action that Ctrl+Z can reverse.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Action {
+    // The models that were stopped; undone by restarting them.
+    Stopped(Vec<String>),
+    // Undone by re-adding the download from its source URL, when that is known.
+    Deleted { model: String, url: Option<String> },
+}
+
+#[derive(Clone)]
+struct LogEntry {
+    at: DateTime<Local>,
+    message: String,
+    is_error: bool,
+}
+
+// The tail of one download's log on the server, as the server log pane shows it.
+#[derive(Clone, Debug, PartialEq)]
+struct ServerLog {
+    model: String,
+    lines: VecDeque<String>,
+    // How many lines up from the newest the pane is scrolled; at 0 it follows new lines.
+    back: usize,
+    // Why the stream stopped, once it has.
+    ended: Option<String>,
+}
+
+impl ServerLog {
+    fn new(model: String) -> ServerLog {
+        ServerLog {
+            model,
+            lines: VecDeque::new(),
+            back: 0,
+            ended: None,
+        }
+    }
+
+    // Appends lines as they arrive. A pane scrolled back stays on the lines it was showing.
+    fn push(&mut self, lines: Vec<String>) {
+        for line in lines {
+            if self.lines.len() == SERVER_LOG_LIMIT {
+                self.lines.pop_front();
+            } else if self.back > 0 {
+                self.back += 1;
+            }
+            self.lines.push_back(line);
+        }
+    }
+
+    fn scroll(&mut self, down: bool) {
+        self.back = if down {
+            self.back.saturating_sub(1)
+        } else {
+            (self.back + 1).min(self.lines.len().saturating_sub(1))
+        };
+    }
+}
+
+// Actions applied to every download in a given state at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FleetAction {
+    PauseAll,
+    ResumeAll,
+    // Recovery after an outage: restarts everything that's errored or offline.
+    RestartErrored,
+    // Deletes every completed download, the manual counterpart to auto-prune.
+    ClearCompleted,
+}
+
+impl FleetAction {
+    // The control request sent to each target; `None` for clearing, which deletes them.
+    fn action(self) -> Option<ControlAction> {
+        match self {
+            FleetAction::PauseAll => Some(ControlAction::Pause),
+            // The API has no separate resume; restarting a paused download resumes it.
+            FleetAction::ResumeAll | FleetAction::RestartErrored => Some(ControlAction::Restart),
+            FleetAction::ClearCompleted => None,
+        }
+    }
+
+    // Show pauses are left alone when resuming; the server lifts those itself.
+    fn applies_to(self, status: &DownloadStatus) -> bool {
+        match self {
+            FleetAction::PauseAll => {
+                matches!(status, DownloadStatus::Downloading | DownloadStatus::Initializing)
+            }
+            FleetAction::ResumeAll => *status == DownloadStatus::Paused,
+            FleetAction::RestartErrored => status.category() == StatusCategory::Errored,
+            FleetAction::ClearCompleted => *status == DownloadStatus::Completed,
+        }
+    }
+
+    fn noun(self) -> &'static str {
+        match self {
+            FleetAction::PauseAll => "active",
+            FleetAction::ResumeAll => "paused",
+            FleetAction::RestartErrored => "errored or offline",
+            FleetAction::ClearCompleted => "completed",
+        }
+    }
+}
+
+impl std::fmt::Display for FleetAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FleetAction::PauseAll => write!(f, "Pause all"),
+            FleetAction::ResumeAll => write!(f, "Resume all"),
+            FleetAction::RestartErrored => write!(f, "Restart all"),
+            FleetAction::ClearCompleted => write!(f, "Clear"),
+        }
+    }
+}
+
+// A transient message reporting the outcome of an action.
+#[derive(Clone)]
+struct Banner {
+    message: String,
+    is_error: bool,
+    shown_at: Instant,
+}
+
+#[derive(Clone, Copy)]
+struct RetryPolicy {
+    retries: u32,
+    base_delay: Duration,
+}
+
+impl RetryPolicy {
+    // Doubles the delay after each failed attempt: base, 2×base, 4×base, ...
+    fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay * 2u32.saturating_pow(attempt)
+    }
+}
+
+// Connection failures and 5xx responses are retried with exponential backoff; 4xx
+// responses fail immediately since repeating the request won't change the outcome.
+async fn control_with_retries(
+    client: &DownloaderClient,
+    retry: &RetryPolicy,
+    model_name: &str,
+    action: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut attempt = 0;
+    loop {
+        let ControlFailure { error, retriable } =
+            match client.control_download(model_name, action).await {
+                Ok(()) => return Ok(()),
+                Err(failure) => failure,
+            };
+
+        if !retriable || attempt >= retry.retries {
+            return Err(error);
+        }
+        // Dropped before sleeping so the future stays `Send` for the background task.
+        drop(error);
+        tokio::time::sleep(retry.delay(attempt)).await;
+        attempt += 1;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum InputMode {
+    Normal,
+    // The add form, with `focus` being the field the input box is editing.
+    AddForm { focus: AddField },
+    ConfirmDelete { model: String },
+    // Stop/restart/pause aimed at an active download, awaiting a yes/no.
+    Confirm { action: ControlAction, model: String },
+    ConfirmFleet { action: FleetAction, count: usize },
+    ConfirmQuit { active: usize },
+    EditingNote { model: String },
+    // Typing a new server URL to connect to.
+    EditingServer,
+    Detail,
+    // The full-screen view of one model, which stays on it whatever happens to the selection.
+    Watch { model: String, duplicate: usize },
+    Filter,
+    Log,
+    // The server's log for the download in `App::server_log`.
+    ServerLog,
+    Stats,
+    Help,
+}
+
+// The fields of the add form, in Tab order. Only the URL is required.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum AddField {
+    Url,
+    Priority,
+    OutputPath,
+    Quality,
+}
+
+impl AddField {
+    const ALL: [AddField; 4] = [
+        AddField::Url,
+        AddField::Priority,
+        AddField::OutputPath,
+        AddField::Quality,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            AddField::Url => "URL",
+            AddField::Priority => "Priority",
+            AddField::OutputPath => "Output path",
+            AddField::Quality => "Quality",
+        }
+    }
+
+    fn index(self) -> usize {
+        AddField::ALL.iter().position(|&f| f == self).unwrap_or(0)
+    }
+
+    // The field Tab (or BackTab, with `back`) moves to, wrapping at either end.
+    fn next(self, back: bool) -> AddField {
+        let len = AddField::ALL.len();
+        let step = if back { len - 1 } else { 1 };
+        AddField::ALL[(self.index() + step) % len]
+    }
+}
+
+impl App {
+    fn new(args: &cli::Args) -> Result<Self, Box<dyn Error>> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &args.headers {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        if let Some(token) = &args.auth_token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let ca_cert = match &args.ca_cert {
+            Some(path) => {
+                let pem = std::fs::read(path).map_err(|e| {
+                    format!("Could not read CA certificate {}: {}", path.display(), e)
+                })?;
+                let cert = Certificate::from_pem(&pem).map_err(|e| {
+                    format!("Invalid CA certificate {}: {}", path.display(), e)
+                })?;
+                Some(cert)
+            }
+            None => None,
+        };
+        let builder = || {
+            let mut builder = Client::builder()
+                .default_headers(headers.clone())
+                .connect_timeout(args.http_timeout.min(CONNECT_TIMEOUT))
+                .danger_accept_invalid_certs(args.insecure);
+            if let Some(cert) = &ca_cert {
+                builder = builder.add_root_certificate(cert.clone());
+            }
+            builder
+        };
+        // Without a timeout a hung server would block whichever task holds the app lock.
+        let client = builder().timeout(args.http_timeout).build()?;
+        // The event stream is long-lived, so it only bounds the time taken to connect.
+        let stream_client = builder().build()?;
+        // An unreadable state file only loses the saved view, so it's logged rather than fatal.
+        let (ui_state, state_error) = match args.state_file.as_deref().map(state::load) {
+            Some(Ok(state)) => (state, None),
+            Some(Err(e)) => (UiState::default(), Some(e)),
+            None => (UiState::default(), None),
+        };
+        // A bad notes file is left alone so its notes can be recovered; none are saved to it.
+        let (notes, notes_path, notes_error) = match args.notes_file.as_deref().map(notes::load) {
+            Some(Ok(notes)) => (notes, args.notes_file.clone(), None),
+            Some(Err(e)) => (BTreeMap::new(), None, Some(format!("{}; notes won't be saved", e))),
+            None => (BTreeMap::new(), None, None),
+        };
+        // The completion times only inform estimates, so a bad file starts them afresh.
+        let completions = args.completions_file.as_deref().map(eta::load);
+        let (completions, completions_error) = match completions {
+            Some(Ok(completions)) => (completions, None),
+            Some(Err(e)) => (eta::Completions::default(), Some(e)),
+            None => (eta::Completions::default(), None),
+        };
+
+        let mut app = App {
+            servers: args.servers.clone(),
+            active_server: (!args.servers.is_empty()).then_some(0),
+            server_changed: Arc::new(Notify::new()),
+            refresh_interval: args.refresh_interval,
+            stale_after: args.stale_after.unwrap_or(args.refresh_interval * 3),
+            expected_duration: args.expected_duration,
+            downloads: Vec::new(),
+            duplicated_names: BTreeSet::new(),
+            selected_model: None,
+            selected_duplicate: 0,
+            selected_group: None,
+            group_separator: args.group_separator.clone(),
+            expanded_groups: HashSet::new(),
+            marked: HashSet::new(),
+            history: HashMap::new(),
+            restarts: HashMap::new(),
+            detail_scroll: 0,
+            message_scroll: 0,
+            message_overflow: Arc::new(AtomicU16::new(0)),
+            log: VecDeque::new(),
+            log_scroll: 0,
+            server_log: None,
+            server_log_task: None,
+            list_state: ListState::default(),
+            input_mode: InputMode::Normal,
+            input_buffer: String::new(),
+            input_cursor: 0,
+            input_error: None,
+            add_form: Default::default(),
+            filter_query: ui_state.filter_query,
+            status_filter: ui_state.status_filter,
+            sort_key: ui_state.sort_key,
+            sort_descending: ui_state.sort_descending,
+            display_mode: if args.compact {
+                DisplayMode::Compact
+            } else {
+                DisplayMode::Normal
+            },
+            row_template: args.row_template.clone(),
+            time_columns: args.time_columns.clone(),
+            absolute_times: false,
+            timestamp_format: args.timestamp_format.clone(),
+            wrap_navigation: args.wrap_navigation,
+            column_width: args.column_width,
+            client: DownloaderClient::new(args.downloader_url.clone(), client),
+            stream_client,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            batch: Arc::default(),
+            max_concurrency: args.max_concurrency,
+            last_refresh: None,
+            paused_refresh: false,
+            connection_ok: true,
+            failure_streak: 0,
+            last_error: None,
+            server_version: None,
+            server_health: None,
+            health_checked: None,
+            capabilities: Capabilities::default(),
+            banner: None,
+            action_retry: RetryPolicy {
+                retries: args.action_retries,
+                base_delay: ACTION_RETRY_BASE_DELAY,
+            },
+            action_cooldown: args.action_cooldown,
+            last_actions: HashMap::new(),
+            confirm_actions: args.confirm_actions,
+            confirm_quit: args.confirm_quit,
+            auto_restart: args.auto_restart.then_some(AutoRestart {
+                grace: args.auto_restart_grace,
+                max_attempts: args.auto_restart_attempts,
+            }),
+            auto_restart_attempts: HashMap::new(),
+            show_resume_timeout: args.auto_resume_shows.then_some(args.show_resume_timeout),
+            show_resumes: HashSet::new(),
+            auto_prune: args.auto_prune_completed.map(|after| AutoPrune {
+                after,
+                dry_run: args.prune_dry_run,
+            }),
+            pruned: HashSet::new(),
+            export_format: args.export_format,
+            export_dir: args.export_dir.clone(),
+            last_action: None,
+            source_urls: HashMap::new(),
+            notes,
+            notes_path,
+            completions,
+            completions_path: args.completions_file.clone(),
+            state_path: args.state_file.clone(),
+            notify_on: if args.notify {
+                args.notify_on.clone()
+            } else {
+                Vec::new()
+            },
+            notified: HashMap::new(),
+            theme: args.theme,
+            events: match &args.emit_events {
+                Some(target) => Some(
+                    EventSink::open(target)
+                        .map_err(|e| format!("Could not open event output: {}", e))?,
+                ),
+                None => None,
+            },
+            keymap: args.keymap.clone(),
+            byte_samples: HashMap::new(),
+            dry_run: args.dry_run,
+            rehearsals: HashMap::new(),
+        };
+        if args.insecure {
+            app.log("TLS certificate verification is disabled (--insecure)".to_string(), true);
+        }
+        for error in [state_error, notes_error, completions_error].into_iter().flatten() {
+            app.log(error, true);
+        }
+        Ok(app)
+    }
+
+    // Refreshes the list, recording the outcome so the UI can show a disconnection banner.
+    async fn fetch_downloads(&mut self) -> Result<(), Box<dyn Error>> {
+        let fetched = self.request_downloads().await.map_err(FetchFailure::from);
+        self.receive_fetch(fetched).map_err(Into::into)
+    }
+
+    // Installs the list a fetch brought, or records the failure that stopped it. Returns the
+    // failure's message.
+    fn receive_fetch(
+        &mut self,
+        fetched: Result<Vec<Download>, FetchFailure>,
+    ) -> Result<(), String> {
+        let FetchFailure { error, body } = match fetched {
+            Ok(downloads) => {
+                self.receive_downloads(downloads);
+                return Ok(());
+            }
+            Err(failure) => failure,
+        };
+        // Repeated identical failures are logged once rather than every refresh.
+        let repeated = !self.connection_ok && self.last_error.as_ref() == Some(&error);
+        if self.connection_ok {
+            self.log(format!("Connection lost: {}", error), true);
+        } else if !repeated {
+            self.log(format!("Fetch failed: {}", error), true);
+        }
+        if let Some(body) = body.filter(|_| !repeated) {
+            self.log(format!("Response body:\n{}", body), false);
+        }
+        self.connection_ok = false;
+        self.last_error = Some(error.clone());
+        self.failure_streak = self.failure_streak.saturating_add(1);
+        Err(error)
+    }
+
+    // Installs a fresh list from the server, whether fetched or pushed, and clears any
+    // connection error.
+    fn receive_downloads(&mut self, downloads: Vec<Download>) {
+        if !self.connection_ok {
+            self.log("Connection restored".to_string(), false);
+        }
+        let downloads = self.with_rehearsals(downloads);
+        // Compared once the new list is numbered, so repeated names pair up with themselves.
+        let previous = self.downloads.clone();
+        self.set_downloads(downloads);
+        let transitions = status_transitions(&previous, &self.downloads);
+        self.record_completions(&transitions);
+        if let Some(events) = &mut self.events {
+            let now = Utc::now();
+            let written: std::io::Result<()> =
+                transitions.iter().try_for_each(|transition| events.write(transition, now));
+            if let Err(e) = written {
+                self.log(format!("Could not write status events: {}", e), true);
+            }
+        }
+        for (summary, body) in self.notifications(&transitions) {
+            if let Err(e) = notify::send(&summary, &body) {
+                self.log(format!("Could not show notification: {}", e), true);
+            }
+        }
+        self.last_refresh = Some(Instant::now());
+        self.connection_ok = true;
+        self.last_error = None;
+        self.failure_streak = 0;
+    }
+
+    // Adds the time taken by each download that just completed to the averages ETAs come
+    // from, and saves them.
+    fn record_completions(&mut self, transitions: &[Transition]) {
+        let mut recorded = false;
+        for transition in transitions {
+            if transition.new_status != DownloadStatus::Completed {
+                continue;
+            }
+            let model = &transition.model_name;
+            let Some(download) = self
+                .downloads
+                .iter()
+                .find(|d| &d.model_name == model && d.duplicate == transition.duplicate)
+            else {
+                continue;
+            };
+            let took = download.last_status_change - download.start_time;
+            self.completions.record(model, took.to_std().unwrap_or_default());
+            recorded = true;
+        }
+        if let Some(path) = self.completions_path.as_ref().filter(|_| recorded) {
+            if let Err(e) = eta::save(path, &self.completions) {
+                let path = path.display();
+                self.log(format!("Could not save completion times to {}: {}", path, e), true);
+            }
+        }
+    }
+
+    // How long the background refresh waits before its next fetch: the refresh interval,
+    // doubled for each failure after the first in the current streak, up to `BACKOFF_CAP`.
+    fn poll_delay(&self) -> Duration {
+        let doublings = self.failure_streak.saturating_sub(1).min(16);
+        let cap = BACKOFF_CAP.max(self.refresh_interval);
+        let delay = self.refresh_interval.checked_mul(2u32.pow(doublings));
+        delay.map_or(cap, |delay| delay.min(cap))
+    }
+
+    // Applies a pushed event, which carries either the full download list or a single
+    // updated download. A single update for a repeated model name goes to the download that
+    // started at the same time, as the name alone can't say which one it is.
+    fn apply_event(&mut self, data: &str) -> Result<(), serde_json::Error> {
+        let downloads = match serde_json::from_str::<Vec<Download>>(data) {
+            Ok(downloads) => downloads,
+            Err(_) => {
+                let update: Download = serde_json::from_str(data)?;
+                let mut downloads = self.downloads.clone();
+                let same_name = |d: &Download| d.model_name == update.model_name;
+                let index = match downloads.iter().filter(|d| same_name(d)).count() {
+                    1 => downloads.iter().position(same_name),
+                    _ => downloads
+                        .iter()
+                        .position(|d| same_name(d) && d.start_time == update.start_time),
+                };
+                match index {
+                    Some(i) => downloads[i] = update,
+                    None => downloads.push(update),
+                }
+                downloads
+            }
+        };
+        self.receive_downloads(downloads);
+        Ok(())
+    }
+
+    async fn request_downloads(&self) -> Result<Vec<Download>, Box<dyn Error>> {
+        let _in_flight = InFlight::start(&self.in_flight);
+        self.client.list_downloads().await
+    }
+
+    // Merges a fresh list into the current one by model name: rows still listed are updated in
+    // place, new models are appended and vanished ones dropped, so the list doesn't reshuffle
+    // from one refresh to the next.
+    fn set_downloads(&mut self, mut downloads: Vec<Download>) {
+        // Warned about once for each set of repeated names rather than on every refresh.
+        let duplicated = number_duplicates(&mut downloads);
+        if !duplicated.is_empty() && duplicated != self.duplicated_names {
+            let names: Vec<&str> = duplicated.iter().map(String::as_str).collect();
+            self.log(
+                format!(
+                    "The server listed {} more than once; actions on them reach whichever \
+                     one the server picks",
+                    names.join(", ")
+                ),
+                true,
+            );
+        }
+        self.duplicated_names = duplicated;
+        let positions: HashMap<(String, usize), usize> = downloads
+            .iter()
+            .enumerate()
+            .map(|(i, download)| ((download.model_name.clone(), download.duplicate), i))
+            .collect();
+        let mut fetched: Vec<Option<Download>> = downloads.into_iter().map(Some).collect();
+        let mut restarted = Vec::new();
+        self.downloads.retain_mut(|existing| {
+            let update = positions
+                .get(&(existing.model_name.clone(), existing.duplicate))
+                .and_then(|&i| fetched[i].take());
+            match update {
+                Some(update) => {
+                    if update.retry_count < existing.retry_count {
+                        restarted.push(update.key());
+                    }
+                    *existing = update;
+                    true
+                }
+                None => false,
+            }
+        });
+        self.downloads.extend(fetched.into_iter().flatten());
+
+        self.downloads.sort_by(|a, b| {
+            match (&a.status, &b.status) {
+                (DownloadStatus::Offline, DownloadStatus::Offline) => std::cmp::Ordering::Equal,
+                (DownloadStatus::Offline, _) => std::cmp::Ordering::Greater,
+                (_, DownloadStatus::Offline) => std::cmp::Ordering::Less,
+                _ => std::cmp::Ordering::Equal,
+            }
+        });
+
+        self.record_history();
+        self.record_restarts(restarted);
+        self.record_byte_samples();
+        let listed: HashSet<DownloadKey> = self.downloads.iter().map(Download::key).collect();
+        self.marked.retain(|key| listed.contains(key));
+        self.resolve_selection();
+    }
+
+    // Picks the transitions to notify about, as (summary, body) pairs. A model only notifies
+    // again once it has moved into a different kind of state.
+    fn notifications(&mut self, transitions: &[Transition]) -> Vec<(String, String)> {
+        let mut notifications = Vec::new();
+        for transition in transitions {
+            let key = (transition.model_name.clone(), transition.duplicate);
+            match NotifyOn::for_status(&transition.new_status) {
+                Some(kind) if self.notify_on.contains(&kind) => {
+                    if self.notified.insert(key, kind) != Some(kind) {
+                        let name = numbered_name(&transition.model_name, transition.duplicate);
+                        notifications.push((
+                            format!("{} is now {}", name, transition.new_status),
+                            format!("Previously {}", transition.old_status),
+                        ));
+                    }
+                }
+                _ => {
+                    self.notified.remove(&key);
+                }
+            }
+        }
+        let listed: HashSet<DownloadKey> = self.downloads.iter().map(Download::key).collect();
+        self.notified.retain(|key, _| listed.contains(key));
+        notifications
+    }
+
+    // Appends an entry to each model's history when its status differs from the last one
+    // recorded, and forgets models the server no longer reports.
+    fn record_history(&mut self) {
+        for download in &self.downloads {
+            let history = self.history.entry(download.key()).or_default();
+            let last_status = history.iter().rev().find_map(|(_, event)| match event {
+                HistoryEvent::Status(status) => Some(status),
+                HistoryEvent::Restarted => None,
+            });
+            if last_status != Some(&download.status) {
+                let event = HistoryEvent::Status(download.status.clone());
+                push_history(history, download.last_status_change, event);
+            }
+        }
+
+        let listed: HashSet<DownloadKey> = self.downloads.iter().map(Download::key).collect();
+        self.history.retain(|key, _| listed.contains(key));
+    }
+
+    // Notes when each of `restarted` was seen restarting, in its history and for the list's
+    // restart flag, and forgets restarts of models the server no longer reports.
+    fn record_restarts(&mut self, restarted: Vec<DownloadKey>) {
+        let now = Utc::now();
+        for key in restarted {
+            if let Some(history) = self.history.get_mut(&key) {
+                push_history(history, now, HistoryEvent::Restarted);
+            }
+            self.restarts.insert(key, now);
+        }
+        let listed: HashSet<DownloadKey> = self.downloads.iter().map(Download::key).collect();
+        self.restarts.retain(|key, _| listed.contains(key));
+    }
+
+    // Updates each model's transfer rate from the change in bytes since its last sample.
+    fn record_byte_samples(&mut self) {
+        let now = Instant::now();
+        for download in &self.downloads {
+            let bytes = match download.bytes_downloaded {
+                Some(bytes) => bytes,
+                None => {
+                    self.byte_samples.remove(&download.key());
+                    continue;
+                }
+            };
+            let sample = match self.byte_samples.get_mut(&download.key()) {
+                Some(sample) => sample,
+                None => {
+                    let sample = ByteSample {
+                        bytes,
+                        at: now,
+                        rate: None,
+                    };
+                    self.byte_samples.insert(download.key(), sample);
+                    continue;
+                }
+            };
+
+            let elapsed = now.duration_since(sample.at);
+            if bytes == sample.bytes && elapsed < self.refresh_interval {
+                // A pushed update for another model; too soon to call this one stalled.
+                continue;
+            }
+            sample.rate = if bytes < sample.bytes || elapsed.is_zero() {
+                // The download restarted, so the old count says nothing about its speed.
+                None
+            } else {
+                Some((bytes - sample.bytes) as f64 / elapsed.as_secs_f64())
+            };
+            sample.bytes = bytes;
+            sample.at = now;
+        }
+
+        let listed: HashSet<DownloadKey> = self.downloads.iter().map(Download::key).collect();
+        self.byte_samples.retain(|key, _| listed.contains(key));
+    }
+
+    fn log(&mut self, message: String, is_error: bool) {
+        // The log file gets everything the log view shows.
+        if is_error {
+            tracing::warn!("{}", message);
+        } else {
+            tracing::info!("{}", message);
+        }
+        if self.log.len() == LOG_LIMIT {
+            self.log.pop_front();
+        }
+        self.log.push_back(LogEntry {
+            at: Local::now(),
+            message,
+            is_error,
+        });
+    }
+
+    // Opens the server log pane on `model`, which `task` streams into, closing any other.
+    fn open_server_log(&mut self, model: String, task: JoinHandle<()>) {
+        self.close_server_log();
+        self.server_log = Some(ServerLog::new(model));
+        self.server_log_task = Some(task);
+        self.input_mode = InputMode::ServerLog;
+    }
+
+    fn close_server_log(&mut self) {
+        if let Some(task) = self.server_log_task.take() {
+            task.abort();
+        }
+        self.server_log = None;
+        if self.input_mode == InputMode::ServerLog {
+            self.input_mode = InputMode::Normal;
+        }
+    }
+
+    fn scroll_server_log(&mut self, down: bool) {
+        if let Some(log) = self.server_log.as_mut() {
+            log.scroll(down);
+        }
+    }
+
+    fn scroll_log(&mut self, down: bool) {
+        let lines: usize = self.log.iter().map(|entry| entry.message.lines().count().max(1)).sum();
+        self.log_scroll = if down {
+            (self.log_scroll + 1).min(lines.saturating_sub(1).min(u16::MAX as usize) as u16)
+        } else {
+            self.log_scroll.saturating_sub(1)
+        };
+    }
+
+    fn scroll_detail(&mut self, down: bool) {
+        let entries = self
+            .detail_key()
+            .and_then(|key| self.history.get(&key))
+            .map_or(0, |history| history.len());
+        self.detail_scroll = if down {
+            (self.detail_scroll + 1).min(entries.saturating_sub(1) as u16)
+        } else {
+            self.detail_scroll.saturating_sub(1)
+        };
+    }
+
+    fn scroll_message(&mut self, down: bool) {
+        let overflow = self.message_overflow.load(Ordering::SeqCst);
+        self.message_scroll = if down {
+            (self.message_scroll + 1).min(overflow)
+        } else {
+            self.message_scroll.saturating_sub(1)
+        };
+    }
+
+    // Copies the selected model's name to the clipboard, for use in commands and reports.
+    fn copy_model_name(&mut self) {
+        let Some(model) = self.selected_model_name().map(str::to_string) else {
+            self.show_message("Select a download to copy its name".to_string());
+            return;
+        };
+        match clipboard::copy(&model) {
+            Ok(()) => self.show_message(format!("Copied {}", model)),
+            Err(e) => self.show_error(format!("Could not copy to the clipboard: {}", e)),
+        }
+    }
+
+    // Copies the selected download's error or retry message to the clipboard.
+    fn copy_status_message(&mut self) {
+        let Some(download) = self.detail_download() else {
+            return;
+        };
+        let model = download.model_name.clone();
+        match download.status.message().map(str::to_string) {
+            Some(message) => match clipboard::copy(&message) {
+                Ok(()) => self.show_message(format!("Copied {}'s status message", model)),
+                Err(e) => self.show_error(format!("Could not copy to the clipboard: {}", e)),
+            },
+            None => self.show_error(format!("{} has no status message to copy", model)),
+        }
+    }
+
+    // The downloads currently shown in the list, in display order. Selection indices in
+    // `list_state` refer to positions in this list rather than in `downloads`.
+    fn visible_downloads(&self) -> Vec<&Download> {
+        let mut visible: Vec<&Download> = self
+            .downloads
+            .iter()
+            .filter(|d| matches_filter(&d.model_name, &self.filter_query))
+            .filter(|d| self.status_filter.is_none_or(|c| d.status.category() == c))
+            .collect();
+
+        if let Some(key) = self.sort_key {
+            // `sort_by` is stable, so ties keep the server's relative order.
+            visible.sort_by(|a, b| {
+                let ordering = key.compare(a, b);
+                if self.sort_descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        visible
+    }
+
+    // The visible downloads laid out as list rows. Selection indices in `list_state` refer to
+    // positions in this list.
+    fn visible_rows(&self) -> Vec<ListRow<&Download>> {
+        list_rows(
+            self.visible_downloads(),
+            self.group_separator.as_deref(),
+            &self.expanded_groups,
+        )
+    }
+
+    // Re-points the list highlight at the selected model or group, dropping the selection if
+    // it's gone (or filtered out) and falling back to the first visible row. A model hidden in
+    // a collapsed group falls back to the group's header instead.
+    fn resolve_selection(&mut self) {
+        let rows = self.visible_rows();
+        let group_position = |name: &str| {
+            rows.iter()
+                .position(|row| matches!(row, ListRow::Group(group) if group.name == name))
+        };
+        let index = match (&self.selected_model, &self.selected_group) {
+            (Some(model), _) => rows
+                .iter()
+                .position(|row| {
+                    row.download().is_some_and(|d| {
+                        &d.model_name == model && d.duplicate == self.selected_duplicate
+                    })
+                })
+                .or_else(|| group_position(group_prefix(model, self.group_separator.as_deref()?)?)),
+            (None, Some(name)) => group_position(name),
+            (None, None) => None,
+        };
+        let empty = rows.is_empty();
+
+        match index {
+            Some(i) => self.select_index(i),
+            None if !empty => self.select_index(0),
+            None => {
+                self.selected_model = None;
+                self.selected_group = None;
+                self.list_state.select(None);
+            }
+        }
+    }
+
+    // Selects `model` if it's visible; otherwise the selection falls back as on any refresh.
+    fn select_model(&mut self, model: String) {
+        self.selected_model = Some(model);
+        self.selected_duplicate = 0;
+        self.resolve_selection();
+    }
+
+    fn select_index(&mut self, i: usize) {
+        (self.selected_model, self.selected_duplicate, self.selected_group) =
+            match &self.visible_rows()[i] {
+                ListRow::Group(group) => (None, 0, Some(group.name.clone())),
+                ListRow::Download { download, .. } => {
+                    (Some(download.model_name.clone()), download.duplicate, None)
+                }
+            };
+        self.list_state.select(Some(i));
+    }
+
+    // Expands the highlighted group.
+    fn expand_group(&mut self) {
+        if let Some(group) = self.selected_group.clone() {
+            self.expanded_groups.insert(group);
+            self.resolve_selection();
+        }
+    }
+
+    // Collapses the highlighted group, or the group of the highlighted download, leaving the
+    // group's header highlighted.
+    fn collapse_group(&mut self) {
+        let group = match (&self.selected_group, &self.selected_model) {
+            (Some(group), _) => Some(group.clone()),
+            (None, Some(model)) => self
+                .group_separator
+                .as_deref()
+                .and_then(|separator| group_prefix(model, separator))
+                .map(str::to_string),
+            (None, None) => None,
+        };
+        if group.is_some_and(|group| self.expanded_groups.remove(&group)) {
+            self.resolve_selection();
+        }
+    }
+
+    fn set_filter_query(&mut self, query: String) {
+        self.filter_query = query;
+        self.resolve_selection();
+    }
+
+    fn cycle_status_filter(&mut self) {
+        self.status_filter = StatusCategory::next(self.status_filter);
+        self.resolve_selection();
+    }
+
+    fn cycle_sort_key(&mut self) {
+        self.sort_key = SortKey::next(self.sort_key);
+        self.resolve_selection();
+    }
+
+    fn toggle_sort_direction(&mut self) {
+        self.sort_descending = !self.sort_descending;
+        self.resolve_selection();
+    }
+
+    fn toggle_refresh_pause(&mut self) {
+        self.paused_refresh = !self.paused_refresh;
+        self.show_message(if self.paused_refresh {
+            "Refresh paused".to_string()
+        } else {
+            "Refresh resumed".to_string()
+        });
+    }
+
+    async fn add_download(
+        &mut self,
+        url: String,
+        options: &AddOptions,
+    ) -> Result<AddOutcome, Box<dyn Error>> {
+        // A URL added earlier in this session whose model is still listed needn't be re-sent.
+        let tracked = self
+            .downloads
+            .iter()
+            .find(|d| self.source_urls.get(&d.model_name) == Some(&url));
+        if let Some(download) = tracked {
+            return Ok(AddOutcome::AlreadyTracking(download.model_name.clone()));
+        }
+
+        if self.dry_run {
+            let add_url = self.client.downloads_url();
+            self.rehearse("POST", &add_url, Some(&client::add_body(&url, options)));
+            return Ok(self.simulate_add(url));
+        }
+
+        // Servers that echo the download back let us tell a new model from an existing one.
+        let echoed_model = {
+            let _in_flight = InFlight::start(&self.in_flight);
+            self.client.add_download(&url, options).await?
+        };
+        let known: HashSet<String> = self.downloads.iter().map(|d| d.model_name.clone()).collect();
+        // The download was added whether or not the list can be refreshed afterwards, so a
+        // failed refresh is only logged (by `fetch_downloads`) and the next poll catches up.
+        let refreshed = self.fetch_downloads().await.is_ok();
+
+        if let Some(model) = echoed_model {
+            if known.contains(&model) {
+                return Ok(AddOutcome::AlreadyTracking(model));
+            }
+            self.source_urls.insert(model.clone(), url);
+            return Ok(AddOutcome::Added(Some(model)));
+        }
+
+        // Otherwise attribute the URL only when exactly one new model showed up.
+        if !refreshed {
+            return Ok(AddOutcome::Added(None));
+        }
+        let added: Vec<&Download> = self
+            .downloads
+            .iter()
+            .filter(|d| !known.contains(&d.model_name))
+            .collect();
+        match added.as_slice() {
+            [download] => {
+                let model = download.model_name.clone();
+                self.source_urls.insert(model.clone(), url);
+                Ok(AddOutcome::Added(Some(model)))
+            }
+            _ => Ok(AddOutcome::Added(None)),
+        }
+    }
+
+    // The downloads that have been errored for longer than the grace period, each with the
+    // restart attempt it's due. A model is given up on after `max_attempts` restarts until it
+    // recovers, which means completing or staying out of error for the grace period: a restart
+    // that only reaches Initializing before failing again still counts against it.
+    fn due_auto_restarts(&mut self) -> Vec<(String, u32)> {
+        let Some(policy) = &self.auto_restart else {
+            return Vec::new();
+        };
+        let (grace, max_attempts) = (policy.grace, policy.max_attempts);
+
+        let now = Utc::now();
+        let recovering: HashSet<&str> = self
+            .downloads
+            .iter()
+            .filter(|d| match d.status {
+                DownloadStatus::Error { .. } => true,
+                DownloadStatus::Completed => false,
+                _ => (now - d.last_status_change).to_std().unwrap_or_default() < grace,
+            })
+            .map(|d| d.model_name.as_str())
+            .collect();
+        self.auto_restart_attempts.retain(|model, _| recovering.contains(model.as_str()));
+
+        let due: Vec<String> = self
+            .downloads
+            .iter()
+            .filter(|d| matches!(d.status, DownloadStatus::Error { .. }))
+            .filter(|d| (now - d.last_status_change).to_std().unwrap_or_default() >= grace)
+            .map(|d| d.model_name.clone())
+            .collect();
+
+        let mut restarts = Vec::new();
+        for model in due {
+            let attempts = self.auto_restart_attempts.entry(model.clone()).or_insert(0);
+            if *attempts > max_attempts {
+                continue;
+            }
+            if *attempts == max_attempts {
+                *attempts += 1;
+                self.log(
+                    format!("Giving up auto-restarting {} after {} attempts", model, max_attempts),
+                    true,
+                );
+                continue;
+            }
+            *attempts += 1;
+            restarts.push((model, *attempts));
+        }
+        restarts
+    }
+
+    // The downloads that have been paused for a show longer than the timeout. Each show pause
+    // gets one attempt; if the server keeps it paused, it's left alone.
+    fn due_auto_resumes(&mut self) -> Vec<String> {
+        let Some(timeout) = self.show_resume_timeout else {
+            return Vec::new();
+        };
+
+        let show_paused: HashSet<&str> = self
+            .downloads
+            .iter()
+            .filter(|d| d.status.is_show_pause())
+            .map(|d| d.model_name.as_str())
+            .collect();
+        self.show_resumes.retain(|model| show_paused.contains(model.as_str()));
+
+        let now = Utc::now();
+        let due: Vec<String> = self
+            .downloads
+            .iter()
+            .filter(|d| d.status.is_show_pause() && !self.show_resumes.contains(&d.model_name))
+            .filter(|d| (now - d.last_status_change).to_std().unwrap_or_default() >= timeout)
+            .map(|d| d.model_name.clone())
+            .collect();
+
+        self.show_resumes.extend(due.iter().cloned());
+        due
+    }
+
+    // The downloads that have been completed for longer than the prune threshold, each given
+    // once. In a prune dry run they're only logged, and none are returned.
+    fn due_auto_prunes(&mut self) -> Vec<String> {
+        let Some(policy) = &self.auto_prune else {
+            return Vec::new();
+        };
+        let (after, dry_run) = (policy.after, policy.dry_run);
+
+        let completed: HashSet<&str> = self
+            .downloads
+            .iter()
+            .filter(|d| d.status == DownloadStatus::Completed)
+            .map(|d| d.model_name.as_str())
+            .collect();
+        self.pruned.retain(|model| completed.contains(model.as_str()));
+
+        let now = Utc::now();
+        let due: Vec<String> = self
+            .downloads
+            .iter()
+            .filter(|d| completed.contains(d.model_name.as_str()))
+            .filter(|d| !self.pruned.contains(&d.model_name))
+            .filter(|d| (now - d.last_status_change).to_std().unwrap_or_default() >= after)
+            .map(|d| d.model_name.clone())
+            .collect();
+
+        self.pruned.extend(due.iter().cloned());
+        if dry_run {
+            for model in due {
+                self.log(format!("Would prune completed download {}", model), false);
+            }
+            return Vec::new();
+        }
+        due
+    }
+
+    // Adds each URL in `text`, one per line, skipping blank lines and `#` comments. Every URL
+    // gets the same `options`.
+    async fn add_batch(&mut self, text: &str, options: &AddOptions) -> BatchSummary {
+        let mut summary = BatchSummary::default();
+        for line in url_list(text) {
+            let url = match validate_download_url(line) {
+                Ok(url) => url,
+                Err(e) => {
+                    summary.failed.push((line.to_string(), e));
+                    continue;
+                }
+            };
+            match self.add_download(url, options).await {
+                Ok(AddOutcome::Added(_)) => summary.added += 1,
+                Ok(AddOutcome::AlreadyTracking(_)) => summary.skipped += 1,
+                Err(e) => summary.failed.push((line.to_string(), e.to_string())),
+            }
+        }
+        summary
+    }
+
+    async fn control_download(&self, model_name: &str, action: &str) -> Result<(), Box<dyn Error>> {
+        let _in_flight = InFlight::start(&self.in_flight);
+        control_with_retries(&self.client, &self.action_retry, model_name, action).await
+    }
+
+    // Whether the download was sent an action too recently for another.
+    fn cooling_down(&self, key: &DownloadKey) -> bool {
+        self.last_actions
+            .get(key)
+            .is_some_and(|at| at.elapsed() < self.action_cooldown)
+    }
+
+    // Downloads an action applies to: every marked one, or the highlighted one if none are
+    // marked.
+    fn action_targets(&self) -> Vec<DownloadKey> {
+        if self.marked.is_empty() {
+            let selected = self.selected_model.clone();
+            selected.map(|model| (model, self.selected_duplicate)).into_iter().collect()
+        } else {
+            let mut targets: Vec<DownloadKey> = self.marked.iter().cloned().collect();
+            targets.sort();
+            targets
+        }
+    }
+
+    // Applies `action`, first asking for confirmation if it would interrupt an active download.
+    async fn request_action(&mut self, action: ControlAction) {
+        let targets = self.action_targets();
+        let active = self
+            .downloads
+            .iter()
+            .filter(|d| targets.contains(&d.key()))
+            .find(|d| {
+                matches!(d.status, DownloadStatus::Downloading | DownloadStatus::Initializing)
+            })
+            .map(Download::display_name);
+
+        match active {
+            Some(model) if self.confirm_actions => {
+                self.input_mode = InputMode::Confirm { action, model };
+            }
+            _ => self.apply_action(action).await,
+        }
+    }
+
+    // Sends `action` to all targets concurrently and reports the outcome in the banner.
+    // Targets still cooling down from their last action are left out.
+    async fn apply_action(&mut self, action: ControlAction) {
+        let (limited, targets): (Vec<DownloadKey>, Vec<DownloadKey>) = self
+            .action_targets()
+            .into_iter()
+            .partition(|key| self.cooling_down(key));
+        if targets.is_empty() {
+            if !limited.is_empty() {
+                let names: Vec<String> =
+                    limited.iter().map(|(model, n)| numbered_name(model, *n)).collect();
+                self.show_message(format!("Action rate-limited for {}", names.join(", ")));
+            }
+            return;
+        }
+
+        let models = model_names(&targets);
+        let errors = self.send_action(action, &models).await;
+        // The cooldown runs from when the server answered, so presses queued up behind a slow
+        // request are dropped too.
+        let now = Instant::now();
+        let cooldown = self.action_cooldown;
+        self.last_actions.retain(|_, at| now.duration_since(*at) < cooldown);
+        for key in targets {
+            self.last_actions.insert(key, now);
+        }
+        if models.len() == 1 {
+            if let Some(error) = errors.first() {
+                self.show_error(format!("Error {} download: {}", action.progressive(), error));
+            }
+        } else {
+            self.report_batch(&action.to_string(), models.len(), &errors);
+        }
+    }
+
+    // Points the app at the configured server at `index` and fetches its list. Returns false
+    // if there's no such server or it's already active.
+    async fn switch_server(&mut self, index: usize) -> bool {
+        let Some((name, url)) = self.servers.get(index).cloned() else {
+            return false;
+        };
+        if Some(index) == self.active_server {
+            return false;
+        }
+
+        self.change_server(url);
+        self.log(format!("Switched to {} ({})", name, self.client.base_url()), false);
+        let _ = self.fetch_downloads().await;
+        true
+    }
+
+    // Points the app at a server URL typed in at runtime and fetches its list. A URL that
+    // belongs to a configured server selects that server.
+    async fn switch_server_url(&mut self, url: String) {
+        if url == self.client.base_url() {
+            self.show_message(format!("Already connected to {}", url));
+            return;
+        }
+        self.change_server(url);
+        self.show_message(format!("Switched server to {}", self.client.base_url()));
+        let _ = self.fetch_downloads().await;
+    }
+
+    // Retargets every request at `url`, forgetting everything learned from the previous
+    // server.
+    fn change_server(&mut self, url: String) {
+        self.active_server = self.servers.iter().position(|(_, server)| *server == url);
+        self.close_server_log();
+        self.client = self.client.with_base_url(url);
+        self.server_changed.notify_waiters();
+        self.input_mode = InputMode::Normal;
+        self.source_urls.clear();
+        self.rehearsals.clear();
+        self.last_action = None;
+        self.last_actions.clear();
+        self.auto_restart_attempts.clear();
+        self.show_resumes.clear();
+        self.pruned.clear();
+        // Also drops marks, history and transfer rates along with the downloads themselves.
+        self.set_downloads(Vec::new());
+        self.duplicated_names.clear();
+        self.last_refresh = None;
+        self.connection_ok = true;
+        self.last_error = None;
+        self.failure_streak = 0;
+        self.server_version = None;
+        self.server_health = None;
+        self.health_checked = None;
+        self.capabilities = Capabilities::default();
+    }
+
+    // Records what the health, version and capabilities endpoints said. The first two may be
+    // missing, in which case their part of the title bar indicator is hidden.
+    fn receive_health(&mut self, report: HealthReport) {
+        self.server_health = match report.health {
+            Lookup::Missing => None,
+            Lookup::Found(healthy) => Some(healthy),
+            // An unreachable server counts as unhealthy.
+            Lookup::Failed => Some(false),
+        };
+        match report.version {
+            Lookup::Missing => self.server_version = None,
+            Lookup::Found(version) => self.server_version = version,
+            // A failed lookup keeps the version already known.
+            Lookup::Failed => {}
+        }
+        match report.capabilities {
+            Lookup::Missing => self.capabilities = Capabilities::default(),
+            Lookup::Found(capabilities) => {
+                let newer = capabilities.api_version.filter(|&v| v > client::API_VERSION);
+                if let Some(version) = newer.filter(|_| capabilities != self.capabilities) {
+                    self.show_error(format!(
+                        "The server uses API version {}, newer than this client's {}; \
+                         update downloader-ctl if requests fail",
+                        version,
+                        client::API_VERSION
+                    ));
+                }
+                self.capabilities = capabilities;
+            }
+            Lookup::Failed => {}
+        }
+    }
+
+    // Whether the server supports what `action` needs. Actions gated on a feature the server
+    // doesn't advertise would only get a 404 back.
+    fn supports(&self, action: KeyAction) -> bool {
+        required_feature(action).is_none_or(|feature| self.capabilities.supports(feature))
+    }
+
+    // Replaces the input box's text, with the cursor at the end.
+    fn set_input(&mut self, text: String) {
+        self.input_cursor = text.len();
+        self.input_buffer = text;
+    }
+
+    fn take_input(&mut self) -> String {
+        self.input_cursor = 0;
+        std::mem::take(&mut self.input_buffer)
+    }
+
+    fn insert_input(&mut self, text: &str) {
+        self.input_buffer.insert_str(self.input_cursor, text);
+        self.input_cursor += text.len();
+    }
+
+    // Applies an editing key to the input box: typing and deleting at the cursor, and moving
+    // it. Returns false for keys that don't edit.
+    fn edit_input(&mut self, key: KeyCode) -> bool {
+        let before = self.input_buffer[..self.input_cursor].chars().next_back();
+        let after = self.input_buffer[self.input_cursor..].chars().next();
+        match key {
+            KeyCode::Char(c) => self.insert_input(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Backspace => {
+                if let Some(c) = before {
+                    self.input_cursor -= c.len_utf8();
+                    self.input_buffer.remove(self.input_cursor);
+                }
+            }
+            KeyCode::Delete => {
+                if after.is_some() {
+                    self.input_buffer.remove(self.input_cursor);
+                }
+            }
+            KeyCode::Left => self.input_cursor -= before.map_or(0, char::len_utf8),
+            KeyCode::Right => self.input_cursor += after.map_or(0, char::len_utf8),
+            KeyCode::Home => self.input_cursor = 0,
+            KeyCode::End => self.input_cursor = self.input_buffer.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn open_add_form(&mut self) {
+        self.add_form = Default::default();
+        self.take_input();
+        self.input_error = None;
+        self.input_mode = InputMode::AddForm {
+            focus: AddField::Url,
+        };
+    }
+
+    // Moves the input box to `field`, keeping what was typed in the field it leaves.
+    fn focus_add_field(&mut self, field: AddField) {
+        let InputMode::AddForm { focus } = self.input_mode else {
+            return;
+        };
+        self.add_form[focus.index()] = self.take_input();
+        let value = std::mem::take(&mut self.add_form[field.index()]);
+        self.set_input(value);
+        self.input_mode = InputMode::AddForm { focus: field };
+    }
+
+    fn close_add_form(&mut self) {
+        self.take_input();
+        self.add_form = Default::default();
+        self.input_error = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    // Validates the form and adds its URL, or every URL when a list was pasted. A field that
+    // fails validation takes the focus with the error shown under it.
+    async fn submit_add_form(&mut self) {
+        let InputMode::AddForm { focus } = self.input_mode else {
+            return;
+        };
+        let mut values = self.add_form.clone();
+        values[focus.index()] = self.input_buffer.clone();
+
+        let text = values[AddField::Url.index()].clone();
+        let batch = text.trim().contains('\n');
+        let checked = add_form_options(&values).and_then(|options| {
+            if batch {
+                return Ok((options, None));
+            }
+            validate_download_url(&text)
+                .map(|url| (options, Some(url)))
+                .map_err(|e| (AddField::Url, e))
+        });
+        let (options, url) = match checked {
+            Ok(checked) => checked,
+            Err((field, error)) => {
+                self.focus_add_field(field);
+                self.input_error = Some(error);
+                return;
+            }
+        };
+        self.close_add_form();
+
+        let Some(url) = url else {
+            let summary = self.add_batch(&text, &options).await;
+            if summary.failed.is_empty() {
+                self.show_message(summary.to_string());
+            } else {
+                self.show_error(summary.to_string());
+            }
+            return;
+        };
+        match self.add_download(url, &options).await {
+            Ok(AddOutcome::Added(Some(model))) => {
+                self.show_message(format!("Added {}", model));
+                self.select_model(model);
+            }
+            Ok(AddOutcome::Added(None)) => self.show_message("Download added".to_string()),
+            Ok(AddOutcome::AlreadyTracking(model)) => {
+                self.show_error(format!("Already tracking {}", model));
+            }
+            Err(e) => self.show_error(format!("Error adding download: {}", e)),
+        }
+    }
+
+    // Opens the note editor for the selected download, starting from its current note.
+    fn edit_note(&mut self) {
+        if let Some(model) = self.selected_model.clone() {
+            self.set_input(self.notes.get(&model).cloned().unwrap_or_default());
+            self.input_mode = InputMode::EditingNote { model };
+        }
+    }
+
+    // Replaces the note on `model`, removing it if `note` is blank, and saves every note.
+    fn set_note(&mut self, model: &str, note: &str) {
+        let note = note.trim();
+        if note.is_empty() {
+            self.notes.remove(model);
+        } else {
+            self.notes.insert(model.to_string(), note.to_string());
+        }
+        if let Some(path) = &self.notes_path {
+            if let Err(e) = notes::save(path, &self.notes) {
+                self.show_error(format!("Could not save notes to {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    // Saves the sort and filters, to be restored at the next launch.
+    fn save_ui_state(&self) -> Result<(), String> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        let state = UiState {
+            sort_key: self.sort_key,
+            sort_descending: self.sort_descending,
+            status_filter: self.status_filter,
+            filter_query: self.filter_query.clone(),
+        };
+        state::save(path, &state)
+            .map_err(|e| format!("Could not save view state to {}: {}", path.display(), e))
+    }
+
+    // Whether quitting can go ahead now. With confirmations on, quitting while downloads are
+    // active asks first instead.
+    fn request_quit(&mut self) -> bool {
+        let active = self
+            .downloads
+            .iter()
+            .filter(|d| {
+                matches!(d.status, DownloadStatus::Downloading | DownloadStatus::Initializing)
+            })
+            .count();
+        if self.confirm_quit && active > 0 {
+            self.input_mode = InputMode::ConfirmQuit { active };
+            return false;
+        }
+        true
+    }
+
+    // Like `request_action`, but for every download the fleet action applies to. Fleet actions
+    // always ask first when confirmations are enabled.
+    async fn request_fleet_action(&mut self, fleet: FleetAction) {
+        let count = self.fleet_targets(fleet).len();
+        if count == 0 {
+            self.show_message(format!("{}: no {} downloads", fleet, fleet.noun()));
+        } else if self.confirm_actions || fleet == FleetAction::ClearCompleted {
+            // Deleting can't be undone, so clearing always asks, as a single delete does.
+            self.input_mode = InputMode::ConfirmFleet { action: fleet, count };
+        } else {
+            self.apply_fleet_action(fleet).await;
+        }
+    }
+
+    async fn apply_fleet_action(&mut self, fleet: FleetAction) {
+        let targets = self.fleet_targets(fleet);
+        if targets.is_empty() {
+            return;
+        }
+
+        let errors = match fleet.action() {
+            Some(action) => self.send_action(action, &targets).await,
+            None => {
+                // Cleared rows vanish, so the selection moves to the nearest one that stays.
+                let nearest = self.nearest_remaining(&targets);
+                let errors = self.remove_downloads(&targets).await;
+                if let Some(model) = nearest {
+                    self.select_model(model);
+                }
+                errors
+            }
+        };
+        self.report_batch(&fleet.to_string(), targets.len(), &errors);
+    }
+
+    // The visible download closest to the selection that isn't one of `leaving`, looking
+    // further down the list first.
+    fn nearest_remaining(&self, leaving: &[String]) -> Option<String> {
+        let rows = self.visible_rows();
+        let selected = self.list_state.selected()?;
+        let stays = |i: &usize| {
+            rows[*i]
+                .download()
+                .is_some_and(|d| !leaving.contains(&d.model_name))
+        };
+        let nearest = (selected..rows.len())
+            .find(stays)
+            .or_else(|| (0..selected).rev().find(stays))?;
+        rows[nearest].download().map(|d| d.model_name.clone())
+    }
+
+    // Deletes every one of `models`, a few at a time, then refreshes once. Returns the error
+    // message of each failure.
+    async fn remove_downloads(&mut self, models: &[String]) -> Vec<String> {
+        if self.dry_run {
+            for model in models {
+                let url = self.client.download_url(model);
+                self.rehearse("DELETE", &url, None);
+                self.simulate(model, None);
+            }
+            return Vec::new();
+        }
+
+        let results = {
+            let _in_flight = InFlight::start(&self.in_flight);
+            let client = &self.client;
+            let requests = models.iter().map(|model| async move {
+                client.remove_download(model).await.map_err(|e| e.to_string())
+            });
+            self.run_batch("deleting", requests.collect()).await
+        };
+        let mut errors = Vec::new();
+        for (model, result) in models.iter().zip(results) {
+            match result {
+                Ok(()) => {
+                    self.source_urls.remove(model);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        let _ = self.fetch_downloads().await;
+        errors
+    }
+
+    fn fleet_targets(&self, fleet: FleetAction) -> Vec<String> {
+        self.downloads
+            .iter()
+            .filter(|d| fleet.applies_to(&d.status))
+            .map(|d| d.model_name.clone())
+            .collect()
+    }
+
+    // Sends `action` to every target concurrently, remembering successful stops for undo.
+    // Returns the error message of each failure.
+    async fn send_action(&mut self, action: ControlAction, targets: &[String]) -> Vec<String> {
+        let mut previous = Vec::new();
+        let results: Vec<Result<(), String>> = if self.dry_run {
+            for model in targets {
+                self.rehearse_control(model, action);
+            }
+            targets.iter().map(|_| Ok(())).collect()
+        } else {
+            previous = self.apply_optimistic(targets, action);
+            // Errors become strings as each request finishes: `join_all` holds finished results
+            // while awaiting the rest, and a boxed error would stop the future being `Send`.
+            let app = &*self;
+            let requests = targets.iter().map(|model| async move {
+                let result = app.control_download(model, action.endpoint()).await;
+                result.map_err(|e| e.to_string())
+            });
+            self.run_batch(action.progressive(), requests.collect()).await
+        };
+        let mut succeeded = Vec::new();
+        let mut errors = Vec::new();
+        for (model, result) in targets.iter().zip(results) {
+            match result {
+                Ok(()) => succeeded.push(model.clone()),
+                Err(e) => errors.push(e),
+            }
+        }
+        previous.retain(|download| !succeeded.contains(&download.model_name));
+        self.revert_optimistic(previous);
+        if action == ControlAction::Stop && !succeeded.is_empty() {
+            self.last_action = Some((Action::Stopped(succeeded), Instant::now()));
+        }
+        errors
+    }
+
+    // Runs `requests` no more than `max_concurrency` at a time, so a fleet-wide action can't
+    // flood the server, and counts them off in the banner as they finish. Results come back in
+    // the order the requests were given.
+    async fn run_batch<T>(
+        &self,
+        verb: &'static str,
+        requests: Vec<impl Future<Output = T>>,
+    ) -> Vec<T> {
+        let total = requests.len();
+        // A lone request finishes too quickly for progress to be worth showing.
+        let report = |done| {
+            if total > 1 {
+                *self.batch.lock().unwrap() = Some(BatchProgress { verb, done, total });
+            }
+        };
+        report(0);
+        let permits = Semaphore::new(self.max_concurrency.max(1));
+        let done = AtomicUsize::new(0);
+        let results = join_all(requests.into_iter().map(|request| async {
+            let _permit = permits.acquire().await.expect("the semaphore is never closed");
+            let result = request.await;
+            report(done.fetch_add(1, Ordering::SeqCst) + 1);
+            result
+        }))
+        .await;
+        *self.batch.lock().unwrap() = None;
+        results
+    }
+
+    // Shows each target in the status `action` should lead to without waiting for the next
+    // refresh, which reconciles it with the server. Returns the replaced downloads so the
+    // change can be reverted for targets whose request fails.
+    fn apply_optimistic(&mut self, targets: &[String], action: ControlAction) -> Vec<Download> {
+        let now = Utc::now();
+        let mut previous = Vec::new();
+        for download in self
+            .downloads
+            .iter_mut()
+            .filter(|d| targets.contains(&d.model_name))
+        {
+            previous.push(download.clone());
+            download.status = action.resulting_status();
+            download.last_status_change = now;
+        }
+        self.resolve_selection();
+        previous
+    }
+
+    fn revert_optimistic(&mut self, previous: Vec<Download>) {
+        if previous.is_empty() {
+            return;
+        }
+        for original in previous {
+            let current = self
+                .downloads
+                .iter_mut()
+                .find(|d| d.key() == original.key());
+            if let Some(download) = current {
+                *download = original;
+            }
+        }
+        self.resolve_selection();
+    }
+
+    fn report_batch(&mut self, label: &str, total: usize, errors: &[String]) {
+        if errors.is_empty() {
+            self.show_message(format!("{}: {} succeeded", label, total));
+        } else {
+            self.show_error(format!(
+                "{}: {} succeeded, {} failed ({})",
+                label,
+                total - errors.len(),
+                errors.len(),
+                errors[0]
+            ));
+        }
+    }
+
+    fn toggle_mark(&mut self) {
+        if let Some(model) = self.selected_model.clone() {
+            let key = (model, self.selected_duplicate);
+            if !self.marked.remove(&key) {
+                self.marked.insert(key);
+            }
+        }
+    }
+
+    async fn remove_download(&mut self, model_name: &str) -> Result<(), Box<dyn Error>> {
+        if self.dry_run {
+            let remove_url = self.client.download_url(model_name);
+            self.rehearse("DELETE", &remove_url, None);
+        } else {
+            let _in_flight = InFlight::start(&self.in_flight);
+            self.client.remove_download(model_name).await?;
+        }
+
+        let action = Action::Deleted {
+            model: model_name.to_string(),
+            // Deletes go by name, so the server may pick any of a repeated name's downloads;
+            // the first one's URL is the best guess at what to restore.
+            url: self.source_url(model_name, 0),
+        };
+        self.source_urls.remove(model_name);
+        self.last_action = Some((action, Instant::now()));
+        if self.dry_run {
+            self.simulate(model_name, None);
+            Ok(())
+        } else {
+            self.fetch_downloads().await
+        }
+    }
+
+    // Moves the selected queued download one place earlier or later in the server's queue.
+    async fn move_in_queue(&mut self, later: bool) {
+        let Some(model) = self.selected_model_name().map(str::to_string) else {
+            return;
+        };
+        let queue = self.queue();
+        let Some(index) = queue.iter().position(|queued| *queued == model) else {
+            self.show_message(format!("{} isn't queued", model));
+            return;
+        };
+        let target = if later { index + 1 } else { index.wrapping_sub(1) };
+        if target >= queue.len() {
+            let end = if later { "last" } else { "first" };
+            self.show_message(format!("{} is already {} in the queue", model, end));
+            return;
+        }
+
+        let position = target as u32;
+        let result = if self.dry_run {
+            let url = self.client.control_url(&model, "priority");
+            self.rehearse("PUT", &url, Some(&client::priority_body(position)));
+            Ok(())
+        } else {
+            let _in_flight = InFlight::start(&self.in_flight);
+            // Kept as a message so the future stays `Send` for the request task.
+            self.client
+                .set_priority(&model, position)
+                .await
+                .map_err(|e| e.to_string())
+        };
+        match result {
+            Ok(()) => {
+                self.show_message(format!("Moved {} to #{} in the queue", model, target + 1));
+                if !self.dry_run {
+                    let _ = self.fetch_downloads().await;
+                }
+            }
+            Err(e) => self.show_error(format!("Error reordering the queue: {}", e)),
+        }
+    }
+
+    // Queued models in the order they'll start: by the position the server reports, falling
+    // back to list order for servers that don't.
+    fn queue(&self) -> Vec<String> {
+        let mut queued: Vec<&Download> = self
+            .downloads
+            .iter()
+            .filter(|d| d.status == DownloadStatus::Queued)
+            .collect();
+        queued.sort_by_key(|d| d.queue_position.unwrap_or(u32::MAX));
+        queued.into_iter().map(|d| d.model_name.clone()).collect()
+    }
+
+    // Logs the request a dry run would have sent in place of sending it.
+    fn rehearse(&mut self, method: &str, url: &str, body: Option<&serde_json::Value>) {
+        let mut message = format!("{} {} {}", DRY_RUN_PREFIX, method, url);
+        if let Some(body) = body {
+            message.push_str(&format!(" {}", body));
+        }
+        self.log(message, false);
+    }
+
+    fn rehearse_control(&mut self, model: &str, action: ControlAction) {
+        let url = self.client.control_url(model, action.endpoint());
+        self.rehearse("POST", &url, None);
+        if let Some(mut download) = self.downloads.iter().find(|d| d.model_name == model).cloned() {
+            download.status = action.resulting_status();
+            download.last_status_change = Utc::now();
+            self.simulate(model, Some(download));
+        }
+    }
+
+    // Simulates the server picking up `url`, naming the model after the URL's last path
+    // segment since there's no server to name it.
+    fn simulate_add(&mut self, url: String) -> AddOutcome {
+        let model = Url::parse(&url)
+            .ok()
+            .and_then(|parsed| {
+                let segment = parsed.path_segments()?.rfind(|s| !s.is_empty())?;
+                Some(segment.to_string())
+            })
+            .unwrap_or_else(|| url.clone());
+        if self.downloads.iter().any(|d| d.model_name == model) {
+            return AddOutcome::AlreadyTracking(model);
+        }
+
+        let now = Utc::now();
+        let download = Download {
+            model_name: model.clone(),
+            status: DownloadStatus::Initializing,
+            start_time: now,
+            last_status_change: now,
+            retry_count: 0,
+            source_url: Some(url.clone()),
+            bytes_downloaded: None,
+            total_bytes: None,
+            queue_position: None,
+            duplicate: 0,
+        };
+        self.simulate(&model, Some(download));
+        self.source_urls.insert(model.clone(), url);
+        AddOutcome::Added(Some(model))
+    }
+
+    // Records the simulated state of `model` and applies it to the current list.
+    fn simulate(&mut self, model: &str, download: Option<Download>) {
+        self.rehearsals.insert(model.to_string(), download);
+        let downloads = self.with_rehearsals(self.downloads.clone());
+        self.set_downloads(downloads);
+    }
+
+    fn with_rehearsals(&self, mut downloads: Vec<Download>) -> Vec<Download> {
+        for (model, simulated) in &self.rehearsals {
+            let index = downloads.iter().position(|d| &d.model_name == model);
+            match (index, simulated) {
+                (Some(i), Some(download)) => downloads[i] = download.clone(),
+                (Some(i), None) => {
+                    downloads.remove(i);
+                }
+                (None, Some(download)) => downloads.push(download.clone()),
+                (None, None) => {}
+            }
+        }
+        downloads
+    }
+
+    // The URL a download was created from, as reported by the server or remembered from
+    // adding it in this session.
+    fn source_url(&self, model_name: &str, duplicate: usize) -> Option<String> {
+        self.downloads
+            .iter()
+            .find(|d| d.model_name == model_name && d.duplicate == duplicate)
+            .and_then(|d| d.source_url.clone())
+            .or_else(|| self.source_urls.get(model_name).cloned())
+    }
+
+    fn open_selected_source(&mut self) {
+        let Some((model, duplicate)) = self.detail_key() else {
+            return;
+        };
+        match self.source_url(&model, duplicate) {
+            Some(url) => {
+                if let Err(e) = open_in_browser(&url) {
+                    self.show_error(format!("Could not open {}: {}", url, e));
+                }
+            }
+            None => {
+                let name = numbered_name(&model, duplicate);
+                self.show_error(format!("The server didn't provide a source URL for {}", name))
+            }
+        }
+    }
+
+    // Writes the full download list, ignoring any filter, to a timestamped file.
+    fn export(&mut self) {
+        match export::write(&self.downloads, self.export_format, &self.export_dir) {
+            Ok(path) => self.show_message(format!("Exported to {}", path.display())),
+            Err(e) => self.show_error(format!("Export failed: {}", e)),
+        }
+    }
+
+    // Reverses the last stop or delete if it happened within `UNDO_WINDOW`.
+    async fn undo(&mut self) {
+        let action = match self.last_action.take() {
+            Some((action, at)) if at.elapsed() < UNDO_WINDOW => action,
+            _ => {
+                self.show_message("Nothing to undo".to_string());
+                return;
+            }
+        };
+
+        match action {
+            Action::Stopped(models) => {
+                let errors = self.send_action(ControlAction::Restart, &models).await;
+                let message = match models.as_slice() {
+                    [model] => format!("Undone: restarted {}", model),
+                    _ => format!("Undone: restarted {} downloads", models.len()),
+                };
+                match errors.first() {
+                    Some(e) => self.show_error(format!("Undo failed: {}", e)),
+                    None => self.show_message(message),
+                }
+            }
+            Action::Deleted { model, url: Some(url) } => {
+                match self.add_download(url, &AddOptions::default()).await {
+                    Ok(AddOutcome::Added(_)) => {
+                        self.show_message(format!("Undone: re-added {}", model))
+                    }
+                    // Someone re-added it in the meantime, so there's nothing left to undo.
+                    Ok(AddOutcome::AlreadyTracking(tracked)) => self.show_message(format!(
+                        "Nothing to undo: {} is already tracked",
+                        tracked
+                    )),
+                    Err(e) => self.show_error(format!("Undo failed: {}", e)),
+                }
+            }
+            Action::Deleted { model, url: None } => self.show_error(format!(
+                "Cannot undo deleting {}: it wasn't added from this session, so its URL is unknown",
+                model
+            )),
+        }
+    }
+
+    // Copies out everything `ui` needs so rendering doesn't hold the app lock.
+    fn snapshot(&self) -> AppSnapshot {
+        AppSnapshot {
+            rows: self
+                .visible_rows()
+                .into_iter()
+                .map(|row| match row {
+                    ListRow::Group(group) => ListRow::Group(group),
+                    ListRow::Download { download, in_group } => ListRow::Download {
+                        download: download.clone(),
+                        in_group,
+                    },
+                })
+                .collect(),
+            total_downloads: self.downloads.len(),
+            category_counts: category_counts(&self.downloads),
+            selected: self.list_state.selected(),
+            selected_download: self.detail_download().cloned().map(|mut download| {
+                download.source_url = self.source_url(&download.model_name, download.duplicate);
+                download
+            }),
+            marked: self.marked.clone(),
+            selected_history: self
+                .detail_key()
+                .and_then(|key| self.history.get(&key))
+                .map(|history| history.iter().cloned().collect())
+                .unwrap_or_default(),
+            restarts: self.restarts.clone(),
+            detail_scroll: self.detail_scroll,
+            message_scroll: self.message_scroll,
+            message_overflow: self.message_overflow.clone(),
+            log: if self.input_mode == InputMode::Log {
+                self.log.iter().cloned().collect()
+            } else {
+                Vec::new()
+            },
+            log_scroll: self.log_scroll,
+            server_log: self
+                .server_log
+                .clone()
+                .filter(|_| self.input_mode == InputMode::ServerLog),
+            stats: (self.input_mode == InputMode::Stats)
+                .then(|| fleet_stats(&self.downloads, Utc::now())),
+            input_mode: self.input_mode.clone(),
+            input_buffer: self.input_buffer.clone(),
+            input_cursor: self.input_cursor,
+            input_error: self.input_error.clone(),
+            add_form: self.add_form.clone(),
+            filter_query: self.filter_query.clone(),
+            status_filter: self.status_filter,
+            sort_key: self.sort_key,
+            sort_descending: self.sort_descending,
+            display_mode: self.display_mode,
+            row_template: self.row_template.clone(),
+            time_columns: self.time_columns.clone(),
+            absolute_times: self.absolute_times,
+            timestamp_format: self.timestamp_format.clone(),
+            column_width: self.column_width,
+            downloader_url: self.client.base_url().to_string(),
+            server_name: self
+                .active_server
+                .and_then(|i| self.servers.get(i))
+                .map(|(name, _)| name.clone()),
+            server_version: self.server_version.clone(),
+            server_health: self.server_health,
+            capabilities: self.capabilities.clone(),
+            in_flight: self.in_flight.load(Ordering::SeqCst) > 0,
+            batch: self.batch.lock().unwrap().clone(),
+            frame: 0,
+            connection_ok: self.connection_ok,
+            poll_delay: self.poll_delay(),
+            failure_streak: self.failure_streak,
+            last_error: self.last_error.clone(),
+            last_refresh: self.last_refresh,
+            paused_refresh: self.paused_refresh,
+            stale_after: self.stale_after,
+            banner: self.active_banner().cloned(),
+            expected_duration: self.expected_duration,
+            keymap: self.keymap.clone(),
+            rates: self
+                .byte_samples
+                .iter()
+                .filter_map(|(model, sample)| Some((model.clone(), sample.rate?)))
+                .collect(),
+            expected_completions: self
+                .downloads
+                .iter()
+                .filter(|d| d.status == DownloadStatus::Downloading)
+                .filter_map(|d| {
+                    let expected = self.completions.expected(&d.model_name)?;
+                    Some((d.model_name.clone(), expected))
+                })
+                .collect(),
+            dry_run: self.dry_run,
+            notes: self.notes.clone(),
+            theme: self.theme,
+        }
+    }
+
+    fn show_message(&mut self, message: String) {
+        self.log(message.clone(), false);
+        self.banner = Some(Banner {
+            message,
+            is_error: false,
+            shown_at: Instant::now(),
+        });
+    }
+
+    fn show_error(&mut self, message: String) {
+        self.log(message.clone(), true);
+        self.banner = Some(Banner {
+            message,
+            is_error: true,
+            shown_at: Instant::now(),
+        });
+    }
+
+    // The banner message, if one was shown recently enough to still be displayed.
+    fn active_banner(&self) -> Option<&Banner> {
+        self.banner
+            .as_ref()
+            .filter(|b| b.shown_at.elapsed() < BANNER_DURATION)
+    }
+
+    fn select_next(&mut self) {
+        self.step_selection(true);
+    }
+
+    fn select_previous(&mut self) {
+        self.step_selection(false);
+    }
+
+    // Moves the selection one row, wrapping around at either end if wrap navigation is on.
+    fn step_selection(&mut self, forward: bool) {
+        let len = self.visible_rows().len();
+        match self.list_state.selected() {
+            Some(i) if self.wrap_navigation && forward && i + 1 == len => self.select_index(0),
+            Some(0) if self.wrap_navigation && !forward && len > 0 => self.select_index(len - 1),
+            _ => self.move_selection(if forward { 1 } else { -1 }),
+        }
+    }
+
+    // Moves the selection by `delta` rows, stopping at either end of the list.
+    fn move_selection(&mut self, delta: isize) {
+        let len = self.visible_rows().len();
+        if len == 0 {
+            self.selected_model = None;
+            self.selected_group = None;
+            self.list_state.select(None);
+            return;
+        }
+
+        let i = match self.list_state.selected() {
+            Some(i) => i.saturating_add_signed(delta).min(len - 1),
+            None => 0,
+        };
+        self.select_index(i);
+    }
+
+    // Moves to the next (or previous) visible download in an error or offline state, wrapping
+    // around the list and starting from the current selection. A collapsed group holding one
+    // counts as a match itself.
+    fn select_problem(&mut self, forward: bool) {
+        let visible = self.visible_rows();
+        let len = visible.len();
+        let current = self.list_state.selected();
+        let found = (1..=len)
+            .map(|step| match current {
+                Some(i) if forward => (i + step) % len,
+                Some(i) => (i + len * 2 - step) % len,
+                None if forward => step - 1,
+                None => len - step,
+            })
+            .find(|&i| match &visible[i] {
+                ListRow::Download { download, .. } => {
+                    download.status.category() == StatusCategory::Errored
+                }
+                ListRow::Group(group) => {
+                    !group.expanded
+                        && group.counts.iter().any(|&(category, count)| {
+                            category == StatusCategory::Errored && count > 0
+                        })
+                }
+            });
+
+        match found {
+            Some(i) => self.select_index(i),
+            None => self.show_message("No errored downloads".to_string()),
+        }
+    }
+
+    fn select_first(&mut self) {
+        self.move_selection(isize::MIN);
+    }
+
+    fn select_last(&mut self) {
+        self.move_selection(isize::MAX);
+    }
+
+    fn selected_model_name(&self) -> Option<&str> {
+        self.selected_model.as_deref()
+    }
+
+    fn selected_download(&self) -> Option<&Download> {
+        let model = self.selected_model.as_ref()?;
+        self.downloads
+            .iter()
+            .find(|d| &d.model_name == model && d.duplicate == self.selected_duplicate)
+    }
+
+    // The download the detail and watch views describe: the watched one while watching,
+    // otherwise the selection.
+    fn detail_key(&self) -> Option<DownloadKey> {
+        match &self.input_mode {
+            InputMode::Watch { model, duplicate } => Some((model.clone(), *duplicate)),
+            _ => Some((self.selected_model.clone()?, self.selected_duplicate)),
+        }
+    }
+
+    fn detail_download(&self) -> Option<&Download> {
+        let (model, duplicate) = self.detail_key()?;
+        self.downloads
+            .iter()
+            .find(|d| d.model_name == model && d.duplicate == duplicate)
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = match cli::Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("downloaderctl: {}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(path) = &args.log_file {
+        if let Err(e) = logging::init(path, std::env::var("RUST_LOG").ok().as_deref()) {
+            eprintln!("downloaderctl: {}", e);
+            std::process::exit(2);
+        }
+        tracing::info!(url = %args.downloader_url, "starting");
+    }
+
+    let mut app = App::new(&args)?;
+    if let Some(command) = args.command {
+        let code = headless::run(&mut app, command, args.output_format).await;
+        std::process::exit(code);
+    }
+
+    let app = Arc::new(Mutex::new(app));
+
+    // Installed before raw mode so a panic anywhere after this leaves a usable terminal.
+    chain_panic_hook(|| {
+        let _ = restore_terminal();
+    });
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = match connect(&mut terminal, &app).await {
+        Ok(true) => {
+            let shutdown = CancellationToken::new();
+            let refresh = tokio::spawn(refresh_loop(app.clone(), args.use_sse, shutdown.clone()));
+            let housekeeping = tokio::spawn(housekeeping_loop(app.clone(), shutdown.clone()));
+            let res = run_app(&mut terminal, app.clone()).await;
+
+            // Stop background refreshes before tearing down the terminal.
+            shutdown.cancel();
+            let _ = tokio::join!(refresh, housekeeping);
+            res
+        }
+        Ok(false) => Ok(()),
+        Err(e) => Err(e),
+    };
+
+    // Saved however the session ended, so the view comes back next time.
+    let saved = app.lock().await.save_ui_state();
+    drop(terminal);
+    restore_terminal()?;
+
+    if let Err(e) = saved {
+        eprintln!("downloaderctl: {}", e);
+    }
+    if let Err(e) = res {
+        eprintln!("downloaderctl: {}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// Undoes everything `main` sets up on the terminal.
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        Show
+    )
+}
+
+// Runs `restore` ahead of the existing panic hook, so the panic message is printed to a
+// terminal that's back in its normal state.
+fn chain_panic_hook(restore: impl Fn() + Send + Sync + 'static) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        previous(info);
+    }));
+}
+
+// Makes the first fetch behind a "Connecting" splash, retrying with backoff so a server
+// that's still starting up gets a chance to come up. Returns `Ok(false)` if the user quits
+// while waiting, and an error once every attempt has failed.
+async fn connect<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &Arc<Mutex<App>>,
+) -> Result<bool, Box<dyn Error>> {
+    let (url, theme) = {
+        let app = app.lock().await;
+        (app.client.base_url().to_string(), app.theme)
+    };
+    let mut backoff = STARTUP_BACKOFF;
+    let mut last_error: Option<String> = None;
+    let mut frame = 0;
+    for attempt in 1..=STARTUP_ATTEMPTS {
+        terminal.draw(|f| render_splash(f, &theme, &url, attempt, last_error.as_deref(), frame))?;
+        let error = match app.lock().await.fetch_downloads().await {
+            Ok(()) => return Ok(true),
+            Err(e) => e.to_string(),
+        };
+        if attempt == STARTUP_ATTEMPTS {
+            return Err(format!(
+                "Could not connect to {} after {} attempts: {}",
+                url, STARTUP_ATTEMPTS, error
+            )
+            .into());
+        }
+        last_error = Some(error);
+
+        let retry_at = Instant::now() + backoff;
+        while let Some(wait) = retry_at.checked_duration_since(Instant::now()) {
+            frame += 1;
+            let error = last_error.as_deref();
+            terminal.draw(|f| render_splash(f, &theme, &url, attempt + 1, error, frame))?;
+            if event::poll(wait.min(TICK_RATE))? {
+                if let Event::Key(key) = event::read()? {
+                    if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        backoff *= 2;
+    }
+    Ok(true)
+}
+
+fn render_splash<B: Backend>(
+    f: &mut Frame<B>,
+    theme: &Theme,
+    url: &str,
+    attempt: u32,
+    last_error: Option<&str>,
+    frame: usize,
+) {
+    let mut lines = vec![Spans::from(format!(
+        "{} Connecting to {}… (attempt {}/{})",
+        SPINNER_FRAMES[frame % SPINNER_FRAMES.len()],
+        url,
+        attempt,
+        STARTUP_ATTEMPTS
+    ))];
+    if let Some(error) = last_error {
+        lines.push(Spans::from(Span::styled(
+            format!("Last error: {}", error),
+            theme.error,
+        )));
+    }
+    lines.push(Spans::from(Span::styled("q to quit", theme.muted)));
+
+    let width = lines.iter().map(Spans::width).max().unwrap_or(0) as u16 + 4;
+    let Some(area) = centered_rect(f.size(), width, lines.len() as u16 + 2) else {
+        return;
+    };
+    let splash = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL).title("downloaderctl"));
+    f.render_widget(splash, area);
+}
+
+// Keeps the download list current. Server-sent events are preferred when the server supports
+// them; otherwise, or whenever the stream drops, the list is polled every refresh interval.
+// Runs until `shutdown` is cancelled; any in-flight request is dropped at that point.
+async fn refresh_loop(app: Arc<Mutex<App>>, use_sse: bool, shutdown: CancellationToken) {
+    // The first refresh is immediate; after that the app decides, backing off while it fails.
+    let mut delay = Duration::ZERO;
+    // Servers without an event stream, which are only ever polled.
+    let mut sse_unsupported = HashSet::new();
+
+    loop {
+        let span = tracing::debug_span!("refresh", delay_ms = delay.as_millis() as u64);
+        let refresh = async {
+            tokio::time::sleep(delay).await;
+            let (url, paused, streams) = {
+                let app = app.lock().await;
+                let streams = app.capabilities.supports(Feature::Events);
+                (app.client.base_url().to_string(), app.paused_refresh, streams)
+            };
+            if paused {
+                delay = app.lock().await.refresh_interval;
+                return;
+            }
+            if use_sse && streams && !sse_unsupported.contains(&url) {
+                if let sse::StreamEnd::Unsupported = sse::stream_updates(&app).await {
+                    sse_unsupported.insert(url);
+                }
+            }
+            {
+                let app = app.lock().await;
+                if app.paused_refresh {
+                    delay = app.refresh_interval;
+                    return;
+                }
+            }
+            // Failures are recorded on the app and shown in the connection banner.
+            fetch_unlocked(&app).await;
+            delay = app.lock().await.poll_delay();
+        }
+        .instrument(span);
+
+        tokio::select! {
+            _ = shutdown.cancelled() => return,
+            _ = refresh => {}
+        }
+    }
+}
+
+// Checks the server's health and runs the watchdogs every refresh interval, against whatever
+// list the refresh loop last installed, so they keep running while the list arrives as events
+// rather than by polling. Skipped while refresh is paused or the server can't be reached.
+async fn housekeeping_loop(app: Arc<Mutex<App>>, shutdown: CancellationToken) {
+    loop {
+        let interval = app.lock().await.refresh_interval;
+        let housekeeping = async {
+            tokio::time::sleep(interval).await;
+            let health_due = {
+                let app = app.lock().await;
+                if app.paused_refresh || !app.connection_ok || app.last_refresh.is_none() {
+                    return;
+                }
+                app.health_checked.is_none_or(|at| at.elapsed() >= HEALTH_INTERVAL)
+            };
+            if health_due {
+                check_health(&app).await;
+            }
+            run_auto_restart(&app).await;
+            run_auto_resume(&app).await;
+            run_auto_prune(&app).await;
+        };
+
+        tokio::select! {
+            _ = shutdown.cancelled() => return,
+            _ = housekeeping => {}
+        }
+    }
+}
+
+// Fetches the list as `App::fetch_downloads` does, but locks the app only to read the client
+// and to install the result, never while the server answers, so keys are handled and frames
+// drawn meanwhile. A list arriving after a switch to another server is dropped.
+async fn fetch_unlocked(app: &Mutex<App>) {
+    let (client, in_flight) = {
+        let app = app.lock().await;
+        (app.client.clone(), app.in_flight.clone())
+    };
+    let fetched = {
+        let _in_flight = InFlight::start(&in_flight);
+        client.list_downloads().await.map_err(FetchFailure::from)
+    };
+    let mut app = app.lock().await;
+    if app.client.base_url() == client.base_url() {
+        let _ = app.receive_fetch(fetched);
+    }
+}
+
+// Queries the server's health, version and capabilities endpoints, locking the app only around
+// the requests like `fetch_unlocked`.
+async fn check_health(app: &Mutex<App>) {
+    let (client, in_flight) = {
+        let mut app = app.lock().await;
+        app.health_checked = Some(Instant::now());
+        (app.client.clone(), app.in_flight.clone())
+    };
+    let report = {
+        let _in_flight = InFlight::start(&in_flight);
+        health_report(&client).await
+    };
+    let mut app = app.lock().await;
+    if app.client.base_url() == client.base_url() {
+        app.receive_health(report);
+    }
+}
+
+// Sends `action` to `model` with the status shown ahead of the server's answer, as
+// `App::send_action` does, without holding the app lock.
+async fn control_unlocked(
+    app: &Mutex<App>,
+    model: &str,
+    action: ControlAction,
+) -> Result<(), String> {
+    let targets = [model.to_string()];
+    let (previous, client, retry, in_flight) = {
+        let mut app = app.lock().await;
+        // A dry run sends nothing, so there's no answer to wait for.
+        if app.dry_run {
+            return app.send_action(action, &targets).await.pop().map_or(Ok(()), Err);
+        }
+        let previous = app.apply_optimistic(&targets, action);
+        (previous, app.client.clone(), app.action_retry, app.in_flight.clone())
+    };
+    let result = {
+        let _in_flight = InFlight::start(&in_flight);
+        control_with_retries(&client, &retry, model, action.endpoint())
+            .await
+            .map_err(|e| e.to_string())
+    };
+    let mut app = app.lock().await;
+    if result.is_err() && app.client.base_url() == client.base_url() {
+        app.revert_optimistic(previous);
+    }
+    result
+}
+
+// Deletes `model` without holding the app lock. Unlike `App::remove_download` it doesn't
+// become the action undo reverts, or refresh the list.
+async fn remove_unlocked(app: &Mutex<App>, model: &str) -> Result<(), String> {
+    let (client, in_flight) = {
+        let mut app = app.lock().await;
+        if app.dry_run {
+            let url = app.client.download_url(model);
+            app.rehearse("DELETE", &url, None);
+            app.simulate(model, None);
+            return Ok(());
+        }
+        (app.client.clone(), app.in_flight.clone())
+    };
+    let result = {
+        let _in_flight = InFlight::start(&in_flight);
+        client.remove_download(model).await.map_err(|e| e.to_string())
+    };
+    let mut app = app.lock().await;
+    if result.is_ok() && app.client.base_url() == client.base_url() {
+        app.source_urls.remove(model);
+    }
+    result
+}
+
+// Restarts downloads that have been errored for longer than the grace period, logging each
+// attempt.
+async fn run_auto_restart(app: &Mutex<App>) {
+    let (due, max_attempts) = {
+        let mut app = app.lock().await;
+        let max_attempts = app.auto_restart.as_ref().map_or(0, |policy| policy.max_attempts);
+        (app.due_auto_restarts(), max_attempts)
+    };
+    for (model, attempt) in due {
+        let result = control_unlocked(app, &model, ControlAction::Restart).await;
+        let mut app = app.lock().await;
+        match result {
+            Ok(()) => app.log(
+                format!("Auto-restarted {} (attempt {}/{})", model, attempt, max_attempts),
+                false,
+            ),
+            Err(e) => app.log(format!("Auto-restart of {} failed: {}", model, e), true),
+        }
+    }
+}
+
+// Resumes downloads that have been paused for a show longer than the timeout.
+async fn run_auto_resume(app: &Mutex<App>) {
+    let due = app.lock().await.due_auto_resumes();
+    for model in due {
+        let result = control_unlocked(app, &model, ControlAction::Restart).await;
+        let mut app = app.lock().await;
+        match result {
+            Ok(()) => app.log(format!("Resumed {} after waiting out a show", model), false),
+            Err(e) => app.log(format!("Auto-resume of {} failed: {}", model, e), true),
+        }
+    }
+}
+
+// Removes downloads that have been completed for longer than the prune threshold, logging
+// each one, then refreshes once. Pruning doesn't replace the last stop or delete as the action
+// undo reverts.
+async fn run_auto_prune(app: &Mutex<App>) {
+    let due = app.lock().await.due_auto_prunes();
+    let mut pruned = false;
+    for model in due {
+        let result = remove_unlocked(app, &model).await;
+        let mut app = app.lock().await;
+        match result {
+            Ok(()) => {
+                pruned = true;
+                app.log(format!("Pruned completed download {}", model), false);
+            }
+            Err(e) => app.log(format!("Auto-prune of {} failed: {}", model, e), true),
+        }
+    }
+    if pruned {
+        fetch_unlocked(app).await;
+    }
+}
+
+// Fetches the list right away, in the background so the spinner keeps turning meanwhile. The
+// fetch holds the app lock throughout, so one manual refresh can't interleave with another.
+fn spawn_refresh(app: &Arc<Mutex<App>>) -> JoinHandle<()> {
+    let app = app.clone();
+    tokio::spawn(async move {
+        // Failures are recorded on the app and shown in the connection banner.
+        let _ = app.lock().await.fetch_downloads().await;
+    })
+}
+
+// A request made with a key press. Like refreshes, these run on their own task so the loop keeps
+// drawing, and the spinner turning, while the server answers.
+enum UserRequest {
+    Action(ControlAction),
+    // An action or fleet action that was confirmed, or needs no confirmation.
+    ConfirmedAction(ControlAction),
+    FleetAction(FleetAction),
+    ConfirmedFleetAction(FleetAction),
+    Delete(String),
+    Undo,
+    MoveInQueue { later: bool },
+    SwitchServer(usize),
+    SwitchServerUrl(String),
+    AddForm,
+}
+
+fn spawn_request(app: &Arc<Mutex<App>>, request: UserRequest) -> JoinHandle<()> {
+    let app = app.clone();
+    tokio::spawn(async move {
+        let mut app = app.lock().await;
+        match request {
+            UserRequest::Action(action) => app.request_action(action).await,
+            UserRequest::ConfirmedAction(action) => app.apply_action(action).await,
+            UserRequest::FleetAction(fleet) => app.request_fleet_action(fleet).await,
+            UserRequest::ConfirmedFleetAction(fleet) => app.apply_fleet_action(fleet).await,
+            UserRequest::Delete(model) => {
+                if let Err(e) = app.remove_download(&model).await {
+                    app.show_error(format!("Error removing download: {}", e));
+                }
+            }
+            UserRequest::Undo => app.undo().await,
+            UserRequest::MoveInQueue { later } => app.move_in_queue(later).await,
+            UserRequest::SwitchServer(index) => {
+                app.switch_server(index).await;
+            }
+            UserRequest::SwitchServerUrl(url) => app.switch_server_url(url).await,
+            UserRequest::AddForm => app.submit_add_form().await,
+        }
+    })
+}
+
+// Brings `snapshot` up to date for the next frame. While a request holds the app lock the last
+// snapshot is kept, with the in-flight count and batch progress read from their shared copies
+// so the spinner and progress banner keep moving.
+fn update_snapshot(
+    app: &Mutex<App>,
+    in_flight: &AtomicUsize,
+    batch: &std::sync::Mutex<Option<BatchProgress>>,
+    snapshot: &mut AppSnapshot,
+) {
+    if let Ok(app) = app.try_lock() {
+        *snapshot = app.snapshot();
+    }
+    snapshot.in_flight = in_flight.load(Ordering::SeqCst) > 0;
+    snapshot.batch = batch.lock().unwrap().clone();
+}
+
+async fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: Arc<Mutex<App>>,
+) -> Result<(), Box<dyn Error>> {
+    // The first download shown in the list, kept across frames and used to map clicks to rows.
+    let mut list_offset = 0;
+    let mut rows_area = Rect::default();
+    let (in_flight, batch, mut snapshot) = {
+        let app = app.lock().await;
+        (app.in_flight.clone(), app.batch.clone(), app.snapshot())
+    };
+    let mut frame = 0;
+    // The last manual refresh, so repeated presses don't queue up fetches behind it.
+    let mut manual_refresh: Option<JoinHandle<()>> = None;
+    // The request made by the last key press, while it's running, and the input that arrived
+    // meanwhile.
+    let mut request: Option<JoinHandle<()>> = None;
+    let mut pending: VecDeque<Event> = VecDeque::new();
+    loop {
+        update_snapshot(&app, &in_flight, &batch, &mut snapshot);
+        snapshot.frame = frame;
+        frame = frame.wrapping_add(1);
+        terminal.draw(|f| rows_area = ui(f, &snapshot, &mut list_offset))?;
+        let columns = list_columns(rows_area.width, snapshot.column_width);
+        let page_size = (rows_area.height as usize).max(1) * columns;
+
+        let running = request.as_ref().is_some_and(|task| !task.is_finished());
+        let queued = if running { None } else { pending.pop_front() };
+        let event = match queued {
+            Some(event) => event,
+            None => {
+                // Poll with a timeout so the list redraws on its own as the background task
+                // refreshes it.
+                if !event::poll(TICK_RATE)? {
+                    continue;
+                }
+                event::read()?
+            }
+        };
+        // Input that arrives while a request is running waits until it finishes, so it acts on
+        // the list as the request left it. Quitting doesn't wait: it abandons the request.
+        if running && !matches!(event, Event::Resize(_, _)) {
+            let quit = match &event {
+                Event::Key(key) => {
+                    snapshot.input_mode == InputMode::Normal
+                        && snapshot.keymap.action(key) == Some(KeyAction::Quit)
+                }
+                _ => false,
+            };
+            if !quit {
+                pending.push_back(event);
+                continue;
+            }
+            if let Some(task) = request.take() {
+                task.abort();
+                let _ = task.await;
+            }
+        }
+        if let Event::Resize(_, _) = event {
+            // Resize the buffers now so the redraw at the top of the loop uses the new size.
+            terminal.autoresize()?;
+            continue;
+        }
+        if let Event::Paste(text) = &event {
+            let mut app = app.lock().await;
+            if let InputMode::AddForm { focus } = app.input_mode {
+                if focus == AddField::Url {
+                    app.insert_input(&join_wrapped_urls(text));
+                } else {
+                    app.insert_input(&text.replace(['\r', '\n'], " "));
+                }
+                app.input_error = None;
+            } else if let InputMode::EditingNote { .. } = app.input_mode {
+                app.insert_input(&text.replace(['\r', '\n'], " "));
+            } else if app.input_mode == InputMode::EditingServer {
+                app.insert_input(text.trim());
+                app.input_error = None;
+            }
+            continue;
+        }
+
+        if let Event::Mouse(mouse) = event {
+            let mut app = app.lock().await;
+            if app.input_mode != InputMode::Normal {
+                continue;
+            }
+            match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let (x, y) = (mouse.column, mouse.row);
+                    let row = clicked_row(rows_area, list_offset, columns, x, y);
+                    if let Some(i) = row.filter(|&i| i < app.visible_rows().len()) {
+                        app.select_index(i);
+                    }
+                }
+                MouseEventKind::ScrollDown => app.select_next(),
+                MouseEventKind::ScrollUp => app.select_previous(),
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Event::Key(key) = event {
+            let shared = app.clone();
+            let mut app = app.lock().await;
+            let (code, modifiers) = (key.code, key.modifiers);
+            tracing::trace!(?code, ?modifiers, mode = ?app.input_mode, "key");
+            // Set by keys that send a request, which is started once the key is handled.
+            let mut user_request = None;
+
+            match app.input_mode.clone() {
+                InputMode::Normal => match app.keymap.action(&key) {
+                    Some(action) if !app.supports(action) => {
+                        if let Some(feature) = required_feature(action) {
+                            let name = feature.description();
+                            app.show_message(format!("The server doesn't support {}", name));
+                        }
+                    }
+                    Some(KeyAction::Quit) if app.request_quit() => return Ok(()),
+                    Some(KeyAction::Add) => app.open_add_form(),
+                    Some(KeyAction::Stop) => {
+                        user_request = Some(UserRequest::Action(ControlAction::Stop))
+                    }
+                    Some(KeyAction::Undo) => user_request = Some(UserRequest::Undo),
+                    Some(KeyAction::Restart) => {
+                        user_request = Some(UserRequest::Action(ControlAction::Restart))
+                    }
+                    Some(KeyAction::Pause) => {
+                        user_request = Some(UserRequest::Action(ControlAction::Pause))
+                    }
+                    Some(KeyAction::PauseAll) => {
+                        user_request = Some(UserRequest::FleetAction(FleetAction::PauseAll))
+                    }
+                    Some(KeyAction::ResumeAll) => {
+                        user_request = Some(UserRequest::FleetAction(FleetAction::ResumeAll))
+                    }
+                    Some(KeyAction::RestartErrored) => {
+                        user_request = Some(UserRequest::FleetAction(FleetAction::RestartErrored))
+                    }
+                    Some(KeyAction::ClearCompleted) => {
+                        user_request = Some(UserRequest::FleetAction(FleetAction::ClearCompleted))
+                    }
+                    Some(KeyAction::Mark) => app.toggle_mark(),
+                    Some(KeyAction::Note) => app.edit_note(),
+                    Some(KeyAction::CopyName) => app.copy_model_name(),
+                    Some(KeyAction::QueueEarlier) => {
+                        user_request = Some(UserRequest::MoveInQueue { later: false })
+                    }
+                    Some(KeyAction::QueueLater) => {
+                        user_request = Some(UserRequest::MoveInQueue { later: true })
+                    }
+                    Some(KeyAction::Delete) => {
+                        if let Some(model_name) = app.selected_model_name() {
+                            app.input_mode = InputMode::ConfirmDelete {
+                                model: model_name.to_string(),
+                            };
+                        }
+                    }
+                    // The force variants skip confirmation but report failures the same way.
+                    Some(KeyAction::ForceStop) => {
+                        user_request = Some(UserRequest::ConfirmedAction(ControlAction::Stop))
+                    }
+                    Some(KeyAction::ForceRestart) => {
+                        user_request = Some(UserRequest::ConfirmedAction(ControlAction::Restart))
+                    }
+                    Some(KeyAction::ForcePause) => {
+                        user_request = Some(UserRequest::ConfirmedAction(ControlAction::Pause))
+                    }
+                    Some(KeyAction::ForceDelete) => {
+                        user_request = app
+                            .selected_model_name()
+                            .map(|model| UserRequest::Delete(model.to_string()));
+                    }
+                    // Across columns, Left and Right move through the grid; on a group header
+                    // they still expand and collapse it.
+                    Some(KeyAction::Expand) if columns > 1 && app.selected_group.is_none() => {
+                        app.move_selection(1)
+                    }
+                    Some(KeyAction::Collapse) if columns > 1 && app.selected_group.is_none() => {
+                        app.move_selection(-1)
+                    }
+                    Some(KeyAction::Details | KeyAction::Expand)
+                        if app.selected_group.is_some() =>
+                    {
+                        app.expand_group()
+                    }
+                    Some(KeyAction::Collapse) => app.collapse_group(),
+                    Some(KeyAction::Details) if app.selected_download().is_some() => {
+                        app.input_mode = InputMode::Detail;
+                        app.detail_scroll = 0;
+                        app.message_scroll = 0;
+                    }
+                    Some(KeyAction::Watch) => {
+                        let watched = app.selected_download().map(Download::key);
+                        if let Some((model, duplicate)) = watched {
+                            app.input_mode = InputMode::Watch { model, duplicate };
+                            app.detail_scroll = 0;
+                            app.message_scroll = 0;
+                        }
+                    }
+                    Some(KeyAction::ServerLog) => {
+                        if let Some(model) = app.selected_model_name().map(str::to_string) {
+                            let task = tokio::spawn(sse::tail_logs(shared.clone(), model.clone()));
+                            app.open_server_log(model, task);
+                        }
+                    }
+                    Some(KeyAction::Filter) => {
+                        app.input_mode = InputMode::Filter;
+                    }
+                    Some(KeyAction::Log) => {
+                        app.input_mode = InputMode::Log;
+                        app.log_scroll = 0;
+                    }
+                    Some(KeyAction::Stats) => app.input_mode = InputMode::Stats,
+                    Some(KeyAction::Help) => app.input_mode = InputMode::Help,
+                    Some(KeyAction::NextServer) if !app.servers.is_empty() => {
+                        let next = app.active_server.map_or(0, |i| (i + 1) % app.servers.len());
+                        user_request = Some(UserRequest::SwitchServer(next));
+                    }
+                    Some(KeyAction::StatusFilter) => app.cycle_status_filter(),
+                    Some(KeyAction::Export) => app.export(),
+                    Some(KeyAction::Sort) => app.cycle_sort_key(),
+                    Some(KeyAction::SortDirection) => app.toggle_sort_direction(),
+                    Some(KeyAction::PauseRefresh) => app.toggle_refresh_pause(),
+                    Some(KeyAction::ChangeServer) => {
+                        let url = app.client.base_url().to_string();
+                        app.set_input(url);
+                        app.input_error = None;
+                        app.input_mode = InputMode::EditingServer;
+                    }
+                    Some(KeyAction::Compact) => app.display_mode = app.display_mode.toggled(),
+                    Some(KeyAction::Timestamps) => app.absolute_times = !app.absolute_times,
+                    Some(KeyAction::Refresh)
+                        if manual_refresh.as_ref().is_none_or(JoinHandle::is_finished) =>
+                    {
+                        manual_refresh = Some(spawn_refresh(&shared));
+                    }
+                    Some(KeyAction::Clear) if !app.filter_query.is_empty() => {
+                        app.set_filter_query(String::new());
+                    }
+                    Some(KeyAction::Clear) => app.marked.clear(),
+                    Some(KeyAction::Down) if columns > 1 => app.move_selection(columns as isize),
+                    Some(KeyAction::Up) if columns > 1 => app.move_selection(-(columns as isize)),
+                    Some(KeyAction::Down) => app.select_next(),
+                    Some(KeyAction::Up) => app.select_previous(),
+                    Some(KeyAction::PageDown) => app.move_selection(page_size as isize),
+                    Some(KeyAction::PageUp) => app.move_selection(-(page_size as isize)),
+                    Some(KeyAction::NextError) => app.select_problem(true),
+                    Some(KeyAction::PreviousError) => app.select_problem(false),
+                    Some(KeyAction::First) => app.select_first(),
+                    Some(KeyAction::Last) => app.select_last(),
+                    // Number keys pick a server directly, unless they're bound to an action.
+                    None => {
+                        if let KeyCode::Char(c @ '1'..='9') = key.code {
+                            let index = c as usize - '1' as usize;
+                            user_request = Some(UserRequest::SwitchServer(index));
+                        }
+                    }
+                    _ => {}
+                },
+                InputMode::AddForm { focus } => match key.code {
+                    KeyCode::Enter => user_request = Some(UserRequest::AddForm),
+                    KeyCode::Tab => app.focus_add_field(focus.next(false)),
+                    KeyCode::BackTab => app.focus_add_field(focus.next(true)),
+                    KeyCode::Esc => app.close_add_form(),
+                    code => {
+                        if app.edit_input(code) {
+                            app.input_error = None;
+                        }
+                    }
+                },
+                InputMode::ConfirmDelete { model } => match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        app.input_mode = InputMode::Normal;
+                        user_request = Some(UserRequest::Delete(model));
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::Confirm { action, .. } => match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        app.input_mode = InputMode::Normal;
+                        user_request = Some(UserRequest::ConfirmedAction(action));
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::ConfirmFleet { action, .. } => match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        app.input_mode = InputMode::Normal;
+                        user_request = Some(UserRequest::ConfirmedFleetAction(action));
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::EditingNote { model } => match key.code {
+                    KeyCode::Enter => {
+                        let note = app.take_input();
+                        app.input_mode = InputMode::Normal;
+                        app.set_note(&model, &note);
+                    }
+                    KeyCode::Esc => {
+                        app.take_input();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    code => {
+                        app.edit_input(code);
+                    }
+                },
+                InputMode::EditingServer => match key.code {
+                    KeyCode::Enter => match validate_server_url(&app.input_buffer) {
+                        Ok(url) => {
+                            app.take_input();
+                            app.input_error = None;
+                            app.input_mode = InputMode::Normal;
+                            user_request = Some(UserRequest::SwitchServerUrl(url));
+                        }
+                        Err(e) => app.input_error = Some(e),
+                    },
+                    KeyCode::Esc => {
+                        app.take_input();
+                        app.input_error = None;
+                        app.input_mode = InputMode::Normal;
+                    }
+                    code => {
+                        if app.edit_input(code) {
+                            app.input_error = None;
+                        }
+                    }
+                },
+                InputMode::ConfirmQuit { .. } => match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(()),
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::Filter => match key.code {
+                    KeyCode::Enter => app.input_mode = InputMode::Normal,
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                        app.set_filter_query(String::new());
+                    }
+                    KeyCode::Char(c) => {
+                        let mut query = app.filter_query.clone();
+                        query.push(c);
+                        app.set_filter_query(query);
+                    }
+                    KeyCode::Backspace => {
+                        let mut query = app.filter_query.clone();
+                        query.pop();
+                        app.set_filter_query(query);
+                    }
+                    KeyCode::Down => app.select_next(),
+                    KeyCode::Up => app.select_previous(),
+                    _ => {}
+                },
+                InputMode::Log => match key.code {
+                    KeyCode::Esc => app.input_mode = InputMode::Normal,
+                    _ if app.keymap.action(&key) == Some(KeyAction::Log) => {
+                        app.input_mode = InputMode::Normal
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => app.scroll_log(true),
+                    KeyCode::Up | KeyCode::Char('k') => app.scroll_log(false),
+                    KeyCode::Char('q') if app.request_quit() => return Ok(()),
+                    _ => {}
+                },
+                InputMode::Detail | InputMode::Watch { .. } => match key.code {
+                    KeyCode::Esc => app.input_mode = InputMode::Normal,
+                    KeyCode::Enter if app.input_mode == InputMode::Detail => {
+                        app.input_mode = InputMode::Normal
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => app.scroll_detail(true),
+                    KeyCode::Up | KeyCode::Char('k') => app.scroll_detail(false),
+                    KeyCode::PageDown => app.scroll_message(true),
+                    KeyCode::PageUp => app.scroll_message(false),
+                    KeyCode::Char('o') => app.open_selected_source(),
+                    KeyCode::Char('c') => app.copy_status_message(),
+                    KeyCode::Char('q') if app.request_quit() => return Ok(()),
+                    _ => {}
+                },
+                InputMode::Help => match key.code {
+                    KeyCode::Esc => app.input_mode = InputMode::Normal,
+                    _ if app.keymap.action(&key) == Some(KeyAction::Help) => {
+                        app.input_mode = InputMode::Normal
+                    }
+                    _ => {}
+                },
+                InputMode::ServerLog => match key.code {
+                    KeyCode::Esc => app.close_server_log(),
+                    _ if app.keymap.action(&key) == Some(KeyAction::ServerLog) => {
+                        app.close_server_log()
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => app.scroll_server_log(true),
+                    KeyCode::Up | KeyCode::Char('k') => app.scroll_server_log(false),
+                    KeyCode::End | KeyCode::Char('G') => {
+                        if let Some(log) = app.server_log.as_mut() {
+                            log.back = 0;
+                        }
+                    }
+                    KeyCode::Char('q') if app.request_quit() => return Ok(()),
+                    _ => {}
+                },
+                InputMode::Stats => match key.code {
+                    KeyCode::Esc => app.input_mode = InputMode::Normal,
+                    _ if app.keymap.action(&key) == Some(KeyAction::Stats) => {
+                        app.input_mode = InputMode::Normal
+                    }
+                    KeyCode::Char('q') if app.request_quit() => return Ok(()),
+                    _ => {}
+                },
+            }
+
+            if let Some(user_request) = user_request {
+                request = Some(spawn_request(&shared, user_request));
+            }
+            // Closed prompts shouldn't stay on screen while the request they started runs.
+            snapshot = app.snapshot();
+        }
+    }
+}
+
+// Hands `url` to the platform's default opener without waiting for it.
+fn open_in_browser(url: &str) -> io::Result<()> {
+    // Only pass on real URLs, so a stray value can't be taken as an option by the opener.
+    let url = Url::parse(url)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    command
+        .arg(url.as_str())
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+// Reads the add form's optional fields, indexed by `AddField`. Blank fields are left unset; a
+// field that doesn't parse is returned with the message to show.
+fn add_form_options(values: &[String; 4]) -> Result<AddOptions, (AddField, String)> {
+    let value = |field: AddField| {
+        let value = values[field.index()].trim();
+        (!value.is_empty()).then(|| value.to_string())
+    };
+    let priority = match value(AddField::Priority) {
+        Some(priority) => Some(priority.parse().map_err(|_| {
+            let message = format!("Invalid priority '{}': expected a whole number", priority);
+            (AddField::Priority, message)
+        })?),
+        None => None,
+    };
+    Ok(AddOptions {
+        priority,
+        output_path: value(AddField::OutputPath),
+        quality: value(AddField::Quality),
+    })
+}
+
+// Trims the typed URL and checks that it is absolute with a host, returning it in the
+// normalized form the `url` crate produces.
+fn validate_download_url(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Enter a URL to download".to_string());
+    }
+
+    let url = Url::parse(input).map_err(|e| format!("Invalid URL: {}", e))?;
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("URL must include a host, e.g. https://example.com/model".to_string());
+    }
+    Ok(url.to_string())
+}
+
+// Checks a server URL typed at runtime, returning it without a trailing slash so request paths
+// can be appended to it.
+fn validate_server_url(input: &str) -> Result<String, String> {
+    let input = input.trim().trim_end_matches('/');
+    if input.is_empty() {
+        return Err("Enter the downloader's URL".to_string());
+    }
+
+    let url = Url::parse(input).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("URL must start with http:// or https://".to_string());
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("URL must include a host, e.g. http://localhost:8080".to_string());
+    }
+    Ok(input.to_string())
+}
+
+fn url_list(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+// Cleans pasted text for the add box: a URL that was wrapped across lines is joined back
+// together, while line breaks that separate one URL from the next are kept so a pasted list
+// still adds each entry. Blank lines and surrounding whitespace are dropped.
+fn join_wrapped_urls(text: &str) -> String {
+    let mut joined = String::new();
+    for line in text.split(['\r', '\n']).map(str::trim).filter(|line| !line.is_empty()) {
+        if !joined.is_empty() && (line.contains("://") || line.starts_with('#')) {
+            joined.push('\n');
+        }
+        joined.push_str(line);
+    }
+    joined
+}
+
+// Compact relative time for list rows: "45s", "12m", "2h 15m", "3d 4h". Negative durations,
+// from clock skew between here and the server, show as "0s".
+fn humanize_duration(elapsed: chrono::Duration) -> String {
+    let secs = elapsed.num_seconds().max(0);
+    let (mins, hours, days) = (secs / 60, secs / 3600, secs / 86400);
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if mins < 60 {
+        format!("{}m", mins)
+    } else if hours < 24 {
+        format!("{}h {}m", hours, mins % 60)
+    } else {
+        format!("{}d {}h", days, hours % 24)
+    }
+}
+
+// `at` as a clock time in the local timezone, for matching against other wall-clock logs.
+fn local_time(at: DateTime<Utc>, format: &str) -> String {
+    at.with_timezone(&Local).format(format).to_string()
+}
+
+fn push_history(
+    history: &mut VecDeque<(DateTime<Utc>, HistoryEvent)>,
+    at: DateTime<Utc>,
+    event: HistoryEvent,
+) {
+    history.push_back((at, event));
+    if history.len() > HISTORY_LIMIT {
+        history.pop_front();
+    }
+}
+
+// Case-insensitive substring match used by the list filter; an empty query matches everything.
+fn matches_filter(model_name: &str, query: &str) -> bool {
+    query.is_empty() || model_name.to_lowercase().contains(&query.to_lowercase())
+}
+
+// Rows with many retries are tinted so downloads stuck in a retry loop stand out. The list's
+// highlight style is patched on top of this, so the selected row still stands out.
+fn retry_style(theme: &Theme, retry_count: u32) -> Style {
+    if retry_count >= RETRY_ALERT_THRESHOLD {
+        theme.error
+    } else if retry_count > RETRY_WARN_THRESHOLD {
+        theme.warning
+    } else {
+        Style::default()
+    }
+}
+
+// Only entries that are actively downloading can be overdue.
+fn is_overdue(download: &Download, expected: Duration) -> bool {
+    download.status == DownloadStatus::Downloading
+        && (Utc::now() - download.start_time).to_std().unwrap_or_default() > expected
+}
+
+// For servers that report no byte progress, this measures run time against the expected
+// duration instead.
+fn elapsed_gauge(theme: &Theme, download: &Download, expected: Duration) -> Gauge<'static> {
+    let elapsed = Utc::now() - download.start_time;
+    let ratio = elapsed.to_std().unwrap_or_default().as_secs_f64() / expected.as_secs_f64();
+    let expected_str = humanize_duration(chrono::Duration::from_std(expected).unwrap_or_default());
+
+    let (style, label) = if is_overdue(download, expected) {
+        let label = format!(
+            "{} elapsed, overdue (expected {})",
+            humanize_duration(elapsed),
+            expected_str
+        );
+        (theme.error, label)
+    } else {
+        (theme.success, format!("{} of {}", humanize_duration(elapsed), expected_str))
+    };
+
+    Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title("Elapsed"))
+        .gauge_style(style)
+        .ratio(ratio.clamp(0.0, 1.0))
+        .label(label)
+}
+
+// A gauge of real byte progress, if the server reports both the bytes so far and the total.
+fn progress_gauge(
+    theme: &Theme,
+    download: &Download,
+    rate: Option<f64>,
+) -> Option<Gauge<'static>> {
+    let total = download.total_bytes.filter(|&total| total > 0)?;
+    let bytes = download.bytes_downloaded?;
+    let ratio = (bytes as f64 / total as f64).clamp(0.0, 1.0);
+
+    let mut label = format!(
+        "{} of {} ({:.0}%)",
+        format_bytes(bytes),
+        format_bytes(total),
+        ratio * 100.0
+    );
+    if let Some(rate) = rate {
+        label.push_str(&format!(" at {}", format_rate(rate)));
+    }
+
+    Some(
+        Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title("Progress"))
+            .gauge_style(theme.success)
+            .ratio(ratio)
+            .label(label),
+    )
+}
+
+// A byte count in decimal units, e.g. "512 B" or "4.2 MB".
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["kB", "MB", "GB", "TB", "PB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn format_rate(bytes_per_sec: f64) -> String {
+    format!("{}/s", format_bytes(bytes_per_sec.round() as u64))
+}
+
+// How many downloads fall in each category, in the order the summary line shows them.
+fn category_counts<'a, I>(downloads: I) -> Vec<(StatusCategory, usize)>
+where
+    I: IntoIterator<Item = &'a Download> + Clone,
+{
+    [
+        StatusCategory::Active,
+        StatusCategory::Queued,
+        StatusCategory::Paused,
+        StatusCategory::Errored,
+        StatusCategory::Completed,
+    ]
+    .into_iter()
+    .map(|category| {
+        let count = downloads
+            .clone()
+            .into_iter()
+            .filter(|d| d.status.category() == category)
+            .count();
+        (category, count)
+    })
+    .collect()
+}
+
+// The optional server feature an action depends on, if any.
+fn required_feature(action: KeyAction) -> Option<Feature> {
+    match action {
+        KeyAction::Delete | KeyAction::ForceDelete | KeyAction::ClearCompleted => {
+            Some(Feature::Delete)
+        }
+        KeyAction::Pause | KeyAction::ForcePause | KeyAction::PauseAll | KeyAction::ResumeAll => {
+            Some(Feature::Resume)
+        }
+        KeyAction::QueueEarlier | KeyAction::QueueLater => Some(Feature::Queue),
+        KeyAction::ServerLog => Some(Feature::Logs),
+        _ => None,
+    }
+}
+
+// Fleet-wide aggregates for the stats view.
+#[derive(Debug, PartialEq)]
+struct FleetStats {
+    count: usize,
+    // Uptimes are measured from each download's start time.
+    mean_uptime: Option<chrono::Duration>,
+    median_uptime: Option<chrono::Duration>,
+    total_retries: u64,
+    category_counts: Vec<(StatusCategory, usize)>,
+    // The active download that started longest ago, with its uptime.
+    oldest_running: Option<(String, chrono::Duration)>,
+}
+
+fn fleet_stats(downloads: &[Download], now: DateTime<Utc>) -> FleetStats {
+    let mut uptimes: Vec<chrono::Duration> =
+        downloads.iter().map(|d| now - d.start_time).collect();
+    uptimes.sort();
+    let mean_uptime = (!uptimes.is_empty()).then(|| {
+        let total: i64 = uptimes.iter().map(chrono::Duration::num_seconds).sum();
+        chrono::Duration::seconds(total / uptimes.len() as i64)
+    });
+    let median_uptime = (!uptimes.is_empty()).then(|| {
+        let mid = uptimes.len() / 2;
+        if uptimes.len().is_multiple_of(2) {
+            (uptimes[mid - 1] + uptimes[mid]) / 2
+        } else {
+            uptimes[mid]
+        }
+    });
+
+    FleetStats {
+        count: downloads.len(),
+        mean_uptime,
+        median_uptime,
+        total_retries: downloads.iter().map(|d| u64::from(d.retry_count)).sum(),
+        category_counts: category_counts(downloads),
+        oldest_running: downloads
+            .iter()
+            .filter(|d| d.status.category() == StatusCategory::Active)
+            .min_by_key(|d| d.start_time)
+            .map(|d| (d.model_name.clone(), now - d.start_time)),
+    }
+}
+
+// One line of fleet-wide totals, e.g. "Total: 5  Active: 2  Paused: 1  Errored: 1 ...".
+fn summary_line(snapshot: &AppSnapshot) -> Paragraph<'static> {
+    let theme = &snapshot.theme;
+    let mut spans = vec![Span::styled(
+        format!("Total: {}", snapshot.total_downloads),
+        theme.header,
+    )];
+    // Servers without a queue never report queued downloads, so an empty queue isn't counted.
+    let counts = snapshot.category_counts.iter().filter(|(category, count)| {
+        *category != StatusCategory::Queued || *count > 0
+    });
+    for (category, count) in counts {
+        spans.push(Span::raw(format!("  {}: ", category)));
+        spans.push(Span::styled(count.to_string(), theme.category(*category)));
+    }
+    if snapshot.dry_run {
+        spans.push(Span::styled(
+            "  DRY RUN",
+            theme.warning.add_modifier(Modifier::BOLD),
+        ));
+    }
+    if snapshot.paused_refresh {
+        spans.push(Span::styled(
+            "  PAUSED",
+            theme.warning.add_modifier(Modifier::BOLD),
+        ));
+    }
+    if snapshot.in_flight {
+        spans.push(Span::styled(
+            format!("  {}", SPINNER_FRAMES[snapshot.frame % SPINNER_FRAMES.len()]),
+            theme.accent,
+        ));
+    }
+    Paragraph::new(Spans::from(spans))
+}
+
+// How old the list is, if it's older than the stale threshold while the server still counts
+// as connected. A disconnect has its own banner, and a paused list is old on purpose.
+fn stale_age(snapshot: &AppSnapshot) -> Option<Duration> {
+    let age = snapshot.last_refresh?.elapsed();
+    (snapshot.connection_ok && !snapshot.paused_refresh && age > snapshot.stale_after)
+        .then_some(age)
+}
+
+fn connection_banner(snapshot: &AppSnapshot) -> Paragraph<'static> {
+    let since = match snapshot.last_refresh {
+        Some(last) => format!("last successful refresh {}s ago", last.elapsed().as_secs()),
+        None => "no successful refresh yet".to_string(),
+    };
+    let mut text = format!("Disconnected — {}", since);
+    if snapshot.failure_streak > 1 {
+        text.push_str(&format!(
+            ", {} failures in a row, retrying every {}s",
+            snapshot.failure_streak,
+            snapshot.poll_delay.as_secs()
+        ));
+    }
+    if let Some(error) = &snapshot.last_error {
+        text.push_str(&format!(" ({})", error));
+    }
+
+    Paragraph::new(text).style(snapshot.theme.alert)
+}
+
+fn detail_text(
+    theme: &Theme,
+    download: &Download,
+    note: Option<&String>,
+    last_restart: Option<DateTime<Utc>>,
+    local_format: Option<&str>,
+) -> Text<'static> {
+    let label = |name: &str| Span::styled(format!("{:<20}", name), theme.header);
+    let now = Utc::now();
+    // UTC as the server reports it, unless the list is showing local clock times.
+    let stamp = |at: DateTime<Utc>| match local_format {
+        Some(format) => local_time(at, format),
+        None => at.to_rfc3339(),
+    };
+    // The message is shown in full below, so the status line only names the state.
+    let status = match &download.status {
+        DownloadStatus::Error { message: Some(_) } => "Error".to_string(),
+        DownloadStatus::Retrying { message: Some(_) } => "Retrying".to_string(),
+        status => status.to_string(),
+    };
+
+    let mut lines = vec![
+        Spans::from(vec![label("Model"), Span::raw(download.display_name())]),
+        Spans::from(vec![label("Status"), Span::raw(status)]),
+        Spans::from(vec![
+            label("Started"),
+            Span::raw(stamp(download.start_time)),
+        ]),
+        Spans::from(vec![
+            label("Last Status Change"),
+            Span::raw(format!(
+                "{} ({} ago)",
+                stamp(download.last_status_change),
+                humanize_duration(now - download.last_status_change)
+            )),
+        ]),
+        Spans::from(vec![label("Retries"), Span::raw(download.retry_count.to_string())]),
+        Spans::from(vec![
+            label("Last Restart"),
+            Span::raw(match last_restart {
+                Some(at) => format!("{} ({} ago)", stamp(at), humanize_duration(now - at)),
+                None => "none seen".to_string(),
+            }),
+        ]),
+        Spans::from(vec![
+            label("Uptime"),
+            Span::raw(humanize_duration(now - download.start_time)),
+        ]),
+        Spans::from(vec![
+            label("Source"),
+            Span::raw(download.source_url.clone().unwrap_or_else(|| "unknown".to_string())),
+        ]),
+        Spans::from(vec![
+            label("Note"),
+            Span::raw(note.cloned().unwrap_or_else(|| "none (t to add)".to_string())),
+        ]),
+    ];
+    // Long messages get their own lines at the end, where they can wrap and scroll freely.
+    if let Some(message) = download.status.message() {
+        lines.push(Spans::from(label("Message")));
+        lines.extend(
+            message
+                .lines()
+                .map(|line| Spans::from(Span::styled(line.to_string(), theme.error))),
+        );
+    }
+    Text::from(lines)
+}
+
+// How many rows `text` takes when word-wrapped to `width` columns, as `Wrap` lays it out.
+fn wrapped_height(text: &Text, width: u16) -> u16 {
+    let width = width.max(1) as usize;
+    let rows: usize = text
+        .lines
+        .iter()
+        .map(|spans| {
+            let line: String = spans.0.iter().map(|span| span.content.as_ref()).collect();
+            let mut rows = 1;
+            let mut used = 0;
+            for word in line.split_inclusive(' ') {
+                let len = word.chars().count();
+                if used > 0 && used + len > width {
+                    rows += 1;
+                    used = 0;
+                }
+                // Words longer than a row are broken across as many rows as they need.
+                if len > width {
+                    rows += (len - 1) / width;
+                    used = (len - 1) % width + 1;
+                } else {
+                    used += len;
+                }
+            }
+            rows
+        })
+        .sum();
+    rows.min(u16::MAX as usize) as u16
+}
+
+// The watch view's one-line summary, with the status set apart so it reads at a glance.
+fn watch_headline(theme: &Theme, download: &Download) -> Spans<'static> {
+    let now = Utc::now();
+    let status = match &download.status {
+        DownloadStatus::Error { .. } => "ERROR".to_string(),
+        DownloadStatus::Retrying { .. } => "RETRYING".to_string(),
+        status => status.to_string().to_uppercase(),
+    };
+    Spans::from(vec![
+        Span::styled(
+            format!(" {} ", status),
+            theme.status(&download.status).add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        ),
+        Span::raw(format!(
+            "  {} retries  ·  {} elapsed  ·  {} in this state",
+            download.retry_count,
+            humanize_duration(now - download.start_time),
+            humanize_duration(now - download.last_status_change)
+        )),
+    ])
+}
+
+fn history_text(theme: &Theme, history: &[(DateTime<Utc>, HistoryEvent)]) -> Text<'static> {
+    // Newest first, so the latest transition is visible without scrolling.
+    Text::from(
+        history
+            .iter()
+            .rev()
+            .map(|(at, event)| {
+                let event = match event {
+                    HistoryEvent::Status(status) => Span::raw(status.to_string()),
+                    HistoryEvent::Restarted => {
+                        Span::styled("Restarted by the server", theme.accent)
+                    }
+                };
+                Spans::from(vec![
+                    Span::styled(format!("{}  ", at.format("%Y-%m-%d %H:%M:%S")), theme.muted),
+                    event,
+                ])
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+// The detail popup, or with `watching` the watch view, which adds a large status headline.
+fn render_detail<B: Backend>(
+    f: &mut Frame<B>,
+    snapshot: &AppSnapshot,
+    area: Rect,
+    watching: Option<&str>,
+) {
+    f.render_widget(Clear, area);
+
+    let title = match watching {
+        Some(model) => format!(
+            "Watching {} (Esc to return, PgUp/PgDn to scroll, c to copy message)",
+            model
+        ),
+        None => "Download Details (PgUp/PgDn to scroll, c to copy message, o to open source)"
+            .to_string(),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let download = match &snapshot.selected_download {
+        Some(download) => download,
+        None => {
+            let detail = Paragraph::new("This download is no longer tracked by the server.");
+            f.render_widget(detail, inner);
+            return;
+        }
+    };
+
+    let show_gauge = download.status == DownloadStatus::Downloading;
+    let history_height = (snapshot.selected_history.len() as u16 + 1).min(inner.height / 2);
+    let headline_height = if watching.is_some() { 2 } else { 0 };
+    let detail_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(headline_height),
+                Constraint::Min(0),
+                Constraint::Length(if show_gauge { 3 } else { 0 }),
+                Constraint::Length(history_height),
+            ]
+            .as_ref(),
+        )
+        .split(inner);
+    let (headline_area, detail_chunks) = (detail_chunks[0], &detail_chunks[1..]);
+    if watching.is_some() {
+        f.render_widget(Paragraph::new(watch_headline(&snapshot.theme, download)), headline_area);
+    }
+
+    let note = snapshot.notes.get(&download.model_name);
+    let last_restart = snapshot.restarts.get(&download.key()).copied();
+    let local_format = snapshot.absolute_times.then_some(snapshot.timestamp_format.as_str());
+    let text = detail_text(&snapshot.theme, download, note, last_restart, local_format);
+    let area = detail_chunks[0];
+    let overflow = wrapped_height(&text, area.width).saturating_sub(area.height);
+    snapshot.message_overflow.store(overflow, Ordering::SeqCst);
+    let detail = Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .scroll((snapshot.message_scroll.min(overflow), 0));
+    f.render_widget(detail, detail_chunks[0]);
+
+    if show_gauge {
+        let rate = snapshot.rates.get(&download.key()).copied();
+        match progress_gauge(&snapshot.theme, download, rate) {
+            Some(gauge) => f.render_widget(gauge, detail_chunks[1]),
+            None => {
+                let gauge = elapsed_gauge(&snapshot.theme, download, snapshot.expected_duration);
+                f.render_widget(gauge, detail_chunks[1]);
+            }
+        }
+    }
+
+    let history = Paragraph::new(history_text(&snapshot.theme, &snapshot.selected_history))
+        .scroll((snapshot.detail_scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .title("Status History (↑/↓ to scroll)"),
+        );
+    f.render_widget(history, detail_chunks[2]);
+}
+
+fn log_text(theme: &Theme, log: &[LogEntry]) -> Text<'static> {
+    // Newest first, like the status history.
+    Text::from(
+        log.iter()
+            .rev()
+            .flat_map(|entry| {
+                let style = if entry.is_error {
+                    theme.error
+                } else {
+                    Style::default()
+                };
+                // Continuation lines, as in a logged response body, are indented under the
+                // first.
+                let mut lines = entry.message.lines();
+                let first = Spans::from(vec![
+                    Span::styled(format!("{}  ", entry.at.format("%H:%M:%S")), theme.muted),
+                    Span::styled(lines.next().unwrap_or_default().to_string(), style),
+                ]);
+                let rest = lines.map(move |line| {
+                    Spans::from(Span::styled(format!("{:10}{}", "", line), style))
+                });
+                std::iter::once(first).chain(rest)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+// The first visible list row after rendering with `selected`, following the same rules tui
+// uses to scroll a list of single-line items: stay put unless the selection leaves the view.
+fn list_scroll_offset(previous: usize, selected: Option<usize>, len: usize, rows: usize) -> usize {
+    let selected = match selected {
+        Some(selected) if len > 0 && rows > 0 => selected.min(len - 1),
+        // tui resets the offset when nothing is selected.
+        None => return 0,
+        _ => return previous,
+    };
+    let offset = previous.min(len - 1);
+    if selected >= offset + rows {
+        selected + 1 - rows
+    } else {
+        offset.min(selected)
+    }
+}
+
+// How many columns the list is split into: as many of `column_width` as fit, or one when
+// columns are turned off.
+fn list_columns(width: u16, column_width: u16) -> usize {
+    match column_width {
+        0 => 1,
+        column_width => (width / column_width).max(1) as usize,
+    }
+}
+
+// The list's rows split into `columns` side by side, with a space between neighbours.
+fn column_areas(rows_area: Rect, columns: usize) -> Vec<Rect> {
+    let columns = columns as u16;
+    let width = rows_area.width / columns;
+    (0..columns)
+        .map(|c| Rect {
+            x: rows_area.x + c * width,
+            width: if c + 1 == columns {
+                rows_area.width - c * width
+            } else {
+                width.saturating_sub(1)
+            },
+            ..rows_area
+        })
+        .collect()
+}
+
+// Index of the row under a click at (`column`, `row`), if it landed among the list's rows.
+// Rows flow left to right across the columns, a line at a time.
+fn clicked_row(
+    rows_area: Rect,
+    offset: usize,
+    columns: usize,
+    column: u16,
+    row: u16,
+) -> Option<usize> {
+    let inside_y = row >= rows_area.y && row < rows_area.bottom();
+    let within = column_areas(rows_area, columns)
+        .iter()
+        .position(|area| column >= area.x && column < area.right())?;
+    inside_y.then(|| offset + (row - rows_area.y) as usize * columns + within)
+}
+
+// One line of the download list. Downloads in a group are indented under its header.
+fn download_row(snapshot: &AppSnapshot, download: &Download, in_group: bool) -> ListItem<'static> {
+    let absolute = snapshot.absolute_times;
+    let (time_str, age) = if absolute {
+        (
+            local_time(download.last_status_change, &snapshot.timestamp_format),
+            local_time(download.start_time, &snapshot.timestamp_format),
+        )
+    } else {
+        (
+            humanize_duration(Utc::now() - download.last_status_change),
+            humanize_duration(Utc::now() - download.start_time),
+        )
+    };
+
+    let mut spans = Vec::new();
+    if !snapshot.marked.is_empty() {
+        let mark = if snapshot.marked.contains(&download.key()) {
+            "* "
+        } else {
+            "  "
+        };
+        spans.push(Span::raw(mark));
+    }
+    if in_group {
+        spans.push(Span::raw("  "));
+    }
+    let theme = &snapshot.theme;
+    let has_note = snapshot.notes.contains_key(&download.model_name);
+    let mode = snapshot.display_mode;
+    let status_style = theme.status(&download.status);
+    let total = download.total_bytes.filter(|&total| total > 0);
+    let progress = match (download.bytes_downloaded, total) {
+        (Some(bytes), Some(total)) => {
+            let percent = (bytes as f64 / total as f64 * 100.0).min(100.0);
+            Some(format!("{:.0}%", percent))
+        }
+        _ => None,
+    };
+    let downloading = download.status == DownloadStatus::Downloading;
+    let rate = snapshot.rates.get(&download.key()).filter(|_| downloading);
+    let rate = rate.map(|rate| format_rate(*rate));
+    // Downloading rows estimate the time left from how long past downloads took.
+    let eta = downloading.then(|| match snapshot.expected_completions.get(&download.model_name) {
+        Some(expected) => {
+            let expected = chrono::Duration::from_std(*expected).unwrap_or_default();
+            let remaining = expected - (Utc::now() - download.start_time);
+            if remaining > chrono::Duration::zero() {
+                format!("ETA ~{}", humanize_duration(remaining))
+            } else {
+                "ETA soon".to_string()
+            }
+        }
+        None => "ETA unknown".to_string(),
+    });
+
+    if let Some(template) = &snapshot.row_template {
+        let status = match mode {
+            DisplayMode::Normal => download.status.to_string(),
+            DisplayMode::Compact => download.status.abbreviation().to_string(),
+        };
+        // Fields without a value, like the rate of a paused download, are left empty.
+        spans.extend(template.segments().iter().map(|segment| match segment {
+            Segment::Text(text) => Span::raw(text.clone()),
+            Segment::Field(Field::Model) => Span::styled(download.display_name(), theme.header),
+            Segment::Field(Field::Status) => Span::styled(status.clone(), status_style),
+            Segment::Field(Field::Elapsed) => Span::raw(time_str.clone()),
+            Segment::Field(Field::Age) => Span::raw(age.clone()),
+            Segment::Field(Field::Retries) => Span::raw(download.retry_count.to_string()),
+            Segment::Field(Field::Progress) => Span::raw(progress.clone().unwrap_or_default()),
+            Segment::Field(Field::Rate) => Span::raw(rate.clone().unwrap_or_default()),
+            Segment::Field(Field::Eta) => Span::raw(eta.clone().unwrap_or_default()),
+            Segment::Field(Field::Note) => {
+                Span::styled(if has_note { "✎" } else { "" }, theme.accent)
+            }
+        }));
+    } else {
+        spans.push(Span::styled(format!("{} ", download.display_name()), theme.header));
+        if has_note {
+            spans.push(Span::styled("✎ ", theme.accent));
+        }
+        match mode {
+            DisplayMode::Normal => spans.extend([
+                Span::raw("Status: "),
+                Span::styled(download.status.to_string(), status_style),
+            ]),
+            DisplayMode::Compact => {
+                spans.push(Span::styled(download.status.abbreviation(), status_style))
+            }
+        }
+        for column in &snapshot.time_columns {
+            let text = match (column, mode, absolute) {
+                (TimeColumn::LastChange, DisplayMode::Normal, false) => {
+                    format!(", Last Change: {}", time_str)
+                }
+                (TimeColumn::LastChange, DisplayMode::Normal, true) => {
+                    format!(", Changed: {}", time_str)
+                }
+                (TimeColumn::Age, DisplayMode::Normal, false) => format!(", Age: {}", age),
+                (TimeColumn::Age, DisplayMode::Normal, true) => format!(", Started: {}", age),
+                (TimeColumn::LastChange, DisplayMode::Compact, _) => format!(" {}", time_str),
+                (TimeColumn::Age, DisplayMode::Compact, false) => format!(" age {}", age),
+                (TimeColumn::Age, DisplayMode::Compact, true) => format!(" from {}", age),
+            };
+            spans.push(Span::raw(text));
+        }
+        if let Some(progress) = progress {
+            spans.push(Span::raw(format!(" {}", progress)));
+        }
+        if let Some(rate) = rate {
+            spans.push(Span::raw(format!(" {}", rate)));
+        }
+        if let Some(eta) = eta {
+            spans.push(Span::styled(format!(" {}", eta), theme.muted));
+        }
+        if download.retry_count > 0 {
+            spans.push(Span::raw(mode.retries_label(download.retry_count)));
+        }
+    }
+    // Restarts and overdue downloads are flagged whatever the layout.
+    let restarted = snapshot.restarts.get(&download.key());
+    if restarted.is_some_and(|at| (Utc::now() - *at).to_std().unwrap_or_default() < RESTART_FLASH) {
+        spans.push(Span::styled(" ↻ restarted", theme.accent));
+    }
+    if is_overdue(download, snapshot.expected_duration) {
+        spans.push(Span::styled(" overdue", theme.error));
+    }
+
+    ListItem::new(vec![Spans::from(spans)]).style(retry_style(theme, download.retry_count))
+}
+
+// A group's header line: its name, size and how many of its downloads are in each category.
+fn group_row(snapshot: &AppSnapshot, group: &GroupRow) -> ListItem<'static> {
+    let theme = &snapshot.theme;
+    let mut spans = Vec::new();
+    if !snapshot.marked.is_empty() {
+        spans.push(Span::raw("  "));
+    }
+    let arrow = if group.expanded { "▾ " } else { "▸ " };
+    spans.push(Span::styled(arrow, theme.accent));
+    spans.push(Span::styled(group.name.clone(), theme.header));
+    spans.push(Span::raw(format!(" ({})", group.len)));
+    for (category, count) in group.counts.iter().filter(|(_, count)| *count > 0) {
+        spans.push(Span::raw(format!("  {}: ", category)));
+        spans.push(Span::styled(count.to_string(), theme.category(*category)));
+    }
+    ListItem::new(Spans::from(spans))
+}
+
+// `list_offset` lives outside the snapshot because the list's scroll position has to persist
+// from one frame to the next. Returns the area the list's rows are drawn in, which the event
+// loop uses for paging and mouse clicks.
+fn ui<B: Backend>(f: &mut Frame<B>, snapshot: &AppSnapshot, list_offset: &mut usize) -> Rect {
+    let stale = stale_age(snapshot);
+    let show_banner = !snapshot.connection_ok
+        || stale.is_some()
+        || snapshot.batch.is_some()
+        || snapshot.banner.as_ref().is_some();
+    let banner_height = if show_banner { 1 } else { 0 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Length(banner_height),
+                Constraint::Min(0),
+                Constraint::Length(snapshot.display_mode.shortcuts_height()),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+    let (summary_area, banner_area, list_area, shortcuts_area) =
+        (chunks[0], chunks[1], chunks[2], chunks[3]);
+
+    f.render_widget(summary_line(snapshot), summary_area);
+
+    if !snapshot.connection_ok {
+        f.render_widget(connection_banner(snapshot), banner_area);
+    } else if let Some(age) = stale {
+        let warning = format!(
+            "Data is stale ({} seconds old) — background refresh may have stopped",
+            age.as_secs()
+        );
+        f.render_widget(Paragraph::new(warning).style(snapshot.theme.alert), banner_area);
+    } else if let Some(batch) = &snapshot.batch {
+        let mut verb = batch.verb.to_string();
+        verb[..1].make_ascii_uppercase();
+        let progress = format!("{} {}/{}…", verb, batch.done, batch.total);
+        f.render_widget(Paragraph::new(progress).style(snapshot.theme.notice), banner_area);
+    } else if let Some(banner) = snapshot.banner.as_ref() {
+        let style = if banner.is_error {
+            snapshot.theme.alert
+        } else {
+            snapshot.theme.notice
+        };
+        f.render_widget(Paragraph::new(banner.message.clone()).style(style), banner_area);
+    }
+
+    let heading = match &snapshot.server_name {
+        Some(name) => format!("Downloads @ {}", name),
+        None => "Downloads".to_string(),
+    };
+    let mut title = String::new();
+    if let Some(category) = snapshot.status_filter {
+        title.push_str(&format!(" [{}]", category));
+    }
+    if let Some(key) = snapshot.sort_key {
+        let direction = if snapshot.sort_descending { "desc" } else { "asc" };
+        title.push_str(&format!(" (sort: {} {})", key, direction));
+    }
+    if !snapshot.marked.is_empty() {
+        title.push_str(&format!(" ({} marked)", snapshot.marked.len()));
+    }
+    if snapshot.absolute_times {
+        title.push_str(" (local times)");
+    }
+    if snapshot.input_mode == InputMode::Filter {
+        title.push_str(&format!(" (filter: {}_)", snapshot.filter_query));
+    } else if !snapshot.filter_query.is_empty() {
+        title.push_str(&format!(" (filter: {})", snapshot.filter_query));
+    }
+
+    let mut title_spans = vec![Span::raw(heading)];
+    if let Some(version) = &snapshot.server_version {
+        title_spans.push(Span::styled(format!(" {}", version), snapshot.theme.muted));
+    }
+    if let Some(healthy) = snapshot.server_health {
+        let style = if healthy {
+            snapshot.theme.success
+        } else {
+            snapshot.theme.error
+        };
+        title_spans.push(Span::styled(" ●", style));
+    }
+    title_spans.push(Span::raw(title));
+    let block = Block::default()
+        .borders(snapshot.display_mode.borders())
+        .title(Spans::from(title_spans));
+    let rows_area = block.inner(list_area);
+    let rows = rows_area.height as usize;
+    let visible = snapshot.rows.len();
+    // Wide terminals flow the list left to right across columns, so the list scrolls a line of
+    // downloads at a time and the offset stays at the start of a line.
+    let columns = if visible == 0 {
+        1
+    } else {
+        list_columns(rows_area.width, snapshot.column_width)
+    };
+    let line_offset = list_scroll_offset(
+        *list_offset / columns,
+        snapshot.selected.map(|i| i / columns),
+        visible.div_ceil(columns),
+        rows,
+    );
+    *list_offset = line_offset * columns;
+    let items: Vec<ListItem> = if snapshot.total_downloads == 0 {
+        let message = if !snapshot.connection_ok {
+            Span::styled(
+                format!("Unable to reach downloader at {}", snapshot.downloader_url),
+                snapshot.theme.error,
+            )
+        } else if snapshot.last_refresh.is_none() {
+            Span::raw(format!("Loading downloads from {}…", snapshot.downloader_url))
+        } else {
+            let add = snapshot.keymap.keys(KeyAction::Add).first();
+            let add = add.map_or_else(|| "A".to_string(), |key| key.to_string());
+            Span::raw(format!("No downloads — press [{}] to add one", add))
+        };
+        vec![ListItem::new(Spans::from(message))]
+    } else if snapshot.rows.is_empty() {
+        vec![ListItem::new("No downloads match the filter")]
+    } else {
+        // Only the rows in view are built, so the cost of a frame doesn't grow with the list.
+        snapshot
+            .rows
+            .iter()
+            .skip(*list_offset)
+            .take(rows * columns)
+            .map(|row| match row {
+                ListRow::Group(group) => group_row(snapshot, group),
+                ListRow::Download { download, in_group } => {
+                    download_row(snapshot, download, *in_group)
+                }
+            })
+            .collect()
+    };
+
+    f.render_widget(block, list_area);
+    // Item `i` lands in column `i % columns`, on line `i / columns` of it.
+    let mut column_items: Vec<Vec<ListItem>> = vec![Vec::new(); columns];
+    for (i, item) in items.into_iter().enumerate() {
+        column_items[i % columns].push(item);
+    }
+    // The items already start at the offset, so the selection is relative to it.
+    let selected = snapshot.selected.and_then(|i| i.checked_sub(*list_offset));
+    let areas = column_areas(rows_area, columns);
+    for (c, (items, area)) in column_items.into_iter().zip(areas).enumerate() {
+        let mut list_state = ListState::default();
+        let line = selected.filter(|i| i % columns == c).map(|i| i / columns);
+        list_state.select(line.filter(|&i| i < items.len()));
+        let list = List::new(items).highlight_style(snapshot.theme.selection);
+        f.render_stateful_widget(list, area, &mut list_state);
+    }
+
+    // The full list lives in the help overlay; the bar only names the most common actions.
+    let shortcuts: Vec<Span> = KeyAction::ALL
+        .into_iter()
+        .filter_map(|action| {
+            let key = snapshot.keymap.keys(action).first()?;
+            let label = format!("[{}] {} ", key, action.shortcut_label()?);
+            // Actions the server can't handle are grayed out rather than hidden.
+            let supported = required_feature(action)
+                .is_none_or(|feature| snapshot.capabilities.supports(feature));
+            Some(if supported {
+                Span::raw(label)
+            } else {
+                Span::styled(label, snapshot.theme.muted)
+            })
+        })
+        .collect();
+    let shortcuts = Paragraph::new(Text::from(Spans::from(shortcuts)));
+    let shortcuts = match snapshot.display_mode {
+        DisplayMode::Normal => {
+            shortcuts.block(Block::default().borders(Borders::ALL).title("Shortcuts"))
+        }
+        DisplayMode::Compact => shortcuts,
+    };
+
+    f.render_widget(shortcuts, shortcuts_area);
+
+    let popup_height = match snapshot.input_mode {
+        InputMode::AddForm { .. } => {
+            AddField::ALL.len() as u16 + 2 + u16::from(snapshot.input_error.is_some())
+        }
+        InputMode::EditingServer if snapshot.input_error.is_some() => 4,
+        InputMode::Detail | InputMode::Log | InputMode::ServerLog => list_area.height,
+        _ => 3,
+    };
+
+    // Watching takes over everything below the summary and banner, which stay visible so a
+    // disconnect isn't missed.
+    if let InputMode::Watch { model, duplicate } = &snapshot.input_mode {
+        let area = Rect {
+            height: list_area.height + shortcuts_area.height,
+            ..list_area
+        };
+        render_detail(f, snapshot, area, Some(&numbered_name(model, *duplicate)));
+        return rows_area;
+    }
+
+    // Help and stats are centered over the whole screen rather than anchored to the list.
+    if snapshot.input_mode == InputMode::Help {
+        render_help(f, snapshot);
+        return rows_area;
+    }
+    if let Some(stats) = &snapshot.stats {
+        render_stats(f, &snapshot.theme, stats);
+        return rows_area;
+    }
+    // Overlays are skipped entirely when the terminal is too small to hold them.
+    let popup = match overlay_rect(list_area, popup_height) {
+        Some(popup) => popup,
+        None => return rows_area,
+    };
+
+    if let InputMode::AddForm { focus } = snapshot.input_mode {
+        render_add_form(f, snapshot, popup, focus);
+    }
+
+    if let InputMode::EditingNote { model } = &snapshot.input_mode {
+        f.render_widget(Clear, popup);
+
+        let (text, column) =
+            input_window(&snapshot.input_buffer, snapshot.input_cursor, popup.width - 2);
+        f.set_cursor(popup.x + 1 + column, popup.y + 1);
+        let input = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Note for {} (Enter to save, empty to remove)", model)),
+        );
+        f.render_widget(input, popup);
+    }
+
+    if snapshot.input_mode == InputMode::EditingServer {
+        f.render_widget(Clear, popup);
+
+        let (text, column) =
+            input_window(&snapshot.input_buffer, snapshot.input_cursor, popup.width - 2);
+        f.set_cursor(popup.x + 1 + column, popup.y + 1);
+        let mut lines = vec![Spans::from(text)];
+        if let Some(error) = &snapshot.input_error {
+            lines.push(Spans::from(Span::styled(error.as_str(), snapshot.theme.error)));
+        }
+        let input = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Server URL (Enter to connect)")
+                .border_style(snapshot.theme.border),
+        );
+        f.render_widget(input, popup);
+    }
+
+    if snapshot.input_mode == InputMode::Detail {
+        render_detail(f, snapshot, popup, None);
+    }
+
+    if snapshot.input_mode == InputMode::Log {
+        f.render_widget(Clear, popup);
+
+        let log = Paragraph::new(log_text(&snapshot.theme, &snapshot.log))
+            .scroll((snapshot.log_scroll, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Log (↑/↓ to scroll, Esc to close)"),
+            );
+        f.render_widget(log, popup);
+    }
+
+    if let Some(log) = &snapshot.server_log {
+        f.render_widget(Clear, popup);
+        render_server_log(f, &snapshot.theme, log, popup);
+    }
+
+    if let InputMode::ConfirmDelete { model } = &snapshot.input_mode {
+        f.render_widget(Clear, popup);
+
+        let confirm = Paragraph::new(format!("Delete {}? (y/n)", model)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Confirm Delete")
+                .border_style(snapshot.theme.error),
+        );
+        f.render_widget(confirm, popup);
+    }
+
+    if let InputMode::Confirm { action, model } = &snapshot.input_mode {
+        f.render_widget(Clear, popup);
+
+        let prompt = if snapshot.marked.len() > 1 {
+            format!(
+                "{} {} marked downloads, including active {}? (y/n)",
+                action,
+                snapshot.marked.len(),
+                model
+            )
+        } else {
+            format!("{} active download {}? (y/n)", action, model)
+        };
+        let confirm = Paragraph::new(prompt).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Confirm {}", action))
+                .border_style(snapshot.theme.warning),
+        );
+        f.render_widget(confirm, popup);
+    }
+
+    if let InputMode::ConfirmFleet { action, count } = &snapshot.input_mode {
+        f.render_widget(Clear, popup);
+
+        let confirm = Paragraph::new(format!(
+            "{} {} {} downloads? (y/n)",
+            action,
+            count,
+            action.noun()
+        ))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Confirm {}", action))
+                .border_style(snapshot.theme.warning),
+        );
+        f.render_widget(confirm, popup);
+    }
+
+    if let InputMode::ConfirmQuit { active } = &snapshot.input_mode {
+        f.render_widget(Clear, popup);
+
+        let noun = if *active == 1 { "download is" } else { "downloads are" };
+        let confirm = Paragraph::new(format!("Quit? {} {} still active (y/n)", active, noun))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Confirm Quit")
+                    .border_style(snapshot.theme.warning),
+            );
+        f.render_widget(confirm, popup);
+    }
+
+    rows_area
+}
+
+// The lines of a download's server log that fit in `area`, newest at the bottom unless the
+// pane has been scrolled back.
+fn render_server_log<B: Backend>(f: &mut Frame<B>, theme: &Theme, log: &ServerLog, area: Rect) {
+    let mut lines: Vec<Spans> = log.lines.iter().map(|line| Spans::from(line.as_str())).collect();
+    match &log.ended {
+        Some(reason) => lines.push(Spans::from(Span::styled(reason.as_str(), theme.muted))),
+        None if lines.is_empty() => {
+            lines.push(Spans::from(Span::styled("Waiting for log lines…", theme.muted)))
+        }
+        None => {}
+    }
+    let end = lines.len().saturating_sub(log.back);
+    let start = end.saturating_sub(area.height.saturating_sub(2) as usize);
+    let following = if log.back == 0 { "following" } else { "End to follow" };
+    let title = format!("Log: {} ({}, ↑/↓ to scroll, Esc to close)", log.model, following);
+    let pane = Paragraph::new(lines[start..end].to_vec())
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(pane, area);
+}
+
+fn render_help<B: Backend>(f: &mut Frame<B>, snapshot: &AppSnapshot) {
+    let help = snapshot.keymap.help();
+    // Bindings that don't fit the terminal's height continue in further columns.
+    let rows = help.len().clamp(1, f.size().height.saturating_sub(2).max(1) as usize);
+    let columns: Vec<_> = help.chunks(rows).collect();
+    let widths: Vec<(usize, usize)> = columns
+        .iter()
+        .map(|column| {
+            let key_width = column.iter().map(|(keys, _)| keys.chars().count()).max();
+            let description_width = column.iter().map(|(_, d)| d.chars().count()).max();
+            (key_width.unwrap_or(0), description_width.unwrap_or(0))
+        })
+        .collect();
+    let lines: Vec<Spans> = (0..rows.min(help.len()))
+        .map(|row| {
+            let mut spans = Vec::new();
+            for (column, (key_width, description_width)) in columns.iter().zip(&widths) {
+                let Some((keys, description)) = column.get(row) else {
+                    break;
+                };
+                if !spans.is_empty() {
+                    spans.push(Span::raw("   "));
+                }
+                spans.push(Span::styled(
+                    format!("{:<width$}  ", keys, width = key_width),
+                    snapshot.theme.header,
+                ));
+                spans.push(Span::raw(format!(
+                    "{:<width$}",
+                    description,
+                    width = description_width
+                )));
+            }
+            Spans::from(spans)
+        })
+        .collect();
+
+    let width = lines.iter().map(Spans::width).max().unwrap_or(0) as u16 + 2;
+    let popup = match centered_rect(f.size(), width, lines.len() as u16 + 2) {
+        Some(popup) => popup,
+        None => return,
+    };
+    f.render_widget(Clear, popup);
+
+    let help = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Keys (? or Esc to close)"),
+    );
+    f.render_widget(help, popup);
+}
+
+fn render_stats<B: Backend>(f: &mut Frame<B>, theme: &Theme, stats: &FleetStats) {
+    let row = |label: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{:<16}", label), theme.header),
+            Span::raw(value),
+        ])
+    };
+    let uptime = |uptime: Option<chrono::Duration>| {
+        uptime.map_or_else(|| "-".to_string(), humanize_duration)
+    };
+
+    let mut lines = vec![
+        row("Downloads", stats.count.to_string()),
+        row("Mean uptime", uptime(stats.mean_uptime)),
+        row("Median uptime", uptime(stats.median_uptime)),
+        row("Total retries", stats.total_retries.to_string()),
+        row(
+            "Oldest running",
+            stats.oldest_running.as_ref().map_or_else(
+                || "-".to_string(),
+                |(model, uptime)| format!("{} ({})", model, humanize_duration(*uptime)),
+            ),
+        ),
+    ];
+    for (category, count) in &stats.category_counts {
+        lines.push(Spans::from(vec![
+            Span::styled(format!("{:<16}", category.to_string()), theme.header),
+            Span::styled(count.to_string(), theme.category(*category)),
+        ]));
+    }
+
+    let width = lines.iter().map(Spans::width).max().unwrap_or(0).max(30) as u16 + 2;
+    let popup = match centered_rect(f.size(), width, lines.len() as u16 + 2) {
+        Some(popup) => popup,
+        None => return,
+    };
+    f.render_widget(Clear, popup);
+
+    let stats = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Stats (S or Esc to close)"),
+    );
+    f.render_widget(stats, popup);
+}
+
+// A `width` by `height` rect centered in `area`, shrunk to fit, or `None` if there isn't
+// room for borders and at least one line of content.
+fn centered_rect(area: Rect, width: u16, height: u16) -> Option<Rect> {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    if width < 3 || height < 3 {
+        return None;
+    }
+    Some(Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    ))
+}
+
+// The area for a popup of up to `height` rows inset one cell into `area`, or `None` if
+// there isn't room for its borders and at least one line of content.
+fn overlay_rect(area: Rect, height: u16) -> Option<Rect> {
+    let width = area.width.saturating_sub(2);
+    let height = height.min(area.height.saturating_sub(2));
+    if width < 3 || height < 3 {
+        return None;
+    }
+    Some(Rect::new(area.x + 1, area.y + 1, width, height))
+}
+
+// The add form: one line per field, the focused one being edited in the input buffer, and any
+// validation error under them all.
+fn render_add_form<B: Backend>(
+    f: &mut Frame<B>,
+    snapshot: &AppSnapshot,
+    popup: Rect,
+    focus: AddField,
+) {
+    f.render_widget(Clear, popup);
+
+    let label_width = AddField::ALL.iter().map(|f| f.label().len()).max().unwrap_or(0) + 2;
+    let field_width = (popup.width.saturating_sub(2) as usize).saturating_sub(label_width);
+    let mut lines = Vec::new();
+    for (i, field) in AddField::ALL.into_iter().enumerate() {
+        let label = format!("{:<width$}", format!("{}:", field.label()), width = label_width);
+        let value = if field == focus {
+            // A pasted list is shown on one line; it's added line by line on Enter.
+            let text = snapshot.input_buffer.replace('\n', " ");
+            let (text, column) =
+                input_window(&text, snapshot.input_cursor, field_width.max(1) as u16);
+            let x = popup.x + 1 + (label_width + column as usize) as u16;
+            if i as u16 + 1 < popup.height.saturating_sub(1) && x < popup.right() {
+                f.set_cursor(x, popup.y + 1 + i as u16);
+            }
+            Span::raw(text)
+        } else if snapshot.add_form[i].is_empty() && field != AddField::Url {
+            Span::styled("optional", snapshot.theme.muted)
+        } else {
+            Span::raw(snapshot.add_form[i].replace('\n', " "))
+        };
+        let label_style = if field == focus {
+            snapshot.theme.accent
+        } else {
+            Style::default()
+        };
+        lines.push(Spans::from(vec![Span::styled(label, label_style), value]));
+    }
+    if let Some(error) = &snapshot.input_error {
+        lines.push(Spans::from(Span::styled(error.as_str(), snapshot.theme.error)));
+    }
+
+    let url = match focus {
+        AddField::Url => &snapshot.input_buffer,
+        _ => &snapshot.add_form[AddField::Url.index()],
+    };
+    let urls = url_list(url).count();
+    let title = if urls > 1 {
+        format!("Add {} downloads (Tab to move between fields, Enter to add)", urls)
+    } else {
+        "Add download (Tab to move between fields, Enter to add)".to_string()
+    };
+    let form = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(snapshot.theme.border),
+    );
+    f.render_widget(form, popup);
+}
+
+// The part of an input box's text that fits in `width` columns with the cursor (a byte
+// offset) in view, and the cursor's column within it. Text scrolls left once the cursor
+// passes the right edge.
+fn input_window(text: &str, cursor: usize, width: u16) -> (String, u16) {
+    let column = text[..cursor].chars().count();
+    let skip = (column + 1).saturating_sub(width as usize);
+    let visible = text.chars().skip(skip).take(width as usize).collect();
+    (visible, (column - skip) as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Color;
+
+    fn download(model_name: &str, status: DownloadStatus) -> Download {
+        Download {
+            model_name: model_name.to_string(),
+            status,
+            start_time: Utc::now(),
+            last_status_change: Utc::now(),
+            retry_count: 0,
+            source_url: None,
+            bytes_downloaded: None,
+            total_bytes: None,
+            queue_position: None,
+            duplicate: 0,
+        }
+    }
+
+    fn test_app() -> App {
+        App::new(&cli::Args {
+            downloader_url: "http://localhost:0".to_string(),
+            ..cli::Args::default()
+        })
+        .unwrap()
+    }
+
+    // A request the mock server received. It compares equal to its request line, e.g.
+    // "POST /downloads HTTP/1.1", so tests only look at headers and body when they matter.
+    #[derive(Debug)]
+    struct MockRequest {
+        line: String,
+        // Header names are lowercased.
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    impl PartialEq<&str> for MockRequest {
+        fn eq(&self, line: &&str) -> bool {
+            self.line == *line
+        }
+    }
+
+    // Reads one request: the head, then as much body as its Content-Length announces.
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> MockRequest {
+        use tokio::io::AsyncReadExt;
+
+        let mut data = Vec::new();
+        let mut buf = [0; 4096];
+        loop {
+            if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&data[..end]).to_string();
+                let mut lines = head.lines();
+                let line = lines.next().unwrap_or_default().to_string();
+                let headers: HashMap<String, String> = lines
+                    .filter_map(|header| header.split_once(':'))
+                    .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+                    .collect();
+                let length = headers.get("content-length").map_or(0, |n| n.parse().unwrap());
+                if data.len() >= end + 4 + length {
+                    let body = String::from_utf8_lossy(&data[end + 4..end + 4 + length]);
+                    return MockRequest {
+                        line,
+                        headers,
+                        body: body.to_string(),
+                    };
+                }
+            }
+            let n = socket.read(&mut buf).await.unwrap();
+            if n == 0 {
+                panic!("connection closed mid-request");
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    // Serves one canned response per connection, in order, and records each request.
+    async fn mock_server(
+        responses: Vec<(u16, &'static str)>,
+    ) -> (String, Arc<Mutex<Vec<MockRequest>>>) {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut socket).await;
+                recorded.lock().await.push(request);
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn app_for(url: String) -> App {
+        let mut app = App::new(&cli::Args {
+            downloader_url: url,
+            ..cli::Args::default()
+        })
+        .unwrap();
+        app.action_retry.base_delay = Duration::from_millis(1);
+        app
+    }
+
+    fn key(model_name: &str) -> DownloadKey {
+        (model_name.to_string(), 0)
+    }
+
+    fn visible_names(app: &App) -> Vec<&str> {
+        app.visible_downloads()
+            .iter()
+            .map(|d| d.model_name.as_str())
+            .collect()
+    }
+
+    fn status(json: &str) -> DownloadStatus {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn deserializes_plain_string_statuses() {
+        assert_eq!(status(r#""Downloading""#), DownloadStatus::Downloading);
+        assert_eq!(status(r#""initializing""#), DownloadStatus::Initializing);
+        assert_eq!(status(r#""Queued""#), DownloadStatus::Queued);
+        assert_eq!(status(r#""Offline""#), DownloadStatus::Offline);
+        assert_eq!(status(r#""Paused""#), DownloadStatus::Paused);
+        assert_eq!(
+            status(r#""Paused for Exclusive Show""#),
+            DownloadStatus::PausedForExclusiveShow
+        );
+        assert_eq!(
+            status(r#""Paused for Ticket Show""#),
+            DownloadStatus::PausedForTicketShow
+        );
+        assert_eq!(status(r#""Completed""#), DownloadStatus::Completed);
+        assert_eq!(
+            status(r#""Retrying""#),
+            DownloadStatus::Retrying { message: None }
+        );
+        assert_eq!(status(r#""Error""#), DownloadStatus::Error { message: None });
+    }
+
+    #[test]
+    fn deserializes_message_strings_preserving_case() {
+        assert_eq!(
+            status(r#""Error: Connection Refused""#),
+            DownloadStatus::Error {
+                message: Some("Connection Refused".to_string())
+            }
+        );
+        assert_eq!(
+            status(r#""Retrying: HTTP 503""#),
+            DownloadStatus::Retrying {
+                message: Some("HTTP 503".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn deserializes_message_objects() {
+        assert_eq!(
+            status(r#"{"ErrorWithMessage": "disk full"}"#),
+            DownloadStatus::Error {
+                message: Some("disk full".to_string())
+            }
+        );
+        assert_eq!(
+            status(r#"{"RetryingWithMessage": "stream ended"}"#),
+            DownloadStatus::Retrying {
+                message: Some("stream ended".to_string())
+            }
+        );
+        assert_eq!(
+            status(r#"{"ErrorWithMessage": null}"#),
+            DownloadStatus::Error { message: None }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_statuses() {
+        assert!(serde_json::from_str::<DownloadStatus>(r#""Exploded""#).is_err());
+        assert!(serde_json::from_str::<DownloadStatus>(r#"{"Exploded": "x"}"#).is_err());
+        assert!(
+            serde_json::from_str::<DownloadStatus>(r#"{"ErrorWithMessage": "a", "Error": "b"}"#)
+                .is_err()
+        );
+        assert!(serde_json::from_str::<DownloadStatus>("42").is_err());
+    }
+
+    #[test]
+    fn statuses_round_trip_through_json() {
+        let statuses = vec![
+            DownloadStatus::Downloading,
+            DownloadStatus::Initializing,
+            DownloadStatus::Retrying { message: None },
+            DownloadStatus::Retrying {
+                message: Some("Upstream Timeout".to_string()),
+            },
+            DownloadStatus::Offline,
+            DownloadStatus::Paused,
+            DownloadStatus::PausedForExclusiveShow,
+            DownloadStatus::PausedForTicketShow,
+            DownloadStatus::Error { message: None },
+            DownloadStatus::Error {
+                message: Some("Disk Full".to_string()),
+            },
+            DownloadStatus::Completed,
+        ];
+
+        for original in statuses {
+            let json = serde_json::to_string(&original).unwrap();
+            assert_eq!(status(&json), original, "round trip of {}", json);
+        }
+    }
+
+    #[test]
+    fn deserializes_full_download_payload() {
+        let json = r#"[
+            {"modelName": "alpha", "status": "Downloading", "startTime": "2024-01-01T00:00:00Z",
+             "lastStatusChange": "2024-01-01T00:05:00Z", "retryCount": 0},
+            {"modelName": "beta", "status": {"ErrorWithMessage": "403"}, "startTime": "2024-01-01T00:00:00Z",
+             "lastStatusChange": "2024-01-01T00:05:00Z", "retryCount": 2}
+        ]"#;
+        let downloads: Vec<Download> = serde_json::from_str(json).unwrap();
+        assert_eq!(downloads.len(), 2);
+        assert_eq!(downloads[0].status, DownloadStatus::Downloading);
+        assert_eq!(
+            downloads[1].status,
+            DownloadStatus::Error {
+                message: Some("403".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn selection_follows_model_across_reorder() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+            download("gamma", DownloadStatus::Downloading),
+        ]);
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("beta"));
+        assert_eq!(app.list_state.selected(), Some(1));
+
+        // Refreshes keep rows in place, but offline downloads still sink to the bottom.
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Offline),
+            download("beta", DownloadStatus::Downloading),
+            download("gamma", DownloadStatus::Downloading),
+        ]);
+        assert_eq!(app.selected_model_name(), Some("beta"));
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn selection_is_cleared_when_model_disappears() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+        ]);
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("beta"));
+
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        app.set_downloads(Vec::new());
+        assert_eq!(app.selected_model_name(), None);
+        assert_eq!(app.list_state.selected(), None);
+    }
+
+    #[test]
+    fn filter_matches_case_insensitive_substrings() {
+        assert!(matches_filter("SiteA_Model", ""));
+        assert!(matches_filter("SiteA_Model", "model"));
+        assert!(matches_filter("SiteA_Model", "TEA_m"));
+        assert!(!matches_filter("SiteA_Model", "siteb"));
+        assert!(!matches_filter("", "a"));
+    }
+
+    #[test]
+    fn selection_operates_over_filtered_rows() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+            download("alphabet", DownloadStatus::Downloading),
+        ]);
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("beta"));
+
+        app.set_filter_query("alpha".to_string());
+        assert_eq!(app.visible_downloads().len(), 2);
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+
+        app.select_next();
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("alphabet"));
+        assert_eq!(app.list_state.selected(), Some(1));
+
+        app.set_filter_query(String::new());
+        assert_eq!(app.selected_model_name(), Some("alphabet"));
+        assert_eq!(app.list_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn status_filter_cycles_and_limits_navigation() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Error { message: None }),
+            download("gamma", DownloadStatus::Paused),
+            download("delta", DownloadStatus::Offline),
+        ]);
+
+        app.cycle_status_filter();
+        assert_eq!(app.status_filter, Some(StatusCategory::Active));
+        app.cycle_status_filter();
+        assert_eq!(app.status_filter, Some(StatusCategory::Errored));
+        assert_eq!(app.selected_model_name(), Some("beta"));
+
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("delta"));
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("delta"));
+
+        app.cycle_status_filter();
+        assert_eq!(app.selected_model_name(), Some("gamma"));
+        app.cycle_status_filter();
+        assert_eq!(app.status_filter, Some(StatusCategory::Completed));
+        assert_eq!(app.selected_model_name(), None);
+
+        app.cycle_status_filter();
+        assert_eq!(app.status_filter, Some(StatusCategory::Queued));
+        app.cycle_status_filter();
+        assert_eq!(app.status_filter, None);
+        assert_eq!(app.visible_downloads().len(), 4);
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+    }
+
+    #[test]
+    fn sorting_is_stable_and_keeps_selection() {
+        let mut app = test_app();
+        let mut alpha = download("alpha", DownloadStatus::Paused);
+        alpha.retry_count = 2;
+        let mut beta = download("beta", DownloadStatus::Downloading);
+        beta.retry_count = 5;
+        let mut gamma = download("gamma", DownloadStatus::Downloading);
+        gamma.retry_count = 2;
+        app.set_downloads(vec![gamma, alpha, beta]);
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+
+        app.cycle_sort_key();
+        assert_eq!(app.sort_key, Some(SortKey::ModelName));
+        assert_eq!(visible_names(&app), vec!["alpha", "beta", "gamma"]);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        app.sort_key = Some(SortKey::RetryCount);
+        app.toggle_sort_direction();
+        assert_eq!(visible_names(&app), vec!["beta", "gamma", "alpha"]);
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+        assert_eq!(app.list_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn retry_delay_doubles_each_attempt() {
+        let policy = RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_millis(200),
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(200));
+        assert_eq!(policy.delay(1), Duration::from_millis(400));
+        assert_eq!(policy.delay(2), Duration::from_millis(800));
+    }
+
+    #[tokio::test]
+    async fn control_download_retries_server_errors() {
+        let (url, requests) = mock_server(vec![(503, ""), (500, ""), (200, "")]).await;
+        let app = app_for(url);
+
+        app.control_download("alpha", "pause").await.unwrap();
+        let requests = requests.lock().await;
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|r| r.line == "POST /downloads/alpha/pause HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn control_download_does_not_retry_client_errors() {
+        let (url, requests) = mock_server(vec![(404, ""), (200, "")]).await;
+        let app = app_for(url);
+
+        let error = app.control_download("alpha", "stop").await.unwrap_err();
+        assert!(error.to_string().contains("404"));
+        assert_eq!(requests.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn control_download_gives_up_after_retries() {
+        let (url, requests) = mock_server(vec![(502, ""); 5]).await;
+        let mut app = app_for(url);
+        app.action_retry.retries = 2;
+
+        assert!(app.control_download("alpha", "restart").await.is_err());
+        assert_eq!(requests.lock().await.len(), 3);
+    }
+
+    #[test]
+    fn ui_renders_from_a_snapshot() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Error { message: None }),
+        ]);
+        let snapshot = app.snapshot();
+
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 10)).unwrap();
+        let mut list_offset = 0;
+        terminal
+            .draw(|f| {
+                ui(f, &snapshot, &mut list_offset);
+            })
+            .unwrap();
+
+        let rendered: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+        assert!(rendered.contains("alpha"));
+        assert!(rendered.contains("Status: Error"));
+        assert!(rendered.contains("Total: 2  Active: 1  Paused: 0  Errored: 1  Completed: 0"));
+        assert_eq!(list_offset, 0);
+    }
+
+    #[test]
+    fn actions_target_marked_models_or_the_selection() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+            download("gamma", DownloadStatus::Downloading),
+        ]);
+        assert_eq!(model_names(&app.action_targets()), vec!["alpha"]);
+
+        app.select_next();
+        app.select_next();
+        app.toggle_mark();
+        app.select_previous();
+        app.toggle_mark();
+        assert_eq!(model_names(&app.action_targets()), vec!["beta", "gamma"]);
+
+        app.toggle_mark();
+        assert_eq!(model_names(&app.action_targets()), vec!["gamma"]);
+
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        assert!(app.marked.is_empty());
+        assert_eq!(model_names(&app.action_targets()), vec!["alpha"]);
+    }
+
+    #[tokio::test]
+    async fn batch_actions_report_counts() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, "")]).await;
+        let mut app = app_for(url);
+        app.marked.insert(key("alpha"));
+        app.marked.insert(key("beta"));
+
+        app.apply_action(ControlAction::Pause).await;
+        let requests = requests.lock().await;
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().any(|r| r.line == "POST /downloads/alpha/pause HTTP/1.1"));
+        assert!(requests.iter().any(|r| r.line == "POST /downloads/beta/pause HTTP/1.1"));
+        assert_eq!(app.banner.as_ref().unwrap().message, "Pause: 2 succeeded");
+    }
+
+    #[test]
+    fn events_replace_the_list_or_update_one_download() {
+        let mut app = test_app();
+        app.apply_event(
+            r#"[{"modelName": "alpha", "status": "Downloading", "startTime": "2024-01-01T00:00:00Z",
+                "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}]"#,
+        )
+        .unwrap();
+        assert_eq!(visible_names(&app), vec!["alpha"]);
+
+        app.apply_event(
+            r#"{"modelName": "alpha", "status": "Paused", "startTime": "2024-01-01T00:00:00Z",
+                "lastStatusChange": "2024-01-01T00:10:00Z", "retryCount": 0}"#,
+        )
+        .unwrap();
+        app.apply_event(
+            r#"{"modelName": "beta", "status": "Completed", "startTime": "2024-01-01T00:00:00Z",
+                "lastStatusChange": "2024-01-01T00:10:00Z", "retryCount": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(visible_names(&app), vec!["alpha", "beta"]);
+        assert_eq!(app.downloads[0].status, DownloadStatus::Paused);
+        assert!(app.connection_ok);
+
+        assert!(app.apply_event("not json").is_err());
+        assert_eq!(app.downloads.len(), 2);
+    }
+
+    #[test]
+    fn history_records_transitions_and_is_bounded() {
+        let mut app = test_app();
+        app.set_downloads(vec![download("alpha", DownloadStatus::Initializing)]);
+        app.set_downloads(vec![download("alpha", DownloadStatus::Initializing)]);
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        let events: Vec<&HistoryEvent> =
+            app.history[&key("alpha")].iter().map(|(_, e)| e).collect();
+        assert_eq!(
+            events,
+            vec![
+                &HistoryEvent::Status(DownloadStatus::Initializing),
+                &HistoryEvent::Status(DownloadStatus::Downloading)
+            ]
+        );
+
+        for i in 0..HISTORY_LIMIT {
+            let status = DownloadStatus::Retrying {
+                message: Some(i.to_string()),
+            };
+            app.set_downloads(vec![download("alpha", status)]);
+        }
+        assert_eq!(app.history[&key("alpha")].len(), HISTORY_LIMIT);
+        assert_eq!(
+            app.history[&key("alpha")].front().unwrap().1,
+            HistoryEvent::Status(DownloadStatus::Retrying {
+                message: Some("0".to_string())
+            })
+        );
+
+        app.set_downloads(Vec::new());
+        assert!(app.history.is_empty());
+    }
+
+    #[test]
+    fn retry_count_drops_are_recorded_as_restarts() {
+        let mut app = test_app();
+        let mut struggling = download("alpha", DownloadStatus::Retrying { message: None });
+        struggling.retry_count = 4;
+        app.set_downloads(vec![struggling.clone()]);
+        struggling.retry_count = 5;
+        app.set_downloads(vec![struggling]);
+        assert!(app.restarts.is_empty(), "a rising count is an ongoing struggle");
+
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        assert!(app.restarts.contains_key(&key("alpha")));
+        assert_eq!(app.history[&key("alpha")].back().unwrap().1, HistoryEvent::Restarted);
+        // The restart doesn't hide the status it happened after.
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        assert_eq!(app.history[&key("alpha")].len(), 3);
+
+        let last_restart = app.restarts.get(&key("alpha")).copied();
+        let text = detail_text(&Theme::default(), &app.downloads[0], None, last_restart, None);
+        assert!(text.lines.iter().any(|line| line.0[0].content.trim() == "Last Restart"));
+
+        app.set_downloads(Vec::new());
+        assert!(app.restarts.is_empty());
+    }
+
+    #[test]
+    fn paging_moves_by_a_screenful_and_stops_at_the_ends() {
+        let mut app = test_app();
+        app.set_downloads(
+            (0..10)
+                .map(|i| download(&format!("model{}", i), DownloadStatus::Downloading))
+                .collect(),
+        );
+
+        app.move_selection(4);
+        assert_eq!(app.selected_model_name(), Some("model4"));
+        app.move_selection(4);
+        app.move_selection(4);
+        assert_eq!(app.selected_model_name(), Some("model9"));
+        app.move_selection(-4);
+        assert_eq!(app.selected_model_name(), Some("model5"));
+        app.select_first();
+        assert_eq!(app.list_state.selected(), Some(0));
+        app.select_last();
+        assert_eq!(app.list_state.selected(), Some(9));
+    }
+
+    #[test]
+    fn accepts_and_normalizes_download_urls() {
+        assert_eq!(
+            validate_download_url("  https://example.com/model.bin \n"),
+            Ok("https://example.com/model.bin".to_string())
+        );
+        assert_eq!(
+            validate_download_url("HTTP://Example.COM"),
+            Ok("http://example.com/".to_string())
+        );
+        assert_eq!(
+            validate_download_url("http://localhost:8000/a b"),
+            Ok("http://localhost:8000/a%20b".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_download_urls() {
+        for input in ["", "   ", "example.com/model", "/models/a", "mailto:me@example.com"] {
+            assert!(validate_download_url(input).is_err(), "accepted {:?}", input);
+        }
+        assert!(validate_download_url("file:///tmp/model")
+            .unwrap_err()
+            .contains("host"));
+    }
+
+    #[test]
+    fn pasted_urls_lose_wrapping_but_keep_list_breaks() {
+        assert_eq!(
+            join_wrapped_urls("https://example.com/models/\r\nalpha?quality=\nhigh\n"),
+            "https://example.com/models/alpha?quality=high"
+        );
+        assert_eq!(
+            join_wrapped_urls("\n# batch\nhttps://a.test/x\n\n  https://b.test/y  "),
+            "# batch\nhttps://a.test/x\nhttps://b.test/y"
+        );
+    }
+
+    #[tokio::test]
+    async fn undo_restarts_a_stopped_download() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, "")]).await;
+        let mut app = app_for(url);
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+
+        app.apply_action(ControlAction::Stop).await;
+        assert_eq!(
+            app.last_action.as_ref().map(|(action, _)| action),
+            Some(&Action::Stopped(vec!["alpha".to_string()]))
+        );
+
+        app.undo().await;
+        assert_eq!(
+            requests.lock().await[1],
+            "POST /downloads/alpha/restart HTTP/1.1"
+        );
+        assert_eq!(app.banner.as_ref().unwrap().message, "Undone: restarted alpha");
+        assert!(app.last_action.is_none());
+    }
+
+    #[tokio::test]
+    async fn control_actions_update_status_optimistically() {
+        let (url, requests) = mock_server(vec![(200, ""), (500, "")]).await;
+        let mut app = app_for(url);
+        app.action_retry.retries = 0;
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+        ]);
+
+        app.apply_action(ControlAction::Pause).await;
+        assert_eq!(app.downloads[0].status, DownloadStatus::Paused);
+
+        // A failed request puts the previous status back.
+        app.select_next();
+        app.apply_action(ControlAction::Pause).await;
+        assert_eq!(app.downloads[1].status, DownloadStatus::Downloading);
+        assert_eq!(requests.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_actions_restore_each_duplicate_in_place() {
+        let (url, _) = mock_server(vec![(500, "")]).await;
+        let mut app = app_for(url);
+        app.action_retry.retries = 0;
+        let mut second = download("alpha", DownloadStatus::Paused);
+        second.retry_count = 3;
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading), second]);
+        let rows = |app: &App| -> Vec<_> {
+            app.downloads
+                .iter()
+                .map(|d| (d.key(), d.status.clone(), d.retry_count))
+                .collect()
+        };
+        let before = rows(&app);
+
+        app.apply_action(ControlAction::Stop).await;
+        assert_eq!(rows(&app), before);
+    }
+
+    #[test]
+    fn quitting_asks_first_only_while_downloads_are_active() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Paused),
+        ]);
+        assert!(app.request_quit());
+
+        app.confirm_quit = true;
+        assert!(!app.request_quit());
+        assert_eq!(app.input_mode, InputMode::ConfirmQuit { active: 1 });
+
+        app.input_mode = InputMode::Normal;
+        app.set_downloads(vec![download("beta", DownloadStatus::Paused)]);
+        assert!(app.request_quit());
+    }
+
+    #[tokio::test]
+    async fn switching_servers_starts_from_a_clean_slate() {
+        let (url, requests) = mock_server(vec![(200, "[]")]).await;
+        let mut app = test_app();
+        app.servers = vec![
+            ("staging".to_string(), app.client.base_url().to_string()),
+            ("prod".to_string(), url.clone()),
+        ];
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        app.toggle_mark();
+
+        assert!(app.switch_server(1).await);
+        assert_eq!(app.client.base_url(), url);
+        assert!(app.downloads.is_empty());
+        assert!(app.marked.is_empty());
+        assert!(app.history.is_empty());
+        assert_eq!(*requests.lock().await, vec!["GET /downloads HTTP/1.1"]);
+        assert_eq!(app.snapshot().server_name.as_deref(), Some("prod"));
+
+        assert!(!app.switch_server(1).await);
+        assert!(!app.switch_server(2).await);
+    }
+
+    #[tokio::test]
+    async fn health_and_version_are_optional() {
+        let (url, requests) = mock_server(vec![
+            (503, ""),
+            (200, r#"{"version": "2.4.1", "commit": "abc"}"#),
+            (404, ""),
+            (404, ""),
+            (404, ""),
+            (404, ""),
+        ])
+        .await;
+        let app = Mutex::new(app_for(url));
+
+        check_health(&app).await;
+        {
+            let app = app.lock().await;
+            assert_eq!(app.server_health, Some(false));
+            assert_eq!(app.server_version.as_deref(), Some("2.4.1"));
+        }
+        check_health(&app).await;
+        let app = app.lock().await;
+        assert_eq!((app.server_health, app.server_version.as_deref()), (None, None));
+        assert_eq!(
+            *requests.lock().await,
+            vec![
+                "GET /health HTTP/1.1",
+                "GET /version HTTP/1.1",
+                "GET /capabilities HTTP/1.1",
+                "GET /health HTTP/1.1",
+                "GET /version HTTP/1.1",
+                "GET /capabilities HTTP/1.1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unadvertised_features_are_gated() {
+        let capabilities = r#"{"apiVersion": 2, "features": ["sse", "resume"]}"#;
+        let (url, _) = mock_server(vec![(200, ""), (200, "1.0"), (200, capabilities)]).await;
+        let app = Mutex::new(app_for(url));
+        assert!(app.lock().await.supports(KeyAction::Delete));
+
+        check_health(&app).await;
+        let mut app = app.into_inner();
+        assert!(!app.supports(KeyAction::Delete));
+        assert!(!app.supports(KeyAction::QueueLater));
+        assert!(app.supports(KeyAction::Pause));
+        assert!(app.supports(KeyAction::Stop));
+        let banner = &app.banner.as_ref().unwrap().message;
+        assert!(banner.contains("API version 2"), "{}", banner);
+
+        app.change_server("http://localhost:0".to_string());
+        assert!(app.supports(KeyAction::Delete));
+    }
+
+    #[tokio::test]
+    async fn typed_server_urls_replace_the_current_server() {
+        let (url, requests) = mock_server(vec![(200, "[]")]).await;
+        let mut app = test_app();
+        app.servers = vec![("prod".to_string(), url.clone())];
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+
+        let typed = validate_server_url(&format!(" {}/ ", url)).unwrap();
+        app.switch_server_url(typed).await;
+        assert_eq!(app.client.base_url(), url);
+        assert!(app.downloads.is_empty());
+        assert_eq!(*requests.lock().await, vec!["GET /downloads HTTP/1.1"]);
+        assert_eq!(app.banner.as_ref().unwrap().message, format!("Switched server to {}", url));
+        // A URL belonging to a configured server is shown under its name.
+        assert_eq!(app.snapshot().server_name.as_deref(), Some("prod"));
+
+        assert!(validate_server_url("").is_err());
+        assert!(validate_server_url("ftp://host").is_err());
+        assert!(validate_server_url("localhost:8080").is_err());
+    }
+
+    #[test]
+    fn refreshes_merge_into_the_existing_list() {
+        let mut app = test_app();
+        app.receive_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+            download("gamma", DownloadStatus::Downloading),
+        ]);
+        app.select_index(2);
+
+        app.receive_downloads(vec![
+            download("delta", DownloadStatus::Initializing),
+            download("gamma", DownloadStatus::Paused),
+            download("alpha", DownloadStatus::Downloading),
+        ]);
+        assert_eq!(visible_names(&app), vec!["alpha", "gamma", "delta"]);
+        assert_eq!(app.downloads[1].status, DownloadStatus::Paused);
+        assert_eq!(app.selected_model_name(), Some("gamma"));
+        assert_eq!(app.history[&key("gamma")].len(), 2);
+        assert!(!app.history.contains_key(&key("beta")));
+    }
+
+    #[test]
+    fn long_status_messages_wrap_and_scroll() {
+        let text = Text::from(vec![Spans::from("aaaa bbbb cc"), Spans::from("x".repeat(25))]);
+        assert_eq!(wrapped_height(&text, 10), 2 + 3);
+        assert_eq!(wrapped_height(&text, 40), 2);
+
+        let mut app = test_app();
+        let message = "disk full ".repeat(40);
+        app.set_downloads(vec![download(
+            "alpha",
+            DownloadStatus::Error {
+                message: Some(message.clone()),
+            },
+        )]);
+        app.input_mode = InputMode::Detail;
+        let backend = tui::backend::TestBackend::new(40, 30);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let snapshot = app.snapshot();
+        terminal
+            .draw(|f| {
+                ui(f, &snapshot, &mut 0);
+            })
+            .unwrap();
+
+        let overflow = app.message_overflow.load(Ordering::SeqCst);
+        assert!(overflow > 0);
+        for _ in 0..overflow + 5 {
+            app.scroll_message(true);
+        }
+        assert_eq!(app.message_scroll, overflow);
+        app.scroll_message(false);
+        assert_eq!(app.message_scroll, overflow - 1);
+    }
+
+    #[test]
+    fn watch_mode_stays_on_its_model() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Offline),
+        ]);
+        app.input_mode = InputMode::Watch {
+            model: "alpha".to_string(),
+            duplicate: 0,
+        };
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("beta"));
+        assert_eq!(app.snapshot().selected_download.unwrap().model_name, "alpha");
+
+        let backend = tui::backend::TestBackend::new(80, 20);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let snapshot = app.snapshot();
+        terminal
+            .draw(|f| {
+                ui(f, &snapshot, &mut 0);
+            })
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+        assert!(screen.contains("Watching alpha"));
+        assert!(screen.contains(" DOWNLOADING "));
+        assert!(!screen.contains("beta"), "the rest of the list is hidden");
+
+        app.set_downloads(vec![download("beta", DownloadStatus::Offline)]);
+        assert!(app.snapshot().selected_download.is_none());
+    }
+
+    #[test]
+    fn flags_stale_data_while_connected() {
+        let mut app = test_app();
+        app.stale_after = Duration::from_secs(9);
+        assert_eq!(stale_age(&app.snapshot()), None, "nothing fetched yet");
+
+        app.last_refresh = Some(Instant::now());
+        assert_eq!(stale_age(&app.snapshot()), None);
+
+        app.last_refresh = Instant::now().checked_sub(Duration::from_secs(10));
+        let age = stale_age(&app.snapshot()).unwrap();
+        assert!(age >= Duration::from_secs(10));
+
+        // A disconnect is reported by the connection banner instead.
+        app.connection_ok = false;
+        assert_eq!(stale_age(&app.snapshot()), None);
+    }
+
+    // A rough benchmark of drawing a very large list; run with
+    // `cargo test --release -- --ignored --nocapture renders_large_lists`.
+    #[test]
+    #[ignore]
+    fn renders_large_lists_quickly() {
+        let mut app = test_app();
+        app.set_downloads(
+            (0..5_000)
+                .map(|i| download(&format!("model{:04}", i), DownloadStatus::Downloading))
+                .collect(),
+        );
+        app.select_index(2_500);
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(120, 50)).unwrap();
+        let mut offset = 0;
+
+        let frames = 200;
+        let started = Instant::now();
+        for _ in 0..frames {
+            let snapshot = app.snapshot();
+            terminal.draw(|f| {
+                ui(f, &snapshot, &mut offset);
+            })
+            .unwrap();
+        }
+        println!("{:?} per frame with 5,000 downloads", started.elapsed() / frames);
+    }
+
+    #[test]
+    fn unreadable_ca_certificates_are_reported() {
+        let missing = cli::Args {
+            ca_cert: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..cli::Args::default()
+        };
+        let error = App::new(&missing).err().unwrap().to_string();
+        assert!(error.starts_with("Could not read CA certificate"), "{}", error);
+    }
+
+    #[test]
+    fn panics_restore_the_terminal_first() {
+        use std::sync::atomic::AtomicBool;
+
+        static RESTORED: AtomicBool = AtomicBool::new(false);
+        chain_panic_hook(|| RESTORED.store(true, Ordering::SeqCst));
+        let result = std::panic::catch_unwind(|| panic!("deliberate panic"));
+        // Put the default hook back so other tests' panics print normally.
+        let _ = std::panic::take_hook();
+
+        assert!(result.is_err());
+        assert!(RESTORED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn computes_fleet_stats() {
+        let now = Utc::now();
+        let started = |name: &str, status, mins: i64, retries: u32| Download {
+            start_time: now - chrono::Duration::minutes(mins),
+            retry_count: retries,
+            ..download(name, status)
+        };
+        let downloads = vec![
+            started("alpha", DownloadStatus::Downloading, 10, 1),
+            started("beta", DownloadStatus::Offline, 30, 4),
+            started("gamma", DownloadStatus::Initializing, 20, 0),
+            started("delta", DownloadStatus::Completed, 60, 0),
+        ];
+
+        let stats = fleet_stats(&downloads, now);
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.mean_uptime, Some(chrono::Duration::minutes(30)));
+        assert_eq!(stats.median_uptime, Some(chrono::Duration::minutes(25)));
+        assert_eq!(stats.total_retries, 5);
+        assert_eq!(
+            stats.oldest_running,
+            Some(("gamma".to_string(), chrono::Duration::minutes(20)))
+        );
+        assert!(stats.category_counts.contains(&(StatusCategory::Active, 2)));
+
+        let empty = fleet_stats(&[], now);
+        assert_eq!((empty.mean_uptime, empty.oldest_running), (None, None));
+    }
+
+    #[test]
+    fn notifies_each_transition_once() {
+        let mut app = test_app();
+        app.notify_on = vec![NotifyOn::Error, NotifyOn::Completed];
+        let before = vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+        ];
+        let failed = vec![
+            download("alpha", DownloadStatus::Error { message: Some("disk".to_string()) }),
+            download("beta", DownloadStatus::Offline),
+        ];
+        let transitions = status_transitions(&before, &failed);
+        assert_eq!(transitions.len(), 2);
+        app.set_downloads(failed.clone());
+        let sent = app.notifications(&transitions);
+        assert_eq!(sent.len(), 1, "offline isn't in notify_on");
+        assert_eq!(sent[0].0, "alpha is now Error: disk");
+        assert_eq!(sent[0].1, "Previously Downloading");
+
+        // A new error message on the same model is still the same kind of transition.
+        let again = vec![download("alpha", DownloadStatus::Error { message: None })];
+        assert!(app.notifications(&status_transitions(&failed, &again)).is_empty());
+
+        // Recovering and failing again notifies afresh.
+        let recovered = vec![download("alpha", DownloadStatus::Downloading)];
+        assert!(app.notifications(&status_transitions(&again, &recovered)).is_empty());
+        assert_eq!(app.notifications(&status_transitions(&recovered, &again)).len(), 1);
+    }
+
+    #[test]
+    fn notes_outlive_the_model_and_are_saved() {
+        let path = std::env::temp_dir()
+            .join(format!("downloader-ctl-app-notes-{}", std::process::id()))
+            .join("notes.json");
+        let mut app = test_app();
+        app.notes_path = Some(path.clone());
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+
+        app.edit_note();
+        app.set_note("alpha", "  known flaky ");
+        app.set_downloads(Vec::new());
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        assert_eq!(app.notes["alpha"], "known flaky");
+        assert_eq!(notes::load(&path).unwrap(), app.notes);
+
+        app.edit_note();
+        assert_eq!(app.input_buffer, "known flaky");
+        app.set_note("alpha", "");
+        assert!(notes::load(&path).unwrap().is_empty());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn the_view_is_restored_at_the_next_launch() {
+        let dir = std::env::temp_dir().join(format!("downloader-ctl-state-{}", std::process::id()));
+        let path = dir.join("state.json");
+        let args = cli::Args {
+            state_file: Some(path.clone()),
+            ..cli::Args::default()
+        };
+        let mut app = App::new(&args).unwrap();
+        assert_eq!(app.sort_key, None);
+        app.sort_key = Some(SortKey::RetryCount);
+        app.sort_descending = true;
+        app.status_filter = Some(StatusCategory::Paused);
+        app.set_filter_query("alpha".to_string());
+        app.save_ui_state().unwrap();
+
+        let app = App::new(&args).unwrap();
+        assert_eq!(app.sort_key, Some(SortKey::RetryCount));
+        assert!(app.sort_descending);
+        assert_eq!(app.status_filter, Some(StatusCategory::Paused));
+        assert_eq!(app.filter_query, "alpha");
+
+        // A corrupt file costs the saved view, not the launch.
+        std::fs::write(&path, "{not json").unwrap();
+        let app = App::new(&args).unwrap();
+        assert_eq!(app.sort_key, None);
+        assert!(app.log.back().unwrap().message.starts_with("Invalid state file"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_notes_are_logged_and_left_alone() {
+        let dir = std::env::temp_dir()
+            .join(format!("downloader-ctl-bad-notes-{}", std::process::id()));
+        let path = dir.join("notes.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "{not json").unwrap();
+        let mut app = App::new(&cli::Args {
+            notes_file: Some(path.clone()),
+            ..cli::Args::default()
+        })
+        .unwrap();
+        assert!(app.log.back().unwrap().message.starts_with("Invalid notes file"));
+
+        app.set_note("alpha", "known flaky");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{not json");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_completion_times_are_logged_and_replaced() {
+        let dir = std::env::temp_dir()
+            .join(format!("downloader-ctl-completions-{}", std::process::id()));
+        let path = dir.join("completions.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "{not json").unwrap();
+        let app = App::new(&cli::Args {
+            completions_file: Some(path),
+            ..cli::Args::default()
+        })
+        .unwrap();
+        assert!(app.log.back().unwrap().message.starts_with("Invalid completions file"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn input_edits_at_the_cursor() {
+        let mut app = test_app();
+        app.set_input("héllo".to_string());
+        assert_eq!(app.input_cursor, "héllo".len());
+
+        for key in [KeyCode::Home, KeyCode::Right, KeyCode::Right, KeyCode::Backspace] {
+            assert!(app.edit_input(key));
+        }
+        assert_eq!((app.input_buffer.as_str(), app.input_cursor), ("hllo", 1));
+        app.edit_input(KeyCode::Char('é'));
+        app.edit_input(KeyCode::Delete);
+        assert_eq!(app.input_buffer, "hélo");
+        app.edit_input(KeyCode::End);
+        app.edit_input(KeyCode::Right);
+        app.edit_input(KeyCode::Char('!'));
+        assert_eq!(app.input_buffer, "hélo!");
+        assert!(!app.edit_input(KeyCode::Enter));
+
+        assert_eq!(app.take_input(), "hélo!");
+        assert_eq!(app.input_cursor, 0);
+
+        // Long text scrolls so the cursor stays in view.
+        assert_eq!(input_window("abcdef", 2, 4), ("abcd".to_string(), 2));
+        assert_eq!(input_window("abcdef", 6, 4), ("def".to_string(), 3));
+    }
+
+    #[tokio::test]
+    async fn undo_expires_after_the_window() {
+        let mut app = test_app();
+        app.last_action = Some((
+            Action::Stopped(vec!["alpha".to_string()]),
+            Instant::now() - UNDO_WINDOW,
+        ));
+
+        app.undo().await;
+        assert_eq!(app.banner.as_ref().unwrap().message, "Nothing to undo");
+    }
+
+    #[tokio::test]
+    async fn undoing_a_delete_of_a_tracked_model_re_adds_nothing() {
+        let mut app = test_app();
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        app.source_urls.insert("alpha".to_string(), "http://host/alpha".to_string());
+        app.last_action = Some((
+            Action::Deleted {
+                model: "alpha".to_string(),
+                url: Some("http://host/alpha".to_string()),
+            },
+            Instant::now(),
+        ));
+
+        app.undo().await;
+        assert_eq!(
+            app.banner.as_ref().unwrap().message,
+            "Nothing to undo: alpha is already tracked"
+        );
+    }
+
+    #[tokio::test]
+    async fn actions_on_active_downloads_ask_for_confirmation() {
+        let (url, requests) = mock_server(vec![(200, ""), (500, "")]).await;
+        let mut app = app_for(url);
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Completed),
+        ]);
+
+        app.select_index(0);
+        app.request_action(ControlAction::Stop).await;
+        assert_eq!(
+            app.input_mode,
+            InputMode::Confirm {
+                action: ControlAction::Stop,
+                model: "alpha".to_string()
+            }
+        );
+        assert!(requests.lock().await.is_empty());
+
+        app.input_mode = InputMode::Normal;
+        app.select_index(1);
+        app.request_action(ControlAction::Restart).await;
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(
+            requests.lock().await[0],
+            "POST /downloads/beta/restart HTTP/1.1"
+        );
+
+        // Forcing skips the question, and a failure still lands in the banner.
+        app.action_retry.retries = 0;
+        app.select_index(0);
+        app.apply_action(ControlAction::Stop).await;
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(requests.lock().await[1], "POST /downloads/alpha/stop HTTP/1.1");
+        let banner = &app.banner.as_ref().unwrap().message;
+        assert!(banner.starts_with("Error stopping download"), "{}", banner);
+    }
+
+    #[test]
+    fn status_colors_follow_severity() {
+        let fg = |status: DownloadStatus| Theme::default().status(&status).fg;
+        assert_eq!(fg(DownloadStatus::Completed), Some(Color::Green));
+        assert_eq!(fg(DownloadStatus::Downloading), Some(Color::Green));
+        assert_eq!(fg(DownloadStatus::Retrying { message: None }), Some(Color::Yellow));
+        assert_eq!(fg(DownloadStatus::Paused), Some(Color::Yellow));
+        assert_eq!(fg(DownloadStatus::PausedForTicketShow), Some(Color::Magenta));
+        assert_eq!(
+            fg(DownloadStatus::Error {
+                message: Some("disk full".to_string())
+            }),
+            Some(Color::Red)
+        );
+        assert_eq!(fg(DownloadStatus::Offline), Some(Color::Red));
+        assert_eq!(fg(DownloadStatus::Initializing), Some(Color::Gray));
+    }
+
+    #[tokio::test]
+    async fn queued_downloads_move_through_the_queue() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, "[]")]).await;
+        let mut app = app_for(url);
+        let mut first = download("alpha", DownloadStatus::Queued);
+        first.queue_position = Some(1);
+        let mut second = download("beta", DownloadStatus::Queued);
+        second.queue_position = Some(0);
+        app.set_downloads(vec![
+            first,
+            second,
+            download("gamma", DownloadStatus::Downloading),
+        ]);
+        assert_eq!(app.queue(), ["beta", "alpha"]);
+        assert!(category_counts(&app.downloads).contains(&(StatusCategory::Queued, 2)));
+
+        app.select_index(0);
+        app.move_in_queue(true).await;
+        assert_eq!(app.banner.as_ref().unwrap().message, "alpha is already last in the queue");
+        app.select_index(2);
+        app.move_in_queue(false).await;
+        assert_eq!(app.banner.as_ref().unwrap().message, "gamma isn't queued");
+        assert!(requests.lock().await.is_empty());
+
+        app.select_index(0);
+        app.move_in_queue(false).await;
+        let request = &requests.lock().await[0];
+        assert_eq!(request.line, "PUT /downloads/alpha/priority HTTP/1.1");
+        assert_eq!(request.body, r#"{"position":0}"#);
+        assert_eq!(app.banner.as_ref().unwrap().message, "Moved alpha to #1 in the queue");
+    }
+
+    #[tokio::test]
+    async fn adding_a_tracked_model_reports_it() {
+        let (url, requests) = mock_server(vec![
+            (200, r#"{"modelName": "alpha"}"#),
+            (200, "[]"),
+        ])
+        .await;
+        let mut app = app_for(url);
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+
+        let outcome = app
+            .add_download("http://host/alpha".to_string(), &AddOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(outcome, AddOutcome::AlreadyTracking("alpha".to_string()));
+        assert_eq!(requests.lock().await[0], "POST /downloads HTTP/1.1");
+
+        // A URL known to belong to a listed model isn't sent again.
+        app.set_downloads(vec![download("beta", DownloadStatus::Downloading)]);
+        app.source_urls.insert("beta".to_string(), "http://host/beta".to_string());
+        let outcome = app
+            .add_download("http://host/beta".to_string(), &AddOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(outcome, AddOutcome::AlreadyTracking("beta".to_string()));
+        assert_eq!(requests.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn streamed_events_update_the_list() {
+        let (url, requests) = mock_server(vec![(
+            200,
+            "data: [{\"modelName\": \"alpha\", \"status\": \"Downloading\", \
+             \"startTime\": \"2024-01-01T00:00:00Z\", \
+             \"lastStatusChange\": \"2024-01-01T00:00:00Z\", \"retryCount\": 0}]\n\n",
+        )])
+        .await;
+        let app = Arc::new(Mutex::new(app_for(url)));
+
+        let end = sse::stream_updates(&app).await;
+        assert!(matches!(end, sse::StreamEnd::Disconnected));
+        assert_eq!(requests.lock().await[0], "GET /events HTTP/1.1");
+        assert_eq!(app.lock().await.downloads[0].model_name, "alpha");
+    }
+
+    #[tokio::test]
+    async fn tails_a_downloads_server_log() {
+        let log = "starting\nchunk 1 of 9\nretry";
+        let (url, requests) = mock_server(vec![(200, log), (404, "")]).await;
+        let app = Arc::new(Mutex::new(app_for(url)));
+
+        let open = |app: &mut App, model: &str| {
+            app.server_log = Some(ServerLog::new(model.to_string()));
+            app.input_mode = InputMode::ServerLog;
+        };
+        open(&mut *app.lock().await, "alpha");
+        sse::tail_logs(app.clone(), "alpha".to_string()).await;
+        {
+            let app = app.lock().await;
+            let log = app.snapshot().server_log.unwrap();
+            assert_eq!(log.lines, ["starting", "chunk 1 of 9", "retry"]);
+            assert_eq!(log.ended.as_deref(), Some("Log stream ended"));
+        }
+
+        open(&mut *app.lock().await, "beta");
+        sse::tail_logs(app.clone(), "beta".to_string()).await;
+        let mut app = app.lock().await;
+        let log = app.server_log.as_ref().unwrap();
+        assert_eq!(log.ended.as_deref(), Some("Logs not supported by server"));
+        assert_eq!(requests.lock().await[1], "GET /downloads/beta/logs HTTP/1.1");
+
+        app.close_server_log();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.snapshot().server_log.is_none());
+    }
+
+    #[tokio::test]
+    async fn adding_reports_the_created_model() {
+        let (url, _) = mock_server(vec![
+            (201, r#"{"modelName": "gamma"}"#),
+            (
+                200,
+                r#"[{"modelName": "gamma", "status": "Initializing",
+                     "startTime": "2024-01-01T00:00:00Z",
+                     "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}]"#,
+            ),
+            (201, ""),
+            (200, "[]"),
+        ])
+        .await;
+        let mut app = app_for(url);
+
+        let outcome = app
+            .add_download("http://host/g".to_string(), &AddOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(outcome, AddOutcome::Added(Some("gamma".to_string())));
+        assert_eq!(app.source_urls["gamma"], "http://host/g");
+
+        // Without a name in the response or a single new row, the model is unknown.
+        let outcome = app
+            .add_download("http://host/h".to_string(), &AddOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(outcome, AddOutcome::Added(None));
+    }
+
+    #[tokio::test]
+    async fn adding_succeeds_when_the_refresh_after_it_fails() {
+        let (url, _) = mock_server(vec![(201, r#"{"modelName": "gamma"}"#), (500, "")]).await;
+        let mut app = app_for(url);
+
+        let outcome = app
+            .add_download("http://host/g".to_string(), &AddOptions::default())
+            .await;
+        assert_eq!(outcome.unwrap(), AddOutcome::Added(Some("gamma".to_string())));
+        assert_eq!(app.source_urls["gamma"], "http://host/g");
+        assert!(!app.connection_ok);
+    }
+
+    #[tokio::test]
+    async fn requests_have_the_shape_the_server_expects() {
+        let list = r#"[{"modelName": "alpha", "status": "Downloading",
+                        "startTime": "2024-01-01T00:00:00Z",
+                        "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}]"#;
+        let (url, requests) = mock_server(vec![(201, ""), (200, list), (200, "")]).await;
+        let mut app = App::new(&cli::Args {
+            downloader_url: url,
+            auth_token: Some("secret".to_string()),
+            headers: vec![("X-Team".to_string(), "ops".to_string())],
+            ..cli::Args::default()
+        })
+        .unwrap();
+
+        app.add_download("http://host/alpha".to_string(), &AddOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(visible_names(&app), ["alpha"]);
+        app.control_download("alpha", "stop").await.unwrap();
+
+        let requests = requests.lock().await;
+        assert_eq!(
+            *requests,
+            vec![
+                "POST /downloads HTTP/1.1",
+                "GET /downloads HTTP/1.1",
+                "POST /downloads/alpha/stop HTTP/1.1"
+            ]
+        );
+        let add = &requests[0];
+        assert_eq!(add.headers["content-type"], "application/json");
+        let body: serde_json::Value = serde_json::from_str(&add.body).unwrap();
+        assert_eq!(body, serde_json::json!({"url": "http://host/alpha"}));
+        for request in requests.iter() {
+            assert_eq!(request.headers["authorization"], "Bearer secret");
+            assert_eq!(request.headers["x-team"], "ops");
+        }
+        assert!(requests[2].body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_add_form_sends_its_optional_fields() {
+        let list = r#"[{"modelName": "alpha", "status": "queued",
+                        "startTime": "2024-01-01T00:00:00Z",
+                        "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}]"#;
+        let (url, requests) =
+            mock_server(vec![(201, r#"{"modelName": "alpha"}"#), (200, list)]).await;
+        let mut app = app_for(url);
+
+        app.open_add_form();
+        app.set_input("http://host/alpha".to_string());
+        app.focus_add_field(AddField::Url.next(false));
+        app.set_input("soon".to_string());
+        app.focus_add_field(AddField::Quality);
+        app.submit_add_form().await;
+        assert_eq!(
+            app.input_mode,
+            InputMode::AddForm {
+                focus: AddField::Priority
+            }
+        );
+        assert_eq!(app.input_buffer, "soon");
+        assert_eq!(
+            app.input_error.as_deref(),
+            Some("Invalid priority 'soon': expected a whole number")
+        );
+        assert!(requests.lock().await.is_empty());
+
+        app.set_input("2".to_string());
+        app.focus_add_field(AddField::Priority.next(false));
+        app.set_input("/models/alpha".to_string());
+        app.submit_add_form().await;
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.banner.as_ref().unwrap().message, "Added alpha");
+        assert_eq!(app.selected_model.as_deref(), Some("alpha"));
+
+        let requests = requests.lock().await;
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "url": "http://host/alpha",
+                "priority": 2,
+                "outputPath": "/models/alpha",
+            })
+        );
+        assert_eq!(AddField::Url.next(true), AddField::Quality);
+    }
+
+    #[test]
+    fn source_url_comes_from_the_server_or_this_session() {
+        let mut app = test_app();
+        app.apply_event(
+            r#"[{"modelName": "alpha", "status": "Downloading", "startTime": "2024-01-01T00:00:00Z",
+                "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0,
+                "sourceUrl": "https://example.com/alpha"},
+               {"modelName": "beta", "status": "Downloading", "startTime": "2024-01-01T00:00:00Z",
+                "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}]"#,
+        )
+        .unwrap();
+        app.source_urls.insert("beta".to_string(), "https://example.com/beta".to_string());
+
+        assert_eq!(app.source_url("alpha", 0).as_deref(), Some("https://example.com/alpha"));
+        assert_eq!(app.source_url("beta", 0).as_deref(), Some("https://example.com/beta"));
+        assert_eq!(app.source_url("gamma", 0), None);
+    }
+
+    #[tokio::test]
+    async fn refresh_loop_stops_when_cancelled() {
+        let app = Arc::new(Mutex::new(test_app()));
+        let shutdown = CancellationToken::new();
+        let refresh = tokio::spawn(refresh_loop(app, false, shutdown.clone()));
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(1), refresh)
+            .await
+            .expect("refresh loop kept running after cancellation")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn background_requests_leave_the_app_unlocked() {
+        // Accepts the requests but never answers them.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut app = app_for(format!("http://{}", listener.local_addr().unwrap()));
+        app.auto_restart = Some(AutoRestart {
+            grace: Duration::ZERO,
+            max_attempts: 1,
+        });
+        app.set_downloads(vec![download("alpha", DownloadStatus::Error { message: None })]);
+        let app = Arc::new(Mutex::new(app));
+        let shutdown = CancellationToken::new();
+
+        let refresh = tokio::spawn(refresh_loop(app.clone(), false, shutdown.clone()));
+        let _fetch = listener.accept().await.unwrap();
+        assert!(app.try_lock().is_ok(), "locked while waiting for the list");
+        shutdown.cancel();
+        refresh.await.unwrap();
+
+        let restart = tokio::spawn({
+            let app = app.clone();
+            async move { run_auto_restart(&app).await }
+        });
+        let _restart = listener.accept().await.unwrap();
+        restart.abort();
+        let app = app.try_lock().expect("locked while waiting for the restart");
+        assert_eq!(app.downloads[0].status, DownloadStatus::Initializing);
+    }
+
+    #[tokio::test]
+    async fn watchdogs_run_without_polling() {
+        let (url, requests) = mock_server(vec![(200, "")]).await;
+        let mut app = app_for(url);
+        app.refresh_interval = Duration::from_millis(10);
+        app.health_checked = Some(Instant::now());
+        app.auto_restart = Some(AutoRestart {
+            grace: Duration::ZERO,
+            max_attempts: 1,
+        });
+        // As a pushed event would install it, with no fetch from the refresh loop.
+        app.receive_downloads(vec![download("alpha", DownloadStatus::Error { message: None })]);
+        let app = Arc::new(Mutex::new(app));
+        let shutdown = CancellationToken::new();
+        let housekeeping = tokio::spawn(housekeeping_loop(app.clone(), shutdown.clone()));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        shutdown.cancel();
+        housekeeping.await.unwrap();
+        assert_eq!(
+            *requests.lock().await,
+            vec!["POST /downloads/alpha/restart HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn manual_refreshes_run_one_at_a_time() {
+        let (url, requests) = mock_server(vec![
+            (
+                200,
+                r#"[{"modelName": "alpha", "status": "Downloading",
+                     "startTime": "2024-01-01T00:00:00Z",
+                     "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}]"#,
+            ),
+            (200, "[]"),
+        ])
+        .await;
+        let app = Arc::new(Mutex::new(app_for(url)));
+        app.lock().await.toggle_refresh_pause();
+
+        // Each fetch installs its whole list under the lock, so the later one wins intact.
+        let first = spawn_refresh(&app);
+        let second = spawn_refresh(&app);
+        first.await.unwrap();
+        second.await.unwrap();
+        assert_eq!(requests.lock().await.len(), 2);
+        let app = app.lock().await;
+        assert!(app.downloads.is_empty());
+        assert!(app.last_refresh.is_some(), "fetched while paused");
+        assert_eq!(app.in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn failures_back_off_the_refresh_interval() {
+        let mut app = test_app();
+        app.refresh_interval = Duration::from_secs(3);
+        let mut delays = Vec::new();
+        for _ in 0..6 {
+            app.fetch_downloads().await.unwrap_err();
+            delays.push(app.poll_delay().as_secs());
+        }
+        assert_eq!(delays, [3, 6, 12, 24, 30, 30]);
+        assert_eq!(app.failure_streak, 6);
+
+        let backend = tui::backend::TestBackend::new(200, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let snapshot = app.snapshot();
+        terminal
+            .draw(|f| {
+                ui(f, &snapshot, &mut 0);
+            })
+            .unwrap();
+        let banner: String = (0..200)
+            .map(|x| terminal.backend().buffer().get(x, 1).symbol.as_str())
+            .collect();
+        assert!(banner.contains("6 failures in a row, retrying every 30s"), "{}", banner);
+
+        app.receive_downloads(Vec::new());
+        assert_eq!(app.poll_delay(), Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn paused_refresh_skips_fetches() {
+        let (url, requests) = mock_server(vec![(200, "[]")]).await;
+        let app = Arc::new(Mutex::new(app_for(url)));
+        app.lock().await.toggle_refresh_pause();
+        let shutdown = CancellationToken::new();
+        let refresh = tokio::spawn(refresh_loop(app.clone(), false, shutdown.clone()));
+
+        // The first tick is immediate, so an unpaused loop would have fetched by now.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        shutdown.cancel();
+        refresh.await.unwrap();
+        assert!(requests.lock().await.is_empty());
+
+        let mut app = app.lock().await;
+        app.last_refresh = Some(Instant::now() - app.stale_after * 2);
+        assert!(app.snapshot().paused_refresh);
+        assert_eq!(stale_age(&app.snapshot()), None, "paused lists are old on purpose");
+        app.toggle_refresh_pause();
+        assert!(stale_age(&app.snapshot()).is_some());
+    }
+
+    #[tokio::test]
+    async fn fleet_actions_target_downloads_by_state() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, ""), (200, "[]")]).await;
+        let mut app = app_for(url);
+        app.confirm_actions = false;
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Paused),
+            download("gamma", DownloadStatus::Initializing),
+            download("delta", DownloadStatus::PausedForTicketShow),
+        ]);
+        assert_eq!(app.fleet_targets(FleetAction::ResumeAll), vec!["beta"]);
+
+        app.request_fleet_action(FleetAction::PauseAll).await;
+        let requests = requests.lock().await;
+        assert!(requests.iter().any(|r| r.line == "POST /downloads/alpha/pause HTTP/1.1"));
+        assert!(requests.iter().any(|r| r.line == "POST /downloads/gamma/pause HTTP/1.1"));
+        assert_eq!(app.banner.as_ref().unwrap().message, "Pause all: 2 succeeded");
+    }
+
+    #[tokio::test]
+    async fn clears_every_completed_download() {
+        let list = r#"[{"modelName": "beta", "status": "Downloading",
+                        "startTime": "2024-01-01T00:00:00Z",
+                        "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}]"#;
+        let (url, requests) = mock_server(vec![(200, ""), (200, ""), (200, list)]).await;
+        let mut app = app_for(url);
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Completed),
+            download("beta", DownloadStatus::Downloading),
+            download("gamma", DownloadStatus::Completed),
+        ]);
+        app.select_model("alpha".to_string());
+
+        app.request_fleet_action(FleetAction::ClearCompleted).await;
+        assert_eq!(
+            app.input_mode,
+            InputMode::ConfirmFleet {
+                action: FleetAction::ClearCompleted,
+                count: 2
+            }
+        );
+        app.apply_fleet_action(FleetAction::ClearCompleted).await;
+        let requests = requests.lock().await;
+        let deletes = requests.iter().filter(|r| r.line.starts_with("DELETE ")).count();
+        assert_eq!(deletes, 2);
+        assert_eq!(app.banner.as_ref().unwrap().message, "Clear: 2 succeeded");
+        assert_eq!(visible_names(&app), ["beta"]);
+        assert_eq!(app.selected_download().unwrap().model_name, "beta");
+    }
+
+    #[tokio::test]
+    async fn repeated_actions_on_a_model_are_rate_limited() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, ""), (200, "")]).await;
+        let mut app = app_for(url);
+        app.confirm_actions = false;
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Error { message: None }),
+            download("beta", DownloadStatus::Error { message: None }),
+        ]);
+        app.select_model("alpha".to_string());
+
+        app.apply_action(ControlAction::Restart).await;
+        app.apply_action(ControlAction::Restart).await;
+        assert_eq!(requests.lock().await.len(), 1);
+        assert_eq!(app.banner.as_ref().unwrap().message, "Action rate-limited for alpha");
+
+        // Other models aren't held up, and the limit lifts once the cooldown is over.
+        app.select_model("beta".to_string());
+        app.apply_action(ControlAction::Restart).await;
+        app.action_cooldown = Duration::ZERO;
+        app.select_model("alpha".to_string());
+        app.apply_action(ControlAction::Restart).await;
+        let requests = requests.lock().await;
+        let lines: Vec<&str> = requests.iter().map(|r| r.line.as_str()).collect();
+        assert_eq!(
+            lines,
+            [
+                "POST /downloads/alpha/restart HTTP/1.1",
+                "POST /downloads/beta/restart HTTP/1.1",
+                "POST /downloads/alpha/restart HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn batches_cap_requests_in_flight_and_report_progress() {
+        let mut app = test_app();
+        app.max_concurrency = 3;
+        let (running, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let seen = std::sync::Mutex::new(Vec::new());
+        let requests = (0..10)
+            .map(|i| {
+                let (app, running, peak, seen) = (&app, &running, &peak, &seen);
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    seen.lock().unwrap().push(app.batch.lock().unwrap().clone().unwrap());
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    i
+                }
+            })
+            .collect();
+        let results = app.run_batch("restarting", requests).await;
+        assert_eq!(results, (0..10).collect::<Vec<_>>());
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        let seen = seen.into_inner().unwrap();
+        assert_eq!(seen[0], BatchProgress { verb: "restarting", done: 0, total: 10 });
+        assert!(seen.iter().all(|progress| progress.total == 10 && progress.done < 10));
+        assert!(app.batch.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn fleet_actions_show_their_progress_while_running() {
+        // Accepts the requests but never answers them.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut app = app_for(format!("http://{}", listener.local_addr().unwrap()));
+        app.max_concurrency = 2;
+        app.set_downloads(
+            ["alpha", "beta", "gamma", "delta"]
+                .into_iter()
+                .map(|name| download(name, DownloadStatus::Error { message: None }))
+                .collect(),
+        );
+        let (in_flight, batch) = (app.in_flight.clone(), app.batch.clone());
+        let mut snapshot = app.snapshot();
+        let app = Arc::new(Mutex::new(app));
+
+        let request = UserRequest::ConfirmedFleetAction(FleetAction::RestartErrored);
+        let task = spawn_request(&app, request);
+        let _connections = (
+            listener.accept().await.unwrap(),
+            listener.accept().await.unwrap(),
+        );
+        update_snapshot(&app, &in_flight, &batch, &mut snapshot);
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(60, 12)).unwrap();
+        terminal.draw(|f| {
+            ui(f, &snapshot, &mut 0);
+        })
+        .unwrap();
+        let buffer = terminal.backend().buffer();
+        let banner: String = (0..60).map(|x| buffer.get(x, 1).symbol.as_str()).collect();
+        assert_eq!(banner.trim_end(), "Restarting 0/4…");
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn restarts_every_errored_download() {
+        let (url, requests) = mock_server(vec![(200, ""), (500, "")]).await;
+        let mut app = app_for(url);
+        app.action_retry.retries = 0;
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Error { message: None }),
+            download("gamma", DownloadStatus::Offline),
+        ]);
+
+        app.request_fleet_action(FleetAction::RestartErrored).await;
+        assert_eq!(
+            app.input_mode,
+            InputMode::ConfirmFleet {
+                action: FleetAction::RestartErrored,
+                count: 2
+            }
+        );
+        app.apply_fleet_action(FleetAction::RestartErrored).await;
+        let requests = requests.lock().await;
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.line.ends_with("/restart HTTP/1.1")));
+        let banner = &app.banner.as_ref().unwrap().message;
+        assert!(banner.starts_with("Restart all: 1 succeeded, 1 failed"), "{}", banner);
+    }
+
+    #[tokio::test]
+    async fn duplicate_model_names_keep_their_own_rows() {
+        let list = r#"[
+            {"modelName": "alpha", "status": "Downloading", "startTime": "2024-01-01T00:00:00Z",
+             "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0},
+            {"modelName": "beta", "status": "Paused", "startTime": "2024-01-01T00:00:00Z",
+             "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0},
+            {"modelName": "alpha", "status": "Offline", "startTime": "2024-01-01T00:00:00Z",
+             "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 3}]"#;
+        let (url, _) = mock_server(vec![(200, list), (200, list)]).await;
+        let mut app = app_for(url);
+        app.fetch_downloads().await.unwrap();
+        let names: Vec<String> = app.downloads.iter().map(Download::display_name).collect();
+        assert_eq!(names, ["alpha", "beta", "alpha #2"]);
+        assert!(app.log.back().unwrap().message.contains("listed alpha more than once"));
+
+        // The second alpha stays selected, and in place, across a refresh.
+        app.select_index(2);
+        let log_len = app.log.len();
+        app.fetch_downloads().await.unwrap();
+        let download = app.selected_download().unwrap();
+        assert_eq!((download.duplicate, download.retry_count), (1, 3));
+        assert_eq!(app.list_state.selected(), Some(2));
+        assert_eq!(visible_names(&app), ["alpha", "beta", "alpha"]);
+        assert_eq!(app.log.len(), log_len);
+    }
+
+    #[test]
+    fn duplicates_keep_their_own_detail_history_and_marks() {
+        let mut app = test_app();
+        let first = download("alpha", DownloadStatus::Downloading);
+        let mut second = download("alpha", DownloadStatus::Offline);
+        second.start_time = "2024-01-01T00:00:00Z".parse().unwrap();
+        app.set_downloads(vec![first.clone(), second.clone()]);
+        app.set_downloads(vec![first, second]);
+        let second_key = ("alpha".to_string(), 1);
+        assert_eq!(app.history[&key("alpha")].len(), 1, "no flapping between the two");
+        assert_eq!(app.history[&second_key].len(), 1);
+
+        app.select_index(1);
+        let detail = app.snapshot().selected_download.unwrap();
+        assert_eq!((detail.duplicate, detail.status), (1, DownloadStatus::Offline));
+        app.input_mode = InputMode::Watch {
+            model: "alpha".to_string(),
+            duplicate: 1,
+        };
+        app.select_index(0);
+        assert_eq!(app.snapshot().selected_download.unwrap().duplicate, 1);
+        app.input_mode = InputMode::Normal;
+
+        app.toggle_mark();
+        assert_eq!(app.marked, HashSet::from([key("alpha")]));
+        let snapshot = app.snapshot();
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(60, 12)).unwrap();
+        terminal.draw(|f| {
+            ui(f, &snapshot, &mut 0);
+        })
+        .unwrap();
+        let buffer = terminal.backend().buffer();
+        let marked_rows = (0..12)
+            .map(|y| (0..60).map(|x| buffer.get(x, y).symbol.as_str()).collect::<String>())
+            .filter(|line| line.contains("* alpha"))
+            .count();
+        assert_eq!(marked_rows, 1);
+
+        // A pushed update lands on the download that started at the same time.
+        app.apply_event(
+            r#"{"modelName": "alpha", "status": "Paused", "startTime": "2024-01-01T00:00:00Z",
+                "lastStatusChange": "2024-01-01T00:00:00Z", "retryCount": 0}"#,
+        )
+        .unwrap();
+        let statuses: Vec<&DownloadStatus> = app.downloads.iter().map(|d| &d.status).collect();
+        assert_eq!(statuses, [&DownloadStatus::Downloading, &DownloadStatus::Paused]);
+    }
+
+    #[tokio::test]
+    async fn log_records_outcomes_and_connection_changes() {
+        let mut app = test_app();
+        for i in 0..LOG_LIMIT {
+            app.show_message(format!("message {}", i));
+        }
+        app.show_error("Export failed".to_string());
+        assert_eq!(app.log.len(), LOG_LIMIT);
+        assert_eq!(app.log.front().unwrap().message, "message 1");
+        assert!(app.log.back().unwrap().is_error);
+
+        // Only the first of repeated identical failures is logged.
+        app.fetch_downloads().await.unwrap_err();
+        app.fetch_downloads().await.unwrap_err();
+        assert!(app.log.back().unwrap().message.starts_with("Connection lost"));
+        assert_eq!(app.log[LOG_LIMIT - 2].message, "Export failed");
+
+        app.receive_downloads(Vec::new());
+        assert_eq!(app.log.back().unwrap().message, "Connection restored");
+    }
+
+    #[tokio::test]
+    async fn unparseable_lists_are_reported_with_the_body() {
+        let page = "<html>\n  <body>Bad Gateway: the upstream server timed out</body>\n</html>";
+        let (url, _) = mock_server(vec![(200, page)]).await;
+        let mut app = app_for(url);
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+
+        app.fetch_downloads().await.unwrap_err();
+        let error = app.last_error.clone().unwrap();
+        assert!(error.starts_with("Unexpected response from the server (200 OK)"), "{}", error);
+        assert!(error.contains("<html> <body>Bad Gateway"), "{}", error);
+        assert!(error.ends_with("…\""), "the snippet is truncated: {}", error);
+        assert_eq!(app.log.back().unwrap().message, format!("Response body:\n{}", page));
+        assert_eq!(app.downloads.len(), 1, "the last good list is kept");
+
+        let text = log_text(&app.theme, &Vec::from(app.log.clone()));
+        assert_eq!(text.lines.len(), 5);
+        app.log_scroll = 0;
+        for _ in 0..10 {
+            app.scroll_log(true);
+        }
+        assert_eq!(app.log_scroll, 4);
+    }
+
+    #[test]
+    fn ui_survives_tiny_terminals() {
+        let mut app = test_app();
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        app.input_error = Some("Invalid URL".to_string());
+        let modes = [
+            InputMode::Normal,
+            InputMode::AddForm {
+                focus: AddField::Priority,
+            },
+            InputMode::Detail,
+            InputMode::Watch {
+                model: "alpha".to_string(),
+                duplicate: 0,
+            },
+            InputMode::Log,
+            InputMode::Help,
+            InputMode::ConfirmQuit { active: 1 },
+            InputMode::EditingNote {
+                model: "alpha".to_string(),
+            },
+            InputMode::Stats,
+            InputMode::ConfirmDelete {
+                model: "alpha".to_string(),
+            },
+        ];
+
+        for (width, height) in [(1, 1), (2, 3), (4, 6), (10, 2), (3, 20)] {
+            for mode in &modes {
+                app.input_mode = mode.clone();
+                let snapshot = app.snapshot();
+                let backend = tui::backend::TestBackend::new(width, height);
+                let mut terminal = Terminal::new(backend).unwrap();
+                terminal
+                    .draw(|f| {
+                        ui(f, &snapshot, &mut 0);
+                    })
+                    .unwrap();
+            }
+            let backend = tui::backend::TestBackend::new(width, height);
+            let mut terminal = Terminal::new(backend).unwrap();
+            terminal
+                .draw(|f| {
+                    let theme = Theme::default();
+                    render_splash(f, &theme, "http://localhost:0", 2, Some("refused"), 3)
+                })
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn estimates_transfer_rate_between_samples() {
+        let mut app = test_app();
+        let with_bytes = |bytes| Download {
+            bytes_downloaded: Some(bytes),
+            total_bytes: Some(10_000_000),
+            ..download("alpha", DownloadStatus::Downloading)
+        };
+
+        app.set_downloads(vec![with_bytes(1_000_000)]);
+        assert_eq!(app.byte_samples[&key("alpha")].rate, None);
+
+        app.byte_samples.get_mut(&key("alpha")).unwrap().at -= Duration::from_secs(2);
+        app.set_downloads(vec![with_bytes(5_000_000)]);
+        let rate = app.byte_samples[&key("alpha")].rate.unwrap();
+        assert!((rate - 2_000_000.0).abs() < 50_000.0, "rate was {}", rate);
+        assert_eq!(app.snapshot().rates.len(), 1);
+
+        // Going backwards means the download restarted; the old rate no longer applies.
+        app.byte_samples.get_mut(&key("alpha")).unwrap().at -= Duration::from_secs(2);
+        app.set_downloads(vec![with_bytes(0)]);
+        assert_eq!(app.byte_samples[&key("alpha")].rate, None);
+
+        app.set_downloads(vec![download("alpha", DownloadStatus::Downloading)]);
+        assert!(app.byte_samples.is_empty());
+    }
+
+    #[test]
+    fn formats_byte_counts_and_rates() {
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(4_200_000), "4.2 MB");
+        assert_eq!(format_bytes(1_500_000_000), "1.5 GB");
+        assert_eq!(format_rate(4_200_000.0), "4.2 MB/s");
+    }
+
+    #[tokio::test]
+    async fn dry_run_logs_requests_and_simulates_their_effect() {
+        let mut app = test_app();
+        app.dry_run = true;
+        let listed = vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+        ];
+        app.set_downloads(listed.clone());
+
+        app.apply_action(ControlAction::Stop).await;
+        app.remove_download("beta").await.unwrap();
+        let outcome = app
+            .add_download("https://host/models/gamma".to_string(), &AddOptions::default())
+            .await;
+        assert_eq!(outcome.unwrap(), AddOutcome::Added(Some("gamma".to_string())));
+
+        let rehearsed: Vec<&str> = app
+            .log
+            .iter()
+            .filter(|entry| entry.message.starts_with(DRY_RUN_PREFIX))
+            .map(|entry| entry.message.as_str())
+            .collect();
+        assert_eq!(
+            rehearsed,
+            vec![
+                "Dry run: POST http://localhost:0/downloads/alpha/stop",
+                "Dry run: DELETE http://localhost:0/downloads/beta",
+                concat!(
+                    "Dry run: POST http://localhost:0/downloads ",
+                    "{\"url\":\"https://host/models/gamma\"}"
+                ),
+            ]
+        );
+
+        // The simulation survives the server reporting the untouched list.
+        app.receive_downloads(listed);
+        assert_eq!(visible_names(&app), vec!["gamma", "alpha"]);
+        assert_eq!(app.downloads[1].status, DownloadStatus::Offline);
+        assert_eq!(app.downloads[0].status, DownloadStatus::Initializing);
+    }
+
+    #[test]
+    fn copying_a_name_needs_a_selection() {
+        let mut app = test_app();
+        app.copy_model_name();
+        let banner = app.banner.as_ref().unwrap();
+        assert_eq!(banner.message, "Select a download to copy its name");
+        assert!(!banner.is_error);
+    }
+
+    #[test]
+    fn help_overlay_lists_every_binding() {
+        let mut app = test_app();
+        app.input_mode = InputMode::Help;
+        let snapshot = app.snapshot();
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(140, 30)).unwrap();
+        terminal
+            .draw(|f| {
+                ui(f, &snapshot, &mut 0);
+            })
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+
+        for action in KeyAction::ALL {
+            assert!(screen.contains(action.description()), "{:?} missing", action);
+        }
+        assert!(screen.contains("Ctrl+z"));
+    }
+
+    #[test]
+    fn jumps_between_problem_downloads_with_wraparound() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Error { message: None }),
+            download("beta", DownloadStatus::Downloading),
+            download("gamma", DownloadStatus::Error { message: None }),
+            download("delta", DownloadStatus::Completed),
+        ]);
+
+        app.select_index(1);
+        app.select_problem(true);
+        assert_eq!(app.selected_model_name(), Some("gamma"));
+        app.select_problem(true);
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+        app.select_problem(false);
+        assert_eq!(app.selected_model_name(), Some("gamma"));
+
+        app.set_downloads(vec![download("beta", DownloadStatus::Downloading)]);
+        app.select_problem(true);
+        assert_eq!(app.selected_model_name(), Some("beta"));
+        assert_eq!(app.banner.as_ref().unwrap().message, "No errored downloads");
+    }
+
+    #[test]
+    fn groups_models_by_prefix() {
+        let mut app = test_app();
+        app.group_separator = Some("/".to_string());
+        app.set_downloads(vec![
+            download("site_a/one", DownloadStatus::Downloading),
+            download("solo", DownloadStatus::Paused),
+            download("site_a/two", DownloadStatus::Error { message: None }),
+            download("site_b/only", DownloadStatus::Downloading),
+        ]);
+        let rows = |app: &App| -> Vec<String> {
+            app.visible_rows()
+                .iter()
+                .map(|row| match row {
+                    ListRow::Group(group) if group.expanded => format!("-{}", group.name),
+                    ListRow::Group(group) => format!("+{} ({})", group.name, group.len),
+                    ListRow::Download { download, in_group } if *in_group => {
+                        format!("  {}", download.model_name)
+                    }
+                    ListRow::Download { download, .. } => download.model_name.clone(),
+                })
+                .collect()
+        };
+        // A prefix only one download has stays a plain row.
+        assert_eq!(rows(&app), ["+site_a (2)", "solo", "site_b/only"]);
+        assert_eq!(app.selected_group.as_deref(), Some("site_a"));
+        assert!(app.selected_download().is_none(), "actions skip group headers");
+
+        app.expand_group();
+        assert_eq!(rows(&app), ["-site_a", "  site_a/one", "  site_a/two", "solo", "site_b/only"]);
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("site_a/one"));
+
+        // Collapsing from inside the group leaves its header selected.
+        app.collapse_group();
+        assert_eq!(app.selected_group.as_deref(), Some("site_a"));
+        assert_eq!(app.selected_model_name(), None);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        // A collapsed group holding an errored download is where the next error is.
+        app.select_last();
+        app.select_problem(true);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        // A model selected inside a collapsed group resolves to the group's header.
+        app.select_model("site_a/two".to_string());
+        assert_eq!(app.selected_group.as_deref(), Some("site_a"));
+    }
+
+    #[test]
+    fn navigation_wraps_only_when_enabled() {
+        let mut app = test_app();
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::Downloading),
+            download("beta", DownloadStatus::Downloading),
+        ]);
+
+        app.select_previous();
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+        app.select_last();
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("beta"));
+
+        app.wrap_navigation = true;
+        app.select_next();
+        assert_eq!(app.selected_model_name(), Some("alpha"));
+        app.select_previous();
+        assert_eq!(app.selected_model_name(), Some("beta"));
+
+        app.set_downloads(Vec::new());
+        app.select_next();
+        assert_eq!(app.selected_model_name(), None);
+    }
+
+    #[tokio::test]
+    async fn batch_add_skips_comments_and_reports_counts() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, "[]"), (500, "")]).await;
+        let mut app = app_for(url);
+
+        let summary = app
+            .add_batch(
+                "# models\n\nhttps://host/a\n  not a url\nhttps://host/b\n",
+                &AddOptions::default(),
+            )
+            .await;
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.skipped, 0);
+        assert_eq!(summary.failed.len(), 2);
+        assert_eq!(summary.failed[0].0, "not a url");
+        assert_eq!(requests.lock().await.len(), 3);
+    }
+
+    #[test]
+    fn empty_list_explains_why_it_is_empty() {
+        let render = |app: &App| {
+            let snapshot = app.snapshot();
+            let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 10)).unwrap();
+            terminal
+                .draw(|f| {
+                    ui(f, &snapshot, &mut 0);
+                })
+                .unwrap();
+            terminal
+                .backend()
+                .buffer()
+                .content()
+                .iter()
+                .map(|cell| cell.symbol.as_str())
+                .collect::<String>()
+        };
+
+        let mut app = test_app();
+        app.receive_downloads(Vec::new());
+        assert!(render(&app).contains("No downloads — press [a] to add one"));
+
+        app.connection_ok = false;
+        assert!(render(&app).contains("Unable to reach downloader at http://localhost:0"));
+    }
+
+    #[tokio::test]
+    async fn auto_restart_gives_up_after_max_attempts() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, "")]).await;
+        let mut app = app_for(url);
+        app.auto_restart = Some(AutoRestart {
+            grace: Duration::from_secs(60),
+            max_attempts: 1,
+        });
+        let since = |status, secs| {
+            let mut download = download("alpha", status);
+            download.last_status_change = Utc::now() - chrono::Duration::seconds(secs);
+            download
+        };
+        let errored = || since(DownloadStatus::Error { message: None }, 120);
+        let app = Mutex::new(app);
+        // The server keeps reporting alpha as errored between runs.
+        let listed = vec![errored(), download("beta", DownloadStatus::Downloading)];
+        for _ in 0..3 {
+            app.lock().await.set_downloads(listed.clone());
+            run_auto_restart(&app).await;
+        }
+        assert_eq!(
+            *requests.lock().await,
+            vec!["POST /downloads/alpha/restart HTTP/1.1"]
+        );
+        let mut app = app.into_inner();
+        assert!(app.log[1].message.starts_with("Giving up auto-restarting alpha"));
+        assert_eq!(app.log.len(), 2);
+
+        // Staying out of error for the grace period resets the count.
+        app.set_downloads(vec![since(DownloadStatus::Downloading, 120)]);
+        app.due_auto_restarts();
+        assert!(app.auto_restart_attempts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn auto_restart_counts_restarts_that_fail_again() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, ""), (200, "")]).await;
+        let mut app = app_for(url);
+        app.auto_restart = Some(AutoRestart {
+            grace: Duration::from_secs(60),
+            max_attempts: 2,
+        });
+        let mut errored = download("alpha", DownloadStatus::Error { message: None });
+        errored.last_status_change = Utc::now() - chrono::Duration::seconds(120);
+        let app = Mutex::new(app);
+        // Each restart gets as far as Initializing before the download errors again.
+        for _ in 0..4 {
+            app.lock().await.set_downloads(vec![errored.clone()]);
+            run_auto_restart(&app).await;
+            let initializing = download("alpha", DownloadStatus::Initializing);
+            app.lock().await.set_downloads(vec![initializing]);
+            run_auto_restart(&app).await;
+        }
+        assert_eq!(requests.lock().await.len(), 2);
+        let mut app = app.into_inner();
+        assert!(app.log[2].message.starts_with("Giving up auto-restarting alpha"));
+
+        // Completing resets it straight away.
+        app.set_downloads(vec![download("alpha", DownloadStatus::Completed)]);
+        app.due_auto_restarts();
+        assert!(app.auto_restart_attempts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_are_counted_while_in_flight() {
+        let (url, _requests) = mock_server(vec![(200, "[]")]).await;
+        let mut app = app_for(url);
+
+        let guard = InFlight::start(&app.in_flight);
+        assert!(app.snapshot().in_flight);
+        drop(guard);
+
+        app.fetch_downloads().await.unwrap();
+        assert_eq!(app.in_flight.load(Ordering::SeqCst), 0);
+        assert!(!app.snapshot().in_flight);
+    }
+
+    #[tokio::test]
+    async fn key_requests_run_while_frames_are_drawn() {
+        // Accepts the request but never answers it.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let app = app_for(format!("http://{}", listener.local_addr().unwrap()));
+        let (in_flight, batch) = (app.in_flight.clone(), app.batch.clone());
+        let mut snapshot = app.snapshot();
+        let app = Arc::new(Mutex::new(app));
+        app.lock()
+            .await
+            .set_downloads(vec![download("alpha", DownloadStatus::Paused)]);
+
+        let task = spawn_request(&app, UserRequest::ConfirmedAction(ControlAction::Restart));
+        let _connection = listener.accept().await.unwrap();
+        update_snapshot(&app, &in_flight, &batch, &mut snapshot);
+        assert!(snapshot.in_flight);
+        assert!(snapshot.rows.is_empty(), "the snapshot from before the request is kept");
+        assert!(!task.is_finished());
+        task.abort();
+    }
+
+    #[test]
+    fn humanizes_durations_at_unit_boundaries() {
+        let secs = |n| humanize_duration(chrono::Duration::seconds(n));
+        assert_eq!(secs(-5), "0s");
+        assert_eq!(secs(59), "59s");
+        assert_eq!(secs(60), "1m");
+        assert_eq!(secs(60 * 60 - 1), "59m");
+        assert_eq!(secs(60 * 60), "1h 0m");
+        assert_eq!(secs(2 * 3600 + 15 * 60), "2h 15m");
+        assert_eq!(secs(24 * 3600 - 1), "23h 59m");
+        assert_eq!(secs(24 * 3600), "1d 0h");
+        assert_eq!(secs(3 * 86400 + 4 * 3600 + 59), "3d 4h");
+    }
+
+    #[tokio::test]
+    async fn show_pauses_are_resumed_once_after_the_timeout() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, "")]).await;
+        let mut app = app_for(url);
+        app.show_resume_timeout = Some(Duration::ZERO);
+        app.set_downloads(vec![
+            download("alpha", DownloadStatus::PausedForExclusiveShow),
+            download("beta", DownloadStatus::Paused),
+        ]);
+
+        let app = Mutex::new(app);
+
+        run_auto_resume(&app).await;
+        run_auto_resume(&app).await;
+        assert_eq!(
+            *requests.lock().await,
+            vec!["POST /downloads/alpha/restart HTTP/1.1"]
+        );
+
+        // A new show pause after the download resumed gets another attempt.
+        let resumed = download("alpha", DownloadStatus::Downloading);
+        app.lock().await.set_downloads(vec![resumed]);
+        run_auto_resume(&app).await;
+        let paused = download("alpha", DownloadStatus::PausedForTicketShow);
+        app.lock().await.set_downloads(vec![paused]);
+        run_auto_resume(&app).await;
+        assert_eq!(requests.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn completed_downloads_are_pruned_once() {
+        let (url, requests) = mock_server(vec![(200, ""), (200, "[]")]).await;
+        let mut app = app_for(url);
+        app.auto_prune = Some(AutoPrune {
+            after: Duration::from_secs(60),
+            dry_run: true,
+        });
+        let mut old = download("alpha", DownloadStatus::Completed);
+        old.last_status_change = Utc::now() - chrono::Duration::minutes(5);
+        app.set_downloads(vec![
+            old.clone(),
+            download("beta", DownloadStatus::Completed),
+            download("gamma", DownloadStatus::Downloading),
+        ]);
+
+        let app = Mutex::new(app);
+
+        run_auto_prune(&app).await;
+        run_auto_prune(&app).await;
+        assert!(requests.lock().await.is_empty());
+        {
+            let mut app = app.lock().await;
+            let logged: Vec<&str> = app.log.iter().map(|entry| entry.message.as_str()).collect();
+            assert_eq!(logged, ["Would prune completed download alpha"]);
+            app.auto_prune.as_mut().unwrap().dry_run = false;
+            app.pruned.clear();
+        }
+
+        run_auto_prune(&app).await;
+        assert_eq!(
+            *requests.lock().await,
+            vec!["DELETE /downloads/alpha HTTP/1.1", "GET /downloads HTTP/1.1"]
+        );
+        let app = app.into_inner();
+        assert!(app.last_action.is_none(), "pruning isn't undoable");
+        assert_eq!(app.log.back().unwrap().message, "Pruned completed download alpha");
+    }
+
+    #[test]
+    fn compact_mode_fits_more_rows() {
+        let mut app = test_app();
+        app.set_downloads(
+            (0..20)
+                .map(|i| download(&format!("model{:02}", i), DownloadStatus::Downloading))
+                .collect(),
+        );
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(60, 12)).unwrap();
+        let mut draw = |app: &App| {
+            let snapshot = app.snapshot();
+            let mut rows_area = Rect::default();
+            terminal
+                .draw(|f| rows_area = ui(f, &snapshot, &mut 0))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            let line: String = (0..60)
+                .map(|x| buffer.get(x, rows_area.y).symbol.as_str())
+                .collect();
+            (rows_area, line)
+        };
+
+        let (normal, line) = draw(&app);
+        assert!(line.contains("model00 Status: Downloading"), "{}", line);
+        app.display_mode = app.display_mode.toggled();
+        let (compact, line) = draw(&app);
+        assert!(line.starts_with("model00 DL"), "{}", line);
+        assert_eq!((normal.height, compact.height), (6, 9));
+        assert_eq!(clicked_row(compact, 0, 1, 0, compact.y + 2), Some(2));
+    }
+
+    #[test]
+    fn row_templates_replace_the_default_layout() {
+        let mut app = test_app();
+        let mut retrying = download("alpha", DownloadStatus::Retrying { message: None });
+        retrying.retry_count = 4;
+        retrying.start_time = Utc::now() - chrono::Duration::hours(3);
+        app.set_downloads(vec![retrying]);
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(60, 12)).unwrap();
+        let mut draw = |app: &App| {
+            let snapshot = app.snapshot();
+            let mut rows_area = Rect::default();
+            terminal
+                .draw(|f| rows_area = ui(f, &snapshot, &mut 0))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            let line: String = (rows_area.x..rows_area.right())
+                .map(|x| buffer.get(x, rows_area.y).symbol.as_str())
+                .collect();
+            line.trim_end().to_string()
+        };
+
+        let line = draw(&app);
+        assert!(line.contains(", Last Change: 0s, Age: 3h"), "{}", line);
+        app.time_columns = vec![TimeColumn::Age];
+        let line = draw(&app);
+        assert!(line.contains("Status: Retrying, Age: 3h"), "{}", line);
+
+        // Clock times are local and use the configured format.
+        app.absolute_times = true;
+        app.timestamp_format = "%H:%M %d/%m".to_string();
+        let started = local_time(app.downloads[0].start_time, "%H:%M %d/%m");
+        let line = draw(&app);
+        assert!(line.ends_with(&format!(", Started: {} (retries: 4)", started)), "{}", line);
+        app.absolute_times = false;
+
+        let template = RowTemplate::parse("{model} [{status}] retries={retries}{rate}");
+        app.row_template = Some(template.unwrap());
+        assert_eq!(draw(&app), "alpha [Retrying] retries=4");
+    }
+
+    #[test]
+    fn completions_give_downloading_rows_an_eta() {
+        let mut app = test_app();
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 12)).unwrap();
+        let mut draw = |app: &App| {
+            let snapshot = app.snapshot();
+            let mut rows_area = Rect::default();
+            terminal
+                .draw(|f| rows_area = ui(f, &snapshot, &mut 0))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            let line: String = (rows_area.x..rows_area.right())
+                .map(|x| buffer.get(x, rows_area.y).symbol.as_str())
+                .collect();
+            line.trim_end().to_string()
+        };
+
+        let mut alpha = download("alpha", DownloadStatus::Downloading);
+        alpha.start_time = Utc::now() - chrono::Duration::minutes(30);
+        app.receive_downloads(vec![alpha.clone()]);
+        assert!(draw(&app).ends_with(" ETA unknown"), "{}", draw(&app));
+
+        let mut completed = alpha.clone();
+        completed.status = DownloadStatus::Completed;
+        completed.last_status_change = alpha.start_time + chrono::Duration::minutes(40);
+        app.receive_downloads(vec![completed]);
+        assert!(!draw(&app).contains("ETA"));
+
+        // The same model started again is expected to take as long.
+        alpha.start_time = Utc::now() - chrono::Duration::seconds(29 * 60 + 30);
+        app.receive_downloads(vec![alpha]);
+        assert!(draw(&app).ends_with(" ETA ~10m"), "{}", draw(&app));
+    }
+
+    #[test]
+    fn clicks_map_to_the_rows_tui_renders() {
+        let mut app = test_app();
+        app.set_downloads(
+            (0..20)
+                .map(|i| download(&format!("model{:02}", i), DownloadStatus::Downloading))
+                .collect(),
+        );
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(60, 12)).unwrap();
+        let mut offset = 0;
+
+        for selected in [15, 3, 4, 19] {
+            app.select_index(selected);
+            let snapshot = app.snapshot();
+            let mut rows_area = Rect::default();
+            terminal
+                .draw(|f| rows_area = ui(f, &snapshot, &mut offset))
+                .unwrap();
+
+            // The first row shows the model a click there would select.
+            let top = rows_area.y;
+            let index = clicked_row(rows_area, offset, 1, rows_area.x + 5, top).unwrap();
+            let buffer = terminal.backend().buffer();
+            let line: String = (rows_area.x..rows_area.right())
+                .map(|x| buffer.get(x, top).symbol.as_str())
+                .collect();
+            assert!(line.contains(&format!("model{:02}", index)), "{}", line);
+        }
+
+        let area = Rect::new(1, 3, 18, 3);
+        assert_eq!(clicked_row(area, 0, 1, 0, 3), None);
+        assert_eq!(clicked_row(area, 0, 1, 5, 2), None);
+        assert_eq!(clicked_row(area, 0, 1, 5, 6), None);
+        assert_eq!(clicked_row(area, 7, 1, 5, 4), Some(8));
+    }
+
+    #[test]
+    fn wide_terminals_flow_the_list_across_columns() {
+        let mut app = test_app();
+        app.set_downloads(
+            (0..30)
+                .map(|i| download(&format!("model{:02}", i), DownloadStatus::Downloading))
+                .collect(),
+        );
+        app.select_index(25);
+        let snapshot = app.snapshot();
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(170, 12)).unwrap();
+        let mut offset = 0;
+        let mut rows_area = Rect::default();
+        terminal
+            .draw(|f| rows_area = ui(f, &snapshot, &mut offset))
+            .unwrap();
+
+        let columns = list_columns(rows_area.width, snapshot.column_width);
+        assert_eq!(columns, 2);
+        // The selection's line is in view, and each line starts at an even index.
+        assert_eq!(offset % 2, 0);
+        assert!(offset <= 25 && 25 < offset + rows_area.height as usize * 2);
+        let buffer = terminal.backend().buffer();
+        let line: String = (rows_area.x..rows_area.right())
+            .map(|x| buffer.get(x, rows_area.y).symbol.as_str())
+            .collect();
+        let (left, right) = (format!("model{:02}", offset), format!("model{:02}", offset + 1));
+        assert!(line.find(&left).unwrap() < line.find(&right).unwrap(), "{}", line);
+
+        let right_column = rows_area.right() - 5;
+        let clicked = clicked_row(rows_area, offset, columns, right_column, rows_area.y + 1);
+        assert_eq!(clicked, Some(offset + 3));
+        assert_eq!(list_columns(100, 80), 1);
+        assert_eq!(list_columns(400, 0), 1);
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+// `$XDG_DATA_HOME/downloader-ctl/notes.json`, falling back to `~/.local/share`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(base.join("downloader-ctl").join("notes.json"))
+}
+
+// Reads the notes at `path`, keyed by model name. A missing file just means there are no
+// notes yet.
+pub fn load(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid notes file {}: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Could not read notes {}: {}", path.display(), e)),
+    }
+}
+
+// Writes every note to `path`, via a temporary file so a crash mid-write can't lose them.
+pub fn save(path: &Path, notes: &BTreeMap<String, String>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(notes)?)?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_round_trip_through_the_file() {
+        let dir = env::temp_dir().join(format!("downloader-ctl-notes-{}", std::process::id()));
+        let path = dir.join("notes.json");
+        assert!(load(&path).unwrap().is_empty());
+
+        let mut notes = BTreeMap::new();
+        notes.insert("alpha".to_string(), "known flaky".to_string());
+        save(&path, &notes).unwrap();
+        assert_eq!(load(&path).unwrap(), notes);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(load(&path).unwrap_err().starts_with("Invalid notes file"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
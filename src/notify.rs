@@ -0,0 +1,84 @@
+use std::{io, process::Stdio};
+
+use tokio::process::Command;
+
+use crate::DownloadStatus;
+
+// The kinds of status a download can move into that raise a desktop notification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotifyOn {
+    Error,
+    Offline,
+    Completed,
+}
+
+impl NotifyOn {
+    pub const ALL: [NotifyOn; 3] = [NotifyOn::Error, NotifyOn::Offline, NotifyOn::Completed];
+
+    pub fn parse(value: &str) -> Result<NotifyOn, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(NotifyOn::Error),
+            "offline" => Ok(NotifyOn::Offline),
+            "completed" => Ok(NotifyOn::Completed),
+            _ => Err(format!(
+                "Invalid notify_on entry '{}': expected error, offline or completed",
+                value
+            )),
+        }
+    }
+
+    // The kind of notification moving into `status` raises, if any.
+    pub fn for_status(status: &DownloadStatus) -> Option<NotifyOn> {
+        match status {
+            DownloadStatus::Error { .. } => Some(NotifyOn::Error),
+            DownloadStatus::Offline => Some(NotifyOn::Offline),
+            DownloadStatus::Completed => Some(NotifyOn::Completed),
+            _ => None,
+        }
+    }
+}
+
+// Shows a desktop notification through the platform's notifier: `osascript` on macOS and
+// `notify-send` elsewhere. The notifier runs in the background; only failing to start it is
+// reported.
+pub fn send(summary: &str, body: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(summary)
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=downloader-ctl", summary, body]);
+        command
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+}
+
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_notify_kinds() {
+        assert_eq!(NotifyOn::parse(" Offline ").unwrap(), NotifyOn::Offline);
+        assert!(NotifyOn::parse("paused").is_err());
+        assert_eq!(
+            NotifyOn::for_status(&DownloadStatus::Error { message: None }),
+            Some(NotifyOn::Error)
+        );
+        assert_eq!(NotifyOn::for_status(&DownloadStatus::Paused), None);
+    }
+}
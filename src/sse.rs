@@ -0,0 +1,234 @@
+use std::{sync::Arc, time::Instant};
+
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    StatusCode,
+};
+use tokio::sync::Mutex;
+
+use crate::App;
+
+// Why an event stream ended.
+pub enum StreamEnd {
+    // The server has no events endpoint; stop trying and rely on polling.
+    Unsupported,
+    // The connection failed or dropped; it's worth reconnecting later.
+    Disconnected,
+    // The app switched to another server, which should be connected to instead.
+    ServerChanged,
+    // Refresh was paused; the stream is reopened once it resumes.
+    Paused,
+}
+
+// Follows `GET {downloader_url}/events`, applying each pushed event to the app until the
+// stream ends or the app switches servers. The app lock is only taken while applying an
+// event, never while waiting.
+pub async fn stream_updates(app: &Arc<Mutex<App>>) -> StreamEnd {
+    // Registered before reading the URL, so a switch in between is never missed.
+    let server_changed = app.lock().await.server_changed.clone();
+    let changed = server_changed.notified();
+    tokio::pin!(changed);
+
+    let (client, server) = {
+        let app = app.lock().await;
+        (app.stream_client.clone(), app.client.base_url().to_string())
+    };
+
+    let request = client
+        .get(format!("{}/events", server))
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send();
+    let response = tokio::select! {
+        _ = &mut changed => return StreamEnd::ServerChanged,
+        response = request => response,
+    };
+    let mut response = match response {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response)
+            if response.status() == StatusCode::NOT_FOUND
+                || response.status() == StatusCode::NOT_IMPLEMENTED =>
+        {
+            return StreamEnd::Unsupported;
+        }
+        _ => return StreamEnd::Disconnected,
+    };
+
+    let mut parser = EventParser::default();
+    loop {
+        let chunk = tokio::select! {
+            _ = &mut changed => return StreamEnd::ServerChanged,
+            chunk = response.chunk() => chunk,
+        };
+        let Ok(Some(chunk)) = chunk else {
+            break;
+        };
+        let events = parser.push(&chunk);
+        let mut app = app.lock().await;
+        if app.client.base_url() != server {
+            return StreamEnd::ServerChanged;
+        }
+        if app.paused_refresh {
+            return StreamEnd::Paused;
+        }
+        // While the stream is open, even a keep-alive means the list is still current.
+        if events.is_empty() {
+            app.last_refresh = Some(Instant::now());
+            continue;
+        }
+        for data in events {
+            // A malformed event is skipped; the next poll or event will correct the list.
+            let _ = app.apply_event(&data);
+        }
+    }
+    StreamEnd::Disconnected
+}
+
+// Follows `GET {downloader_url}/downloads/{model}/logs` into the server log pane, which it
+// stops for once the pane is closed or moves to another model. Servers may send the log as
+// events, each data field holding one or more lines, or as plain text, streamed or not.
+pub async fn tail_logs(app: Arc<Mutex<App>>, model: String) {
+    let (client, url) = {
+        let app = app.lock().await;
+        let url = format!("{}/logs", app.client.download_url(&model));
+        (app.stream_client.clone(), url)
+    };
+
+    let response = client
+        .get(url)
+        .header(ACCEPT, "text/event-stream, text/plain")
+        .send()
+        .await;
+    let mut response = match response {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response)
+            if response.status() == StatusCode::NOT_FOUND
+                || response.status() == StatusCode::NOT_IMPLEMENTED =>
+        {
+            return end_log(&app, &model, "Logs not supported by server".to_string()).await;
+        }
+        Ok(response) => {
+            let reason = format!("Could not fetch logs: HTTP {}", response.status());
+            return end_log(&app, &model, reason).await;
+        }
+        Err(e) => return end_log(&app, &model, format!("Could not fetch logs: {}", e)).await,
+    };
+
+    let events = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    let mut parser = EventParser::default();
+    let mut partial = Vec::new();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => return end_log(&app, &model, format!("Log stream dropped: {}", e)).await,
+        };
+        let lines = if events {
+            let data = parser.push(&chunk);
+            data.iter()
+                .flat_map(|data| data.lines().map(str::to_string))
+                .collect()
+        } else {
+            complete_lines(&mut partial, &chunk)
+        };
+        let mut app = app.lock().await;
+        match app.server_log.as_mut().filter(|log| log.model == model) {
+            Some(log) => log.push(lines),
+            None => return,
+        }
+    }
+    // A plain-text log without a final newline still ends with a line.
+    if !partial.is_empty() {
+        let line = String::from_utf8_lossy(&partial).into_owned();
+        let mut app = app.lock().await;
+        if let Some(log) = app.server_log.as_mut().filter(|log| log.model == model) {
+            log.push(vec![line]);
+        }
+    }
+    end_log(&app, &model, "Log stream ended".to_string()).await
+}
+
+// Records why the stream for `model` stopped, if its pane is still open.
+async fn end_log(app: &Arc<Mutex<App>>, model: &str, reason: String) {
+    let mut app = app.lock().await;
+    if let Some(log) = app.server_log.as_mut().filter(|log| log.model == model) {
+        log.ended = Some(reason);
+    }
+}
+
+// Appends `chunk` to `buffer` and takes out every line it completes, leaving the unfinished
+// rest for the next chunk.
+fn complete_lines(buffer: &mut Vec<u8>, chunk: &[u8]) -> Vec<String> {
+    buffer.extend_from_slice(chunk);
+    let mut lines = Vec::new();
+    while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = buffer.drain(..=newline).collect();
+        let line = String::from_utf8_lossy(&line);
+        lines.push(line.trim_end_matches(['\n', '\r']).to_string());
+    }
+    lines
+}
+
+// Incremental parser for the `text/event-stream` format. Only `data:` fields matter here;
+// event names, ids and comments are ignored.
+#[derive(Default)]
+pub struct EventParser {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl EventParser {
+    // Feeds a chunk of the response body and returns the data of every event it completes.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data
+                    .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut parser = EventParser::default();
+        assert!(parser.push(b"event: update\r\ndata: {\"a\"").is_empty());
+        assert!(parser.push(b": 1}\r\n").is_empty());
+        assert_eq!(parser.push(b"\r\n"), vec!["{\"a\": 1}"]);
+    }
+
+    #[test]
+    fn joins_multi_line_data_and_skips_comments() {
+        let mut parser = EventParser::default();
+        let events = parser.push(b": keep-alive\n\ndata: one\ndata:two\n\ndata: three\n\n");
+        assert_eq!(events, vec!["one\ntwo", "three"]);
+    }
+
+    #[test]
+    fn splits_plain_text_logs_into_lines() {
+        let mut buffer = Vec::new();
+        assert_eq!(complete_lines(&mut buffer, b"first\r\nsec"), vec!["first"]);
+        assert_eq!(complete_lines(&mut buffer, b"ond\n\nthi"), vec!["second", ""]);
+        assert_eq!(buffer, b"thi");
+    }
+}
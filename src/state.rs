@@ -0,0 +1,85 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+use crate::{notes, SortKey, StatusCategory};
+
+// How the list was last viewed, saved on quit and restored at the next launch. A field that's
+// missing or unreadable, as in a file from an older or newer version, keeps its default.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    #[serde(deserialize_with = "lenient")]
+    pub sort_key: Option<SortKey>,
+    #[serde(deserialize_with = "lenient")]
+    pub sort_descending: bool,
+    #[serde(deserialize_with = "lenient")]
+    pub status_filter: Option<StatusCategory>,
+    #[serde(deserialize_with = "lenient")]
+    pub filter_query: String,
+}
+
+// Reads a field, falling back to its default rather than failing the whole file.
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(T::deserialize(value).unwrap_or_default())
+}
+
+// `state.json` beside the notes file.
+pub fn default_path() -> Option<PathBuf> {
+    Some(notes::default_path()?.with_file_name("state.json"))
+}
+
+// Reads the saved view at `path`. A missing file just means there's nothing to restore.
+pub fn load(path: &Path) -> Result<UiState, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid state file {}: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(UiState::default()),
+        Err(e) => Err(format!("Could not read state {}: {}", path.display(), e)),
+    }
+}
+
+// Writes the view to `path`, via a temporary file like the notes.
+pub fn save(path: &Path, state: &UiState) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(state)?)?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_and_missing_fields_keep_their_defaults() {
+        let state: UiState = serde_json::from_str(
+            r#"{"sort_key": "size", "sort_descending": true, "status_filter": "errored",
+                "layout": "tree"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            state,
+            UiState {
+                sort_key: None,
+                sort_descending: true,
+                status_filter: Some(StatusCategory::Errored),
+                filter_query: String::new(),
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<UiState>("{}").unwrap(),
+            UiState::default()
+        );
+    }
+}
@@ -0,0 +1,183 @@
+// A download's values that a row template can place, each written as `{name}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Model,
+    Status,
+    // Time since the last status change.
+    Elapsed,
+    // Time since the download started.
+    Age,
+    Retries,
+    // Percent complete, when the server reports sizes.
+    Progress,
+    // Transfer rate while downloading.
+    Rate,
+    // Estimated time left while downloading, from how long past downloads took.
+    Eta,
+    // A marker for downloads with a note.
+    Note,
+}
+
+impl Field {
+    pub const ALL: [Field; 9] = [
+        Field::Model,
+        Field::Status,
+        Field::Elapsed,
+        Field::Age,
+        Field::Retries,
+        Field::Progress,
+        Field::Rate,
+        Field::Eta,
+        Field::Note,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Model => "model",
+            Field::Status => "status",
+            Field::Elapsed => "elapsed",
+            Field::Age => "age",
+            Field::Retries => "retries",
+            Field::Progress => "progress",
+            Field::Rate => "rate",
+            Field::Eta => "eta",
+            Field::Note => "note",
+        }
+    }
+}
+
+// The durations the built-in row layout can show. They drift apart for a download that keeps
+// retrying, which is worth seeing, but narrow terminals may only have room for one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeColumn {
+    // Since the last status change.
+    LastChange,
+    // Since the download started.
+    Age,
+}
+
+impl TimeColumn {
+    pub const ALL: [TimeColumn; 2] = [TimeColumn::LastChange, TimeColumn::Age];
+
+    pub fn parse(value: &str) -> Result<TimeColumn, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "last_change" => Ok(TimeColumn::LastChange),
+            "age" => Ok(TimeColumn::Age),
+            _ => Err(format!(
+                "Invalid time_columns entry '{}': expected last_change or age",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+// A list row layout from the config, e.g. "{model} [{status}] {elapsed} retries={retries}".
+// `{{` and `}}` stand for literal braces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowTemplate {
+    segments: Vec<Segment>,
+}
+
+impl RowTemplate {
+    pub fn parse(template: &str) -> Result<RowTemplate, String> {
+        let invalid = |reason: String| format!("Invalid row template '{}': {}", template, reason);
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let (name, rest) = chars
+                        .as_str()
+                        .split_once('}')
+                        .ok_or_else(|| invalid("unclosed '{'".to_string()))?;
+                    let field = Field::ALL
+                        .into_iter()
+                        .find(|field| field.name() == name.trim())
+                        .ok_or_else(|| {
+                            let names: Vec<&str> = Field::ALL.iter().map(|f| f.name()).collect();
+                            invalid(format!(
+                                "unknown placeholder '{{{}}}', expected one of {}",
+                                name,
+                                names.join(", ")
+                            ))
+                        })?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(field));
+                    chars = rest.chars();
+                }
+                '}' => {
+                    return Err(invalid(
+                        "unmatched '}' (write '}}' for a brace)".to_string(),
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        if !segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Field(_)))
+        {
+            return Err(invalid("it has no placeholders".to_string()));
+        }
+        Ok(RowTemplate { segments })
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_placeholders_and_escaped_braces() {
+        let template = RowTemplate::parse("{model} [{ status }] {{{retries}}}").unwrap();
+        assert_eq!(
+            template.segments(),
+            [
+                Segment::Field(Field::Model),
+                Segment::Text(" [".to_string()),
+                Segment::Field(Field::Status),
+                Segment::Text("] {".to_string()),
+                Segment::Field(Field::Retries),
+                Segment::Text("}".to_string()),
+            ]
+        );
+
+        let error = RowTemplate::parse("{model} {size}").unwrap_err();
+        assert!(error.contains("unknown placeholder '{size}'"), "{}", error);
+        assert!(error.contains("model, status, elapsed"), "{}", error);
+        assert!(RowTemplate::parse("{model")
+            .unwrap_err()
+            .contains("unclosed"));
+        assert!(RowTemplate::parse("{model} }")
+            .unwrap_err()
+            .contains("unmatched"));
+        assert!(RowTemplate::parse("just text").is_err());
+
+        assert_eq!(TimeColumn::parse(" Age ").unwrap(), TimeColumn::Age);
+        assert!(TimeColumn::parse("uptime").is_err());
+    }
+}
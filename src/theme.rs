@@ -0,0 +1,166 @@
+use tui::style::{Color, Modifier, Style};
+
+use crate::{DownloadStatus, StatusCategory};
+
+// Every style the UI draws with, so a preset can restyle the whole interface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    // The selected row in the download list.
+    pub selection: Style,
+    pub error: Style,
+    pub warning: Style,
+    pub success: Style,
+    // Labels and model names.
+    pub header: Style,
+    // Secondary text such as timestamps.
+    pub muted: Style,
+    // Small indicators like the activity spinner and note marker.
+    pub accent: Style,
+    // Pauses the downloader applies for shows, set apart from manual ones.
+    pub show_pause: Style,
+    // Downloads waiting in the server's queue.
+    pub queued: Style,
+    pub border: Style,
+    // Full-width bars: `alert` for errors and disconnects, `notice` for other messages.
+    pub alert: Style,
+    pub notice: Style,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 3] = ["default", "high-contrast", "monochrome"];
+
+    pub fn named(name: &str) -> Result<Theme, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "default" => Ok(Theme::default()),
+            "high-contrast" => Ok(Theme::high_contrast()),
+            "monochrome" => Ok(Theme::monochrome()),
+            _ => Err(format!(
+                "Invalid theme '{}': expected {}",
+                name,
+                Theme::NAMES.join(", ")
+            )),
+        }
+    }
+
+    fn high_contrast() -> Theme {
+        let bold = |color| Style::default().fg(color).add_modifier(Modifier::BOLD);
+        Theme {
+            selection: Style::default()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            error: bold(Color::LightRed),
+            warning: bold(Color::LightYellow),
+            success: Style::default().fg(Color::LightGreen),
+            header: bold(Color::White),
+            muted: Style::default().fg(Color::White),
+            accent: bold(Color::LightCyan),
+            show_pause: bold(Color::LightMagenta),
+            queued: bold(Color::LightBlue),
+            border: Style::default().fg(Color::White),
+            alert: Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+            notice: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+
+    // No colors at all; severity is carried by bold, underline and reverse video instead.
+    fn monochrome() -> Theme {
+        let style = |modifier| Style::default().add_modifier(modifier);
+        Theme {
+            selection: style(Modifier::REVERSED | Modifier::BOLD),
+            error: style(Modifier::BOLD | Modifier::UNDERLINED),
+            warning: style(Modifier::UNDERLINED),
+            success: Style::default(),
+            header: style(Modifier::BOLD),
+            muted: style(Modifier::DIM),
+            accent: style(Modifier::BOLD),
+            show_pause: style(Modifier::ITALIC),
+            queued: style(Modifier::DIM | Modifier::ITALIC),
+            border: Style::default(),
+            alert: style(Modifier::REVERSED | Modifier::BOLD),
+            notice: style(Modifier::REVERSED),
+        }
+    }
+
+    // Styles status text by severity.
+    pub fn status(&self, status: &DownloadStatus) -> Style {
+        match status {
+            DownloadStatus::Completed | DownloadStatus::Downloading => self.success,
+            DownloadStatus::Retrying { .. } | DownloadStatus::Paused => self.warning,
+            DownloadStatus::PausedForExclusiveShow | DownloadStatus::PausedForTicketShow => {
+                self.show_pause
+            }
+            DownloadStatus::Error { .. } | DownloadStatus::Offline => self.error,
+            DownloadStatus::Initializing => self.muted,
+            DownloadStatus::Queued => self.queued,
+        }
+    }
+
+    // Matches the styles `status` uses for the statuses in each category.
+    pub fn category(&self, category: StatusCategory) -> Style {
+        match category {
+            StatusCategory::Active | StatusCategory::Completed => self.success,
+            StatusCategory::Paused => self.warning,
+            StatusCategory::Errored => self.error,
+            StatusCategory::Queued => self.queued,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            selection: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            error: Style::default().fg(Color::Red),
+            warning: Style::default().fg(Color::Yellow),
+            success: Style::default().fg(Color::Green),
+            header: Style::default().add_modifier(Modifier::BOLD),
+            muted: Style::default().fg(Color::Gray),
+            accent: Style::default().fg(Color::Cyan),
+            show_pause: Style::default().fg(Color::Magenta),
+            queued: Style::default().fg(Color::Blue),
+            border: Style::default().fg(Color::White),
+            alert: Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+            notice: Style::default().fg(Color::Black).bg(Color::Green),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monochrome_uses_no_colors() {
+        let theme = Theme::named("Monochrome").unwrap();
+        let styles = [
+            theme.selection,
+            theme.error,
+            theme.warning,
+            theme.success,
+            theme.header,
+            theme.muted,
+            theme.accent,
+            theme.show_pause,
+            theme.queued,
+            theme.border,
+            theme.alert,
+            theme.notice,
+        ];
+        assert!(styles.iter().all(|s| s.fg.is_none() && s.bg.is_none()));
+        assert_ne!(theme.error, theme.warning);
+        assert!(Theme::named("high-contrast").is_ok());
+        assert!(Theme::named("neon").is_err());
+    }
+}